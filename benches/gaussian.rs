@@ -0,0 +1,42 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use growth_simulator::types;
+
+/// The number of organisms sensing the resources in every iteration
+const ORGANISM_COUNT: usize = 100_000;
+
+/// Benchmarks evaluating a Gaussian at a single point per organism with and
+/// without allocating
+fn gaussian_single_point(c: &mut Criterion) {
+    let gaussian = types::Gaussian::new(
+        1.0,
+        types::Point::new(0.5, -0.5),
+        types::Matrix::new(&[[2.0, 0.3], [0.3, 1.0]]),
+    );
+    let offset = types::Point::new(0.0, 0.0);
+    let positions = (0..ORGANISM_COUNT)
+        .map(|id| {
+            let angle = id as f64 * 0.01;
+            return types::Point::new(angle.cos(), angle.sin()) * (id as f64 * 1e-4);
+        })
+        .collect::<Vec<types::Point>>();
+
+    c.bench_function("gaussian evaluate single point", |b| {
+        b.iter(|| {
+            return positions
+                .iter()
+                .map(|position| return gaussian.evaluate(&offset, &[*black_box(position)])[0])
+                .sum::<f64>();
+        });
+    });
+    c.bench_function("gaussian evaluate_single", |b| {
+        b.iter(|| {
+            return positions
+                .iter()
+                .map(|position| return gaussian.evaluate_single(&offset, black_box(position)))
+                .sum::<f64>();
+        });
+    });
+}
+
+criterion_group!(benches, gaussian_single_point);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use growth_simulator::{
+    map::{Map, MapBuilder, MapFormat, MapLayout, Source, SourceMap},
+    types,
+};
+use std::{f64::consts::PI, path::PathBuf};
+
+/// Creates a map large enough for the file format to matter
+fn create_map() -> Map {
+    let sources = SourceMap {
+        nutrients: vec![Source::Gaussian(types::Gaussian::new(
+            2.0 * PI * 4.0,
+            types::Point::new(0.0, 0.0),
+            types::Matrix::new(&[[4.0, 0.0], [0.0, 4.0]]),
+        ))],
+        energy: Vec::new(),
+        water: Vec::new(),
+    };
+
+    return MapBuilder::new(sources)
+        .with_layout(MapLayout::Finite(8, 8))
+        .build();
+}
+
+/// Benchmarks saving and loading a map in every format
+fn map_round_trip(c: &mut Criterion) {
+    let map = create_map();
+    let directory = std::env::temp_dir().join("growth_simulator_bench");
+
+    [
+        (MapFormat::Json, "map.json"),
+        (MapFormat::Binary, "map.bin"),
+    ]
+    .into_iter()
+    .for_each(|(format, name)| {
+        let path: PathBuf = directory.join(name);
+        c.bench_function(&format!("round trip {:?}", format), |b| {
+            b.iter(|| {
+                map.save(&path, format).expect("Unable to save the map");
+                return Map::load(&path).expect("Unable to load the map");
+            });
+        });
+    });
+}
+
+criterion_group!(benches, map_round_trip);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use growth_simulator::types;
+
+/// The number of points transformed in every iteration, about the number of
+/// tile centers of a large map
+const POINT_COUNT: usize = 100_000;
+
+/// Benchmarks transforming a list of points into a new vector, in place and
+/// into a preallocated destination
+fn transform_list(c: &mut Criterion) {
+    // A rigid transform keeps the points bounded when applied repeatedly in place
+    let transform =
+        types::Transform2D::from_matrix(types::Matrix::rotation(0.3), types::Point::new(1.0, -2.0));
+    let points = (0..POINT_COUNT)
+        .map(|id| {
+            let angle = id as f64 * 0.01;
+            return types::Point::new(angle.cos(), angle.sin()) * (id as f64 * 1e-3);
+        })
+        .collect::<Vec<types::Point>>();
+
+    c.bench_function("transform collect", |b| {
+        b.iter(|| {
+            return black_box(&points)
+                .iter()
+                .map(|point| return &transform * point)
+                .collect::<Vec<types::Point>>();
+        });
+    });
+    c.bench_function("transform apply_list_inplace", |b| {
+        let mut points = points.clone();
+        b.iter(|| {
+            transform.apply_list_inplace(black_box(&mut points));
+        });
+    });
+    c.bench_function("transform apply_list_into", |b| {
+        let mut dst = vec![types::Point::new(0.0, 0.0); POINT_COUNT];
+        b.iter(|| {
+            transform.apply_list_into(black_box(&points), &mut dst);
+        });
+    });
+}
+
+criterion_group!(benches, transform_list);
+criterion_main!(benches);
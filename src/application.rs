@@ -1,13 +1,26 @@
-use crate::{camera, graphics, map, render};
+use crate::{
+    camera,
+    constants::{
+        CAMERA_FOLLOW_DURATION, CAMERA_FOLLOW_SIZE, CAMERA_HOME_DURATION, FRAMERATE_PRESETS,
+        INV_SQRT_3, STATISTICS_HISTORY_LENGTH, TITLE_MESSAGE_DURATION,
+    },
+    graphics, map, render, simulation, types,
+};
+#[cfg(feature = "notify")]
+use std::sync::mpsc;
 use std::{
+    fs, io,
+    path::{Path, PathBuf},
     sync::Arc,
     time::{Duration, Instant},
 };
+use thiserror::Error;
 use winit::{
     application::ApplicationHandler,
-    dpi::PhysicalSize,
-    event::{DeviceId, KeyEvent, StartCause, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{DeviceId, ElementState, KeyEvent, MouseButton, StartCause, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    keyboard::{KeyCode, PhysicalKey},
     window::Window,
 };
 
@@ -31,14 +44,23 @@ pub fn run(main_loop: &mut MainLoop) {
     }
 }
 
+/// Retrieves the path of the file where the camera is stored between runs,
+/// returns None if there is no configuration directory
+fn get_camera_path() -> Option<PathBuf> {
+    return dirs::config_dir().map(|dir| {
+        return dir.join("growth_evolution").join("camera.json");
+    });
+}
+
 /// Controls the main game loop of the application
 pub struct MainLoop {
     /// The name of the application
     name: String,
     /// The framerate of the application
     framerate: f64,
-    /// The size of the application window
-    size: PhysicalSize<u32>,
+    /// The size of the application window when it is opened, afterwards the
+    /// render state holds the current size
+    initial_size: PhysicalSize<u32>,
     /// The settings for rendering
     graphics_settings: graphics::Settings,
     /// The currently opened window of the application
@@ -47,6 +69,45 @@ pub struct MainLoop {
     map: map::Map,
     /// The camera for controlling what is displayed
     camera: camera::HexCamera,
+    /// The number of simulation ticks which have run
+    tick: u64,
+    /// The simulated time in seconds
+    sim_time: f64,
+    /// The parameters for the diffusion of resources
+    diffusion: simulation::DiffusionParams,
+    /// True if the simulation is frozen, the camera and rendering keep running
+    paused: bool,
+    /// All organisms living on the map
+    organisms: simulation::OrganismPool,
+    /// The statistics of the latest ticks
+    statistics: simulation::StatisticsBuffer,
+    /// The csv file the statistics are appended to every tick, None if the
+    /// statistics should not be logged
+    stats_log: Option<simulation::StatisticsLog>,
+    /// The last known position of the cursor in pixels
+    cursor_position: PhysicalPosition<f64>,
+    /// The position in pixels where the current drag selection started, None
+    /// if no selection is in progress
+    selection_start: Option<PhysicalPosition<f64>>,
+    /// The last finished selection rectangle in normalized device coordinates
+    selection: Option<types::View>,
+    /// The number of frames and the path prefix of a screenshot series to
+    /// record once the window is opened, None if nothing should be recorded
+    recording: Option<(u32, String)>,
+    /// A message shown as the window title instead of the normal title
+    /// together with the time it was set, it is removed after a few seconds
+    title_message: Option<(String, Instant)>,
+    /// True if the time the gpu spends rendering should be shown in the title
+    profile_gpu: bool,
+    /// The wgsl file to load the tile shader from, None if the built in
+    /// shader is used
+    shader_path: Option<PathBuf>,
+    /// Reports changes to the shader file, None if no shader file is watched
+    #[cfg(feature = "notify")]
+    shader_watcher: Option<ShaderWatcher>,
+    /// The id of the organism the camera is tracking, None if the camera is
+    /// controlled by the keys
+    followed_organism: Option<u64>,
 }
 
 impl MainLoop {
@@ -57,6 +118,8 @@ impl MainLoop {
     /// name: The name of the application shown on the window
     ///
     /// size: The size of the window in pixels
+    ///
+    /// camera: The camera to use if no camera was saved by a previous run
     pub fn new(
         name: String,
         framerate: f64,
@@ -65,15 +128,371 @@ impl MainLoop {
         map: map::Map,
         camera: camera::HexCamera,
     ) -> Self {
+        // Load the camera from the previous run
+        let camera = match get_camera_path().map(|path| camera::HexCamera::load_from_file(&path)) {
+            Some(Ok(value)) => value,
+            Some(Err(camera::CameraFileError::Io(error)))
+                if error.kind() == io::ErrorKind::NotFound =>
+            {
+                camera
+            }
+            Some(Err(error)) => {
+                eprintln!("Unable to load camera: {:?}", error);
+                camera
+            }
+            None => camera,
+        };
+
         return Self {
             name,
             framerate,
-            size,
+            initial_size: size,
             graphics_settings,
             window: None,
             map,
             camera,
+            tick: 0,
+            sim_time: 0.0,
+            diffusion: simulation::DiffusionParams::default(),
+            paused: false,
+            organisms: simulation::OrganismPool::new(),
+            statistics: simulation::StatisticsBuffer::new(STATISTICS_HISTORY_LENGTH),
+            stats_log: None,
+            cursor_position: PhysicalPosition::new(0.0, 0.0),
+            selection_start: None,
+            selection: None,
+            recording: None,
+            title_message: None,
+            profile_gpu: false,
+            shader_path: None,
+            #[cfg(feature = "notify")]
+            shader_watcher: None,
+            followed_organism: None,
+        };
+    }
+
+    /// Retrieves the number of simulation ticks which have run
+    pub fn get_tick(&self) -> u64 {
+        return self.tick;
+    }
+
+    /// Retrieves the simulated time in seconds
+    pub fn get_sim_time(&self) -> f64 {
+        return self.sim_time;
+    }
+
+    /// Retrieves the parameters for the diffusion of resources
+    pub fn get_diffusion(&self) -> &simulation::DiffusionParams {
+        return &self.diffusion;
+    }
+
+    /// Retrieves the parameters for the diffusion of resources as mutable
+    pub fn get_diffusion_mut(&mut self) -> &mut simulation::DiffusionParams {
+        return &mut self.diffusion;
+    }
+
+    /// Retrieves the camera as mutable
+    pub fn get_camera_mut(&mut self) -> &mut camera::HexCamera {
+        return &mut self.camera;
+    }
+
+    /// Freezes the simulation, the camera can still be moved
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Continues the simulation after it has been paused
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Checks if the simulation is paused
+    pub fn is_paused(&self) -> bool {
+        return self.paused;
+    }
+
+    /// Retrieves the target framerate
+    pub fn get_target_fps(&self) -> f64 {
+        return self.framerate;
+    }
+
+    /// Changes the target framerate, it takes effect from the next iteration
+    /// of the game loop and the camera is updated to keep its speed
+    ///
+    /// # Parameters
+    ///
+    /// fps: The new target framerate
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if the framerate is not positive
+    pub fn set_target_fps(&mut self, fps: f64) {
+        if cfg!(debug_assertions) && fps <= 0.0 {
+            panic!("The framerate must be positive: {:?}", fps);
+        }
+
+        self.framerate = fps;
+        self.camera.set_framerate(fps);
+    }
+
+    /// Retrieves a mutable reference to the organisms living on the map
+    pub fn get_organisms_mut(&mut self) -> &mut simulation::OrganismPool {
+        return &mut self.organisms;
+    }
+
+    /// Retrieves the statistics of the latest ticks
+    pub fn get_statistics(&self) -> &simulation::StatisticsBuffer {
+        return &self.statistics;
+    }
+
+    /// Sets the csv file the statistics are appended to every tick, the file
+    /// is overwritten, if it cannot be created the error is reported and the
+    /// statistics are not logged
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the file, None to disable logging
+    pub fn set_stats_log(&mut self, path: Option<PathBuf>) {
+        self.stats_log = path.and_then(|path| {
+            return match simulation::StatisticsLog::create(&path) {
+                Ok(log) => Some(log),
+                Err(error) => {
+                    eprintln!("Unable to create statistics log {:?}: {:?}", path, error);
+                    None
+                }
+            };
+        });
+    }
+
+    /// Sets a wgsl file to load the tile shader from, it is loaded whenever
+    /// the graphics state is created and with the notify feature it is
+    /// reloaded in the next game loop iteration after the file changes
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the wgsl file, None to keep the current shader
+    pub fn set_shader_watch(&mut self, path: Option<PathBuf>) {
+        #[cfg(feature = "notify")]
+        {
+            self.shader_watcher = path.as_ref().and_then(|path| {
+                return match ShaderWatcher::new(path) {
+                    Ok(value) => Some(value),
+                    Err(error) => {
+                        eprintln!("Unable to watch shader: {:?}", error);
+                        None
+                    }
+                };
+            });
+        }
+
+        self.shader_path = path;
+        if self.window.is_some() {
+            self.load_shader();
+        }
+    }
+
+    /// Retrieves the last finished selection rectangle in normalized device
+    /// coordinates, None if nothing has been selected
+    pub fn get_selection(&self) -> Option<&types::View> {
+        return self.selection.as_ref();
+    }
+
+    /// Saves the current frame as a png image
+    ///
+    /// # Parameters
+    ///
+    /// path: The path to save the image to
+    ///
+    /// # Errors
+    ///
+    /// See ScreenshotError for a description of the different errors which may occur
+    pub fn screenshot(&self, path: &Path) -> Result<(), ScreenshotError> {
+        let window = self.window.as_ref().ok_or(ScreenshotError::NoWindow)?;
+        let image = window.graphics_state.capture(
+            window.get_render_state(),
+            &self.camera.get_transform(),
+            self.organisms.get_organisms(),
+        )?;
+        image.save_with_format(path, image::ImageFormat::Png)?;
+
+        return Ok(());
+    }
+
+    /// Saves the given resource of all tiles as a grayscale png image with
+    /// one pixel per tile
+    ///
+    /// # Parameters
+    ///
+    /// path: The path to save the image to
+    ///
+    /// resource: The resource to export
+    ///
+    /// # Errors
+    ///
+    /// See ScreenshotError for a description of the different errors which may occur
+    pub fn export_resource_texture(
+        &self,
+        path: &Path,
+        resource: map::ResourceType,
+    ) -> Result<(), ScreenshotError> {
+        let window = self.window.as_ref().ok_or(ScreenshotError::NoWindow)?;
+        let png = window.graphics_state.export_resource_texture(
+            window.get_render_state(),
+            &self.map,
+            resource,
+        )?;
+        fs::write(path, png)?;
+
+        return Ok(());
+    }
+
+    /// Advances the simulation by n ticks and saves a screenshot after each
+    /// tick as path_prefix_NNNNN.png where NNNNN is the zero padded frame
+    /// number starting from 0
+    ///
+    /// # Parameters
+    ///
+    /// n: The number of ticks to advance and frames to save
+    ///
+    /// path_prefix: The start of the path of each image
+    ///
+    /// # Errors
+    ///
+    /// See ScreenshotError for a description of the different errors which may occur
+    pub fn export_screenshot_series(
+        &mut self,
+        n: u32,
+        path_prefix: &str,
+    ) -> Result<(), ScreenshotError> {
+        if self.window.is_none() {
+            return Err(ScreenshotError::NoWindow);
+        }
+
+        return (0..n).try_for_each(|frame| {
+            self.step_simulation();
+            self.upload_visible_chunks();
+            self.window
+                .as_mut()
+                .expect("Should not happen")
+                .graphics_state
+                .resolve_dirty();
+
+            return self.screenshot(&PathBuf::from(format!("{}_{:05}.png", path_prefix, frame)));
+        });
+    }
+
+    /// Sets a screenshot series to record with export_screenshot_series once
+    /// the window has been opened
+    ///
+    /// # Parameters
+    ///
+    /// recording: The number of frames and the path prefix, None to not record
+    pub fn set_recording(&mut self, recording: Option<(u32, String)>) {
+        self.recording = recording;
+    }
+
+    /// Advances the simulation by a single tick
+    fn step_simulation(&mut self) {
+        self.tick += 1;
+        self.sim_time += 1.0 / self.framerate;
+
+        if self.diffusion.enabled {
+            self.map
+                .step_diffusion(self.diffusion.dt, self.diffusion.rate);
+        }
+
+        self.organisms.step_all(&mut self.map, 1.0 / self.framerate);
+        let statistics = self.organisms.collect_statistics(self.tick);
+        self.statistics.push(statistics);
+
+        // Log the statistics and stop logging if the file cannot be written
+        if let Some(log) = &mut self.stats_log {
+            if let Err(error) = log.append(&statistics) {
+                eprintln!("Unable to write statistics: {:?}", error);
+                self.stats_log = None;
+            }
+        }
+
+        // Keep the shown gradient up to date with the simulation
+        if let Some(window) = &mut self.window {
+            window.graphics_state.update_gradient(&mut self.map);
+        }
+    }
+
+    /// Restarts the simulation at tick 0 without any organisms or statistics,
+    /// the map is not changed
+    fn reset_simulation(&mut self) {
+        self.tick = 0;
+        self.sim_time = 0.0;
+        self.organisms = simulation::OrganismPool::new();
+        self.statistics = simulation::StatisticsBuffer::new(STATISTICS_HISTORY_LENGTH);
+        self.followed_organism = None;
+    }
+
+    /// Moves the camera towards the followed organism, stops following it if
+    /// it has died
+    fn follow_organism(&mut self) {
+        let id = match self.followed_organism {
+            Some(value) => value,
+            None => return,
         };
+
+        let organism = match self
+            .organisms
+            .get_organisms()
+            .iter()
+            .find(|organism| return organism.id == id)
+        {
+            Some(value) => value,
+            None => {
+                self.followed_organism = None;
+                return;
+            }
+        };
+
+        let view = types::View::new(
+            &organism.position,
+            &types::Size::new(CAMERA_FOLLOW_SIZE, CAMERA_FOLLOW_SIZE),
+        );
+        self.camera
+            .focus_on_bbox_animated(&view, CAMERA_FOLLOW_DURATION);
+    }
+
+    /// Finds the id of the organism closest to the center of the screen,
+    /// returns None if there are no organisms
+    fn find_organism_near_center(&self) -> Option<u64> {
+        let center = self.camera.screen_to_world(&types::Point::new(0.0, 0.0));
+
+        return self
+            .organisms
+            .get_organisms()
+            .iter()
+            .min_by(|a, b| {
+                return a
+                    .position
+                    .distance_to(&center)
+                    .total_cmp(&b.position.distance_to(&center));
+            })
+            .map(|organism| return organism.id);
+    }
+
+    /// Uploads all modified chunks which are visible to the gpu, the rest
+    /// are uploaded once they become visible
+    fn upload_visible_chunks(&mut self) {
+        let window = self.window.as_mut().expect("Should not happen");
+
+        self.camera
+            .get_visible_chunks(&self.map)
+            .iter()
+            .for_each(|(chunk_type, index)| {
+                let chunk = self.map.get_data_mut().get_chunk_mut(chunk_type, *index);
+                if !chunk.is_modified() {
+                    return;
+                }
+
+                window.graphics_state.update_chunk(chunk);
+                chunk.resolved();
+            });
     }
 
     /// Handles the initialization of the game loop
@@ -87,9 +506,6 @@ impl MainLoop {
         event_loop.set_control_flow(ControlFlow::WaitUntil(
             Instant::now() + Duration::from_micros(wait_time),
         ));
-
-        // Set the size of the camera
-        self.camera.resize(&self.size);
     }
 
     /// Handles the iteration of the game loop
@@ -109,19 +525,143 @@ impl MainLoop {
         }
         event_loop.set_control_flow(ControlFlow::WaitUntil(new_time));
 
-        // Get the window and id
-        let window = match &self.window {
-            Some(window) => window,
-            None => {
-                eprintln!("Cannot process game loop because window is not initialized");
-                return;
-            }
-        };
+        // Advance the simulation unless it is paused
+        if !self.paused {
+            self.step_simulation();
+        }
+
+        // Make sure the window exists
+        if self.window.is_none() {
+            eprintln!("Cannot process game loop because window is not initialized");
+            return;
+        }
+
+        self.upload_visible_chunks();
+        self.follow_organism();
+        let mut redraw = self.reload_changed_shader();
+        let window = self.window.as_mut().expect("Should not happen");
+        redraw |= window.graphics_state.resolve_dirty();
 
         // Update the camera
-        if self.camera.update_transform() {
+        redraw |= self.camera.update_transform();
+
+        if redraw {
             window.get_window().request_redraw();
         }
+
+        self.update_title();
+    }
+
+    /// Reloads the watched shader if the file has changed since the last
+    /// game loop iteration, returns true if the shader was reloaded
+    #[cfg(feature = "notify")]
+    fn reload_changed_shader(&mut self) -> bool {
+        let changed = match &self.shader_watcher {
+            Some(watcher) => watcher.has_changed(),
+            None => return false,
+        };
+        if !changed {
+            return false;
+        }
+
+        return self.load_shader();
+    }
+
+    /// The shader file is only loaded when the graphics state is created
+    /// without the notify feature so it never changes
+    #[cfg(not(feature = "notify"))]
+    fn reload_changed_shader(&mut self) -> bool {
+        return false;
+    }
+
+    /// Loads the tile shader from the shader file if one is set, the current
+    /// shader is kept if it fails, returns true if the shader was loaded
+    fn load_shader(&mut self) -> bool {
+        let path = match &self.shader_path {
+            Some(value) => value,
+            None => return false,
+        };
+
+        let window = self.window.as_mut().expect("Should not happen");
+        return match window
+            .graphics_state
+            .reload_shader(&window.render_state, path)
+        {
+            Ok(()) => {
+                println!("Shader reloaded");
+                true
+            }
+            Err(error) => {
+                eprintln!("Shader compile error: {}", error);
+                false
+            }
+        };
+    }
+
+    /// Updates the title of the window to show the name, framerate,
+    /// simulation time, diffusion parameters, whether the simulation is
+    /// paused and the gpu timings and visible tiles if profiling, a temporary
+    /// message is shown instead while there is one
+    fn update_title(&mut self) {
+        // Expire the temporary message
+        if let Some((_, start_time)) = &self.title_message {
+            if start_time.elapsed().as_secs_f64() >= TITLE_MESSAGE_DURATION {
+                self.title_message = None;
+            }
+        }
+
+        // Read back the gpu timings of the latest frame and count the visible tiles
+        let profile = match (&self.window, self.profile_gpu) {
+            (Some(window), true) => {
+                let gpu_timings = window
+                    .graphics_state
+                    .read_gpu_timings(window.get_render_state())
+                    .map(|timings| {
+                        return match timings.chunk_lines_us {
+                            Some(chunk_lines_us) => format!(
+                                " | gpu tiles={}us lines={}us",
+                                timings.tiles_us, chunk_lines_us
+                            ),
+                            None => format!(" | gpu tiles={}us", timings.tiles_us),
+                        };
+                    })
+                    .unwrap_or_else(|| return " | gpu timings unavailable".to_string());
+                let centers = self
+                    .map
+                    .iter_tiles()
+                    .map(|(_, center)| return center)
+                    .collect::<Vec<types::Point>>();
+                let visible = types::View::from_transform(&self.camera.get_transform())
+                    .tile_overlap_count(&centers, INV_SQRT_3);
+
+                format!("{} visible tiles={}", gpu_timings, visible)
+            }
+            _ => String::new(),
+        };
+
+        let title = match &self.title_message {
+            Some((message, _)) => message.clone(),
+            None => format!(
+                "{} | {:.0} fps | tick={} t={:.2}s | diffusion rate={:.3} dt={:.4}{}{}{}",
+                self.name,
+                self.framerate,
+                self.tick,
+                self.sim_time,
+                self.diffusion.rate,
+                self.diffusion.dt,
+                if self.diffusion.enabled {
+                    ""
+                } else {
+                    " (diffusion off)"
+                },
+                if self.paused { " [PAUSED]" } else { "" },
+                profile,
+            ),
+        };
+
+        if let Some(window) = &self.window {
+            window.set_title(&title);
+        }
     }
 
     /// Handles a window event for the main window
@@ -139,13 +679,18 @@ impl MainLoop {
         // Find the correct event
         match event {
             WindowEvent::CloseRequested => self.main_window_close_request(event_loop),
-            WindowEvent::RedrawRequested => self.main_window_redraw_requested(),
+            WindowEvent::RedrawRequested => self.main_window_redraw_requested(event_loop),
             WindowEvent::Resized(size) => self.main_window_resized(size),
             WindowEvent::KeyboardInput {
                 device_id,
                 event,
                 is_synthetic,
             } => self.main_window_keyboard_input(device_id, event, is_synthetic),
+            WindowEvent::CursorMoved { position, .. } => self.main_window_cursor_moved(position),
+            WindowEvent::MouseInput { state, button, .. } => {
+                self.main_window_mouse_input(state, button)
+            }
+            WindowEvent::DroppedFile(path) => self.main_window_dropped_file(&path),
             _ => (),
         }
     }
@@ -155,22 +700,60 @@ impl MainLoop {
     /// # Parameters
     ///
     /// event_loop: The event loop currently running
-    fn main_window_close_request(&self, event_loop: &ActiveEventLoop) {
+    fn main_window_close_request(&mut self, event_loop: &ActiveEventLoop) {
+        // Save the camera for the next run
+        if let Some(path) = get_camera_path() {
+            if let Err(error) = self.camera.save_to_file(&path) {
+                eprintln!("Unable to save camera: {:?}", error);
+            }
+        }
+
+        // Write the remaining statistics
+        if let Some(log) = &mut self.stats_log {
+            if let Err(error) = log.flush() {
+                eprintln!("Unable to write statistics: {:?}", error);
+            }
+        }
+
         // Stop the application
         event_loop.exit();
     }
 
-    /// Run when the main window must be redrawn
-    fn main_window_redraw_requested(&self) {
+    /// Run when the main window must be redrawn, if the surface has been lost
+    /// or is outdated it is reconfigured and the texture is requested again,
+    /// if the gpu is out of memory the application is closed
+    ///
+    /// # Parameters
+    ///
+    /// event_loop: The event loop currently running
+    fn main_window_redraw_requested(&mut self, event_loop: &ActiveEventLoop) {
         let window = self.window.as_ref().expect("Should not happen");
 
         // Get the current view
-        let output_texture = match window
-            .get_render_state()
-            .get_surface()
-            .get_current_texture()
-        {
+        let surface = match window.get_render_state().get_surface() {
+            Some(value) => value,
+            None => {
+                eprintln!("The window has no surface to draw on");
+                return;
+            }
+        };
+        let output_texture = match surface.get_current_texture() {
             Ok(value) => value,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                window.get_render_state().reconfigure();
+                match surface.get_current_texture() {
+                    Ok(value) => value,
+                    Err(error) => {
+                        eprintln!("Unable to get texture after reconfiguring: {:?}", error);
+                        return;
+                    }
+                }
+            }
+            Err(wgpu::SurfaceError::OutOfMemory) => {
+                eprintln!("The gpu is out of memory, closing the application");
+                self.main_window_close_request(event_loop);
+                return;
+            }
             Err(error) => {
                 eprintln!("Unable to get texture: {:?}", error);
                 return;
@@ -180,11 +763,16 @@ impl MainLoop {
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
-        // Draw the map
-        window.graphics_state.render(
+        // Draw the map and the organisms on top
+        let transform = self.camera.get_transform();
+        window
+            .graphics_state
+            .render(window.get_render_state(), &view, &transform);
+        window.graphics_state.render_organism_positions(
             window.get_render_state(),
             &view,
-            &self.camera.get_transform(),
+            &transform,
+            self.organisms.get_organisms(),
         );
 
         // Show to screen
@@ -197,18 +785,136 @@ impl MainLoop {
     ///
     /// size: The new size of the window
     fn main_window_resized(&mut self, size: PhysicalSize<u32>) {
-        // Set the new size
-        self.size = size;
-
         // Update the window
+        let window = self.window.as_mut().expect("Should not happen");
+        window.render_state.on_resize(size);
+        window.graphics_state.on_resize(&window.render_state);
+
+        // Update the camera
+        self.camera.resize(&window.render_state.get_surface_size());
+    }
+
+    /// Run when the cursor has moved, updates the selection rectangle if a
+    /// selection is in progress
+    ///
+    /// # Parameters
+    ///
+    /// position: The new position of the cursor in pixels
+    fn main_window_cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+        self.cursor_position = position;
+
+        if self.selection_start.is_some() {
+            let rect = self.get_selection_rect();
+            let window = self.window.as_mut().expect("Should not happen");
+            window
+                .graphics_state
+                .set_selection_rect(&window.render_state, Some(rect));
+            window.get_window().request_redraw();
+        }
+    }
+
+    /// Handles mouse button input, the right button is used for drag selection
+    ///
+    /// # Parameters
+    ///
+    /// state: Whether the button was pressed or released
+    ///
+    /// button: The button which changed state
+    fn main_window_mouse_input(&mut self, state: ElementState, button: MouseButton) {
+        if button != MouseButton::Right {
+            return;
+        }
+
+        match state {
+            ElementState::Pressed => {
+                // Start a new selection at the cursor
+                self.selection_start = Some(self.cursor_position);
+            }
+            ElementState::Released => {
+                if self.selection_start.is_none() {
+                    return;
+                }
+
+                // Finalize the selection and hide the rectangle
+                self.selection = Some(self.get_selection_rect());
+                self.selection_start = None;
+                let window = self.window.as_mut().expect("Should not happen");
+                window
+                    .graphics_state
+                    .set_selection_rect(&window.render_state, None);
+                window.get_window().request_redraw();
+            }
+        }
+    }
+
+    /// Run when a file is dropped onto the main window, attempts to load it
+    /// as a map and replaces the current map with it, if it fails the error
+    /// is shown in the title for a few seconds
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the dropped file
+    fn main_window_dropped_file(&mut self, path: &Path) {
+        let map = match map::Map::load(path) {
+            Ok((value, _)) => value,
+            Err(error) => {
+                eprintln!("Unable to load map: {:?}", error);
+                self.title_message =
+                    Some((format!("Unable to load map: {}", error), Instant::now()));
+                return;
+            }
+        };
+
+        // Replace the map and the simulation running on it
+        self.map = map;
+        self.reset_simulation();
+        self.title_message = None;
+
+        // Rebuild all gpu data for the new map
+        let window = self.window.as_mut().expect("Should not happen");
+        window.graphics_state =
+            graphics::State::new(&window.render_state, self.graphics_settings, &self.map);
+
+        // The new state uses the built in shader so the shader file must be loaded again
+        self.load_shader();
+
+        // Show the entire map
+        if let Some(view) = self.map.get_bounding_box() {
+            self.camera.fit_to_view(&view);
+        }
+
         self.window
-            .as_mut()
+            .as_ref()
             .expect("Should not happen")
-            .get_render_state_mut()
-            .resize(size);
+            .get_window()
+            .request_redraw();
+    }
 
-        // Update the camera
-        self.camera.resize(&size);
+    /// Calculates the rectangle in normalized device coordinates spanned by
+    /// the start of the selection and the cursor
+    fn get_selection_rect(&self) -> types::View {
+        let start = self.selection_start.unwrap_or(self.cursor_position);
+        let size = self
+            .window
+            .as_ref()
+            .expect("Should not happen")
+            .get_render_state()
+            .get_surface_size();
+
+        // Convert from pixels with y pointing down to normalized device coordinates
+        let to_ndc = |position: PhysicalPosition<f64>| {
+            return types::Point::new(
+                2.0 * position.x / size.width as f64 - 1.0,
+                1.0 - 2.0 * position.y / size.height as f64,
+            );
+        };
+        let start = to_ndc(start);
+        let end = to_ndc(self.cursor_position);
+
+        return types::View::new(
+            &((start + end) * 0.5),
+            &types::Size::new((end.x - start.x).abs(), (end.y - start.y).abs()),
+        );
     }
 
     /// Handles any keyboard input like camera movement
@@ -226,10 +932,149 @@ impl MainLoop {
         event: KeyEvent,
         _is_synthetic: bool,
     ) {
+        // Show the entire map when going home
+        if event.physical_key == PhysicalKey::Code(KeyCode::KeyH) {
+            if event.state == ElementState::Pressed {
+                self.followed_organism = None;
+                match self.map.get_bounding_box() {
+                    Some(view) => self
+                        .camera
+                        .focus_on_bbox_animated(&view, CAMERA_HOME_DURATION),
+                    None => self.camera.reset_home(),
+                }
+            }
+            return;
+        }
+
         // Handle camera events, stop if input was captured
         if self.camera.apply_key(&event) {
             return;
         }
+
+        // Only handle key presses for the remaining keys
+        if event.state != ElementState::Pressed {
+            return;
+        }
+
+        // Toggle the debug overlay, axes and gpu profiling, reset the simulation, take screenshots,
+        // export resources, pause with P and change the diffusion parameters where T toggles the
+        // diffusion
+        match event.physical_key {
+            PhysicalKey::Code(KeyCode::F12) => {
+                let path = PathBuf::from(format!(
+                    "screenshot_{}.png",
+                    chrono::Local::now().format("%Y%m%d_%H%M%S")
+                ));
+                if let Err(error) = self.screenshot(&path) {
+                    eprintln!("Unable to take screenshot: {:?}", error);
+                }
+                return;
+            }
+            PhysicalKey::Code(KeyCode::F4) => {
+                self.profile_gpu = !self.profile_gpu;
+                return;
+            }
+            PhysicalKey::Code(KeyCode::F5) => {
+                self.map.reset_to_base();
+                self.reset_simulation();
+                let window = self.window.as_mut().expect("Should not happen");
+                window.graphics_state.update_gradient(&mut self.map);
+                window.get_window().request_redraw();
+                return;
+            }
+            PhysicalKey::Code(KeyCode::F11) => {
+                let time = chrono::Local::now().format("%Y%m%d_%H%M%S");
+                [
+                    (map::ResourceType::Nutrients, "nutrients"),
+                    (map::ResourceType::Energy, "energy"),
+                    (map::ResourceType::Water, "water"),
+                ]
+                .into_iter()
+                .for_each(|(resource, name)| {
+                    let path = PathBuf::from(format!("resource_{}_{}.png", name, time));
+                    if let Err(error) = self.export_resource_texture(&path, resource) {
+                        eprintln!("Unable to export resource texture: {:?}", error);
+                    }
+                });
+                return;
+            }
+            PhysicalKey::Code(KeyCode::F2) => {
+                let window = self.window.as_mut().expect("Should not happen");
+                let show = !window.graphics_state.get_show_debug_indices();
+                window.graphics_state.set_show_debug_indices(show);
+                window.get_window().request_redraw();
+                return;
+            }
+            PhysicalKey::Code(KeyCode::F3) => {
+                let window = self.window.as_mut().expect("Should not happen");
+                let show = !window.graphics_state.get_show_axes();
+                window.graphics_state.set_show_axes(show);
+                window.get_window().request_redraw();
+                return;
+            }
+            PhysicalKey::Code(KeyCode::Equal | KeyCode::NumpadAdd) => {
+                self.diffusion.rate *= 1.1;
+                return;
+            }
+            PhysicalKey::Code(KeyCode::Minus | KeyCode::NumpadSubtract) => {
+                self.diffusion.rate *= 0.9;
+                return;
+            }
+            PhysicalKey::Code(KeyCode::KeyT) => {
+                self.diffusion.enabled = !self.diffusion.enabled;
+                return;
+            }
+            PhysicalKey::Code(KeyCode::KeyF) => {
+                // Cycle to the next framerate preset
+                let fps = FRAMERATE_PRESETS
+                    .iter()
+                    .copied()
+                    .find(|&fps| return fps > self.framerate)
+                    .unwrap_or(FRAMERATE_PRESETS[0]);
+                self.set_target_fps(fps);
+                return;
+            }
+            PhysicalKey::Code(KeyCode::KeyO) => {
+                // Toggle following the organism closest to the center of the screen
+                self.followed_organism = match self.followed_organism {
+                    Some(_) => None,
+                    None => self.find_organism_near_center(),
+                };
+                return;
+            }
+            PhysicalKey::Code(KeyCode::KeyP) => {
+                if self.paused {
+                    self.resume();
+                } else {
+                    self.pause();
+                }
+                return;
+            }
+            _ => (),
+        }
+
+        // Change the color mode
+        let color_mode = match event.physical_key {
+            PhysicalKey::Code(KeyCode::Digit1) => graphics::TileColorMode::Nutrients,
+            PhysicalKey::Code(KeyCode::Digit2) => graphics::TileColorMode::Energy,
+            PhysicalKey::Code(KeyCode::Digit3) => graphics::TileColorMode::Water,
+            PhysicalKey::Code(KeyCode::Digit4) => graphics::TileColorMode::Composite,
+            PhysicalKey::Code(KeyCode::Digit5) => graphics::TileColorMode::Biome,
+            PhysicalKey::Code(KeyCode::Digit6) => {
+                graphics::TileColorMode::GradientMagnitude(map::ResourceType::Nutrients)
+            }
+            PhysicalKey::Code(KeyCode::Digit7) => {
+                graphics::TileColorMode::GradientMagnitude(map::ResourceType::Energy)
+            }
+            PhysicalKey::Code(KeyCode::Digit8) => {
+                graphics::TileColorMode::GradientMagnitude(map::ResourceType::Water)
+            }
+            _ => return,
+        };
+        let window = self.window.as_mut().expect("Should not happen");
+        window
+            .graphics_state
+            .set_color_mode(&mut self.map, color_mode);
     }
 }
 
@@ -238,7 +1083,7 @@ impl ApplicationHandler for MainLoop {
         // Open a new window
         let window_attributes = Window::default_attributes()
             .with_title(&self.name)
-            .with_inner_size(self.size);
+            .with_inner_size(self.initial_size);
 
         let window = match event_loop.create_window(window_attributes) {
             Ok(value) => value,
@@ -250,14 +1095,32 @@ impl ApplicationHandler for MainLoop {
         };
 
         // Add a render state
-        self.window = match pollster::block_on(RenderedWindow::new(window, self.graphics_settings))
-        {
+        self.window = match pollster::block_on(RenderedWindow::new(
+            window,
+            self.graphics_settings,
+            &self.map,
+        )) {
             Ok(value) => Some(value),
             Err(error) => {
                 eprintln!("Unable to add render state: {:?}", error);
                 event_loop.exit();
                 return;
             }
+        };
+
+        // Set the size of the camera
+        if let Some(window) = &self.window {
+            self.camera.resize(&window.render_state.get_surface_size());
+        }
+
+        // Replace the built in shader before anything is rendered
+        self.load_shader();
+
+        // Record the requested screenshot series
+        if let Some((n, path_prefix)) = self.recording.take() {
+            if let Err(error) = self.export_screenshot_series(n, &path_prefix) {
+                eprintln!("Unable to record screenshot series: {:?}", error);
+            }
         }
     }
 
@@ -320,13 +1183,18 @@ impl RenderedWindow {
     /// # Parameters
     ///
     /// window: The window to add a render state to
+    ///
+    /// graphics_settings: The settings for rendering
+    ///
+    /// map: The map to render
     pub async fn new(
         window: Window,
         graphics_settings: graphics::Settings,
+        map: &map::Map,
     ) -> Result<Self, render::NewRenderStateError> {
         let window = Arc::new(window);
         let render_state = render::RenderState::new(&window).await?;
-        let graphics_state = graphics::State::new(&render_state, graphics_settings);
+        let graphics_state = graphics::State::new(&render_state, graphics_settings, map);
 
         return Ok(Self {
             window,
@@ -349,4 +1217,102 @@ impl RenderedWindow {
     pub fn get_window(&self) -> &Window {
         return &self.window;
     }
+
+    /// Sets the title of the window
+    ///
+    /// # Parameters
+    ///
+    /// title: The new title
+    pub fn set_title(&self, title: &str) {
+        self.window.set_title(title);
+    }
+}
+
+/// Watches a shader file for changes, the directory of the file is watched
+/// such that editors replacing the file are also noticed
+#[cfg(feature = "notify")]
+struct ShaderWatcher {
+    /// The watcher sending the events, it stops watching when dropped
+    _watcher: notify::RecommendedWatcher,
+    /// The name of the shader file
+    file_name: std::ffi::OsString,
+    /// Receives the events for all files in the directory of the shader
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+}
+
+#[cfg(feature = "notify")]
+impl ShaderWatcher {
+    /// Starts watching the shader file
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the wgsl file
+    ///
+    /// # Errors
+    ///
+    /// See notify::Error for a description of the different errors which may occur
+    fn new(path: &Path) -> notify::Result<Self> {
+        use notify::Watcher;
+
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| return notify::Error::path_not_found().add_path(path.to_path_buf()))?
+            .to_os_string();
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(directory, notify::RecursiveMode::NonRecursive)?;
+
+        return Ok(Self {
+            _watcher: watcher,
+            file_name,
+            events,
+        });
+    }
+
+    /// Receives all events since the last call and returns true if any of
+    /// them created or modified the shader file
+    fn has_changed(&self) -> bool {
+        return self
+            .events
+            .try_iter()
+            .filter(|event| {
+                return match event {
+                    Ok(event) => {
+                        (event.kind.is_create() || event.kind.is_modify())
+                            && event
+                                .paths
+                                .iter()
+                                .any(|path| return path.file_name() == Some(&self.file_name))
+                    }
+                    Err(error) => {
+                        eprintln!("Unable to watch shader: {:?}", error);
+                        false
+                    }
+                };
+            })
+            .count()
+            > 0;
+    }
+}
+
+/// The error types for when taking a screenshot
+#[derive(Error, Debug)]
+pub enum ScreenshotError {
+    /// There is no window to take a screenshot of
+    #[error("The window is not initialized")]
+    NoWindow,
+    /// The frame could not be read from the gpu
+    #[error("Unable to capture the frame: {:?}", .0)]
+    Capture(#[from] graphics::CaptureError),
+    /// The image could not be saved
+    #[error("Unable to save the image: {:?}", .0)]
+    Save(#[from] image::ImageError),
+    /// The encoded image could not be written
+    #[error("Unable to write the image: {:?}", .0)]
+    Write(#[from] io::Error),
 }
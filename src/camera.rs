@@ -1,9 +1,20 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::Path,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
 use winit::{
     event::{ElementState, KeyEvent},
     keyboard::{KeyCode, PhysicalKey},
 };
 
-use super::{constants::SQRT_3, types};
+use super::{
+    constants::{CAMERA_ANIMATION_DURATION, MAX_ZOOM, MIN_ZOOM, SQRT_3},
+    map, types,
+};
 
 /// Describes a how the camera is moving
 #[derive(Clone, Copy, Debug)]
@@ -24,6 +35,15 @@ pub struct HexCamera {
     transform_aspect: types::Transform2D,
     /// The transform to apply to the current transform every frame
     transform_update: types::Transform2D,
+    /// The transform the camera started with which it can be reset to
+    transform_home: types::Transform2D,
+    /// The running animation towards a new transform, None if the camera is
+    /// controlled by the keys
+    animation: Option<CameraAnimation>,
+    /// The smallest zoom the keys can reach
+    zoom_min: f64,
+    /// The largest zoom the keys can reach
+    zoom_max: f64,
 }
 
 impl HexCamera {
@@ -50,9 +70,53 @@ impl HexCamera {
             transform: *transform,
             transform_aspect: Self::size_to_aspect(size),
             transform_update: types::Transform2D::identity(),
+            transform_home: *transform,
+            animation: None,
+            zoom_min: MIN_ZOOM,
+            zoom_max: MAX_ZOOM,
         }
     }
 
+    /// Loads a camera from a file written by save_to_file, the camera is
+    /// given a size of 1x1 so it must be resized before use
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the file to load
+    pub fn load_from_file(path: &Path) -> Result<Self, CameraFileError> {
+        let data = fs::read_to_string(path)?;
+        let state: HexCameraState = serde_json::from_str(&data)?;
+
+        return Ok(Self::new(
+            &state.settings,
+            &state.transform,
+            &winit::dpi::PhysicalSize {
+                width: 1,
+                height: 1,
+            },
+        ));
+    }
+
+    /// Saves the settings and current transform of the camera to a file,
+    /// any missing directories are created
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the file to save to
+    pub fn save_to_file(&self, path: &Path) -> Result<(), CameraFileError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let state = HexCameraState {
+            settings: self.settings,
+            transform: self.transform,
+        };
+        fs::write(path, serde_json::to_string_pretty(&state)?)?;
+
+        return Ok(());
+    }
+
     /// Retrieves a reference to the settings
     pub fn get_settings(&self) -> &HexCameraSettings {
         return &self.settings;
@@ -63,6 +127,17 @@ impl HexCamera {
         return HexCameraSettingsMut { camera: self };
     }
 
+    /// Changes the framerate the transform is updated with such that the
+    /// speed of the camera stays the same
+    ///
+    /// # Parameters
+    ///
+    /// framerate: The new framerate
+    pub fn set_framerate(&mut self, framerate: f64) {
+        self.settings.framerate = framerate;
+        self.reload_transform();
+    }
+
     /// Attempts to use a key press from a key event, if the key press is used,
     /// it returns true, if it is ignored, it returns false
     ///
@@ -120,6 +195,97 @@ impl HexCamera {
         &self.transform_aspect * self.transform
     }
 
+    /// Converts a point from world coordinates to normalized device
+    /// coordinates where (-1, -1) is the bottom left and (1, 1) is the top
+    /// right of the screen
+    ///
+    /// # Parameters
+    ///
+    /// p: The point in world coordinates
+    pub fn world_to_screen(&self, p: &types::Point) -> types::Point {
+        return self.get_transform() * p;
+    }
+
+    /// Converts a point from normalized device coordinates where (-1, -1) is
+    /// the bottom left and (1, 1) is the top right of the screen to world
+    /// coordinates
+    ///
+    /// # Parameters
+    ///
+    /// p: The point in normalized device coordinates
+    pub fn screen_to_world(&self, p: &types::Point) -> types::Point {
+        return self.get_transform().inv() * p;
+    }
+
+    /// Retrieves the type and index of all chunks of the map which are at
+    /// least partially visible on the screen
+    ///
+    /// # Parameters
+    ///
+    /// map: The map to find the visible chunks for
+    pub fn get_visible_chunks(&self, map: &map::Map) -> Vec<(map::ChunkType, usize)> {
+        // Get the corners of the screen in world coordinates
+        let corners = [
+            types::Point::new(-1.0, -1.0),
+            types::Point::new(1.0, -1.0),
+            types::Point::new(1.0, 1.0),
+            types::Point::new(-1.0, 1.0),
+        ]
+        .map(|corner| self.screen_to_world(&corner));
+
+        // Get the bounding box of the screen
+        let screen = types::View::from_points(&corners).expect("Should not happen");
+        let half_size = types::Point::new(screen.get_size().w, screen.get_size().h) * 0.5;
+        let min = *screen.get_center() - half_size;
+        let max = *screen.get_center() + half_size;
+
+        // Get the range of chunk indices to check
+        let chunk_corners = corners.map(|corner| map.coordinate_to_chunk(&corner));
+        let min_index = chunk_corners.iter().fold(chunk_corners[0], |prev, next| {
+            types::Index::new(prev.x.min(next.x) - 1, prev.y.min(next.y) - 1)
+        });
+        let max_index = chunk_corners.iter().fold(chunk_corners[0], |prev, next| {
+            types::Index::new(prev.x.max(next.x) + 1, prev.y.max(next.y) + 1)
+        });
+
+        // Find all chunks overlapping the screen
+        let mut visible = HashSet::new();
+        (min_index.y..max_index.y + 1).for_each(|y| {
+            (min_index.x..max_index.x + 1).for_each(|x| {
+                let chunk_index = types::Index::new(x, y);
+                let center = map.chunk_to_coordinate(&chunk_index);
+
+                map::ChunkType::all().iter().for_each(|chunk_type| {
+                    // Quickly reject chunks whose bounding circle is off the screen
+                    let (circle_center, radius) = map
+                        .get_geometry()
+                        .world_bounding_circle(chunk_type, &center);
+                    if circle_center.clamp(&min, &max).distance_to(&circle_center) > radius {
+                        return;
+                    }
+
+                    // Check the bounding box of the stored chunk
+                    let index = match map.get_data().get_index(chunk_type, chunk_index) {
+                        Some(value) => value,
+                        None => return,
+                    };
+                    if !map
+                        .get_data()
+                        .get_chunk(chunk_type, index)
+                        .bounding_box_world(map.get_geometry(), &center)
+                        .intersects(&screen)
+                    {
+                        return;
+                    }
+
+                    visible.insert((*chunk_type, index));
+                });
+            });
+        });
+
+        return visible.into_iter().collect();
+    }
+
     /// Sets a new transform
     ///
     /// # Parameters
@@ -129,16 +295,174 @@ impl HexCamera {
         self.transform = *transform;
     }
 
-    /// Update the transform using the current input, should be run once per frame
+    /// Retrieves the zoom of the camera, this is the factor the area of
+    /// the world is scaled by before the aspect ratio is applied
+    pub fn get_zoom_factor(&self) -> f64 {
+        return self.transform.center_transform.det().abs().sqrt();
+    }
+
+    /// Sets the zoom of the camera while keeping the rotation and the point
+    /// at the center of the screen, the zoom limits are not applied
+    ///
+    /// # Parameters
+    ///
+    /// zoom: The new zoom factor
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if zoom is not positive
+    pub fn set_zoom_factor(&mut self, zoom: f64) {
+        if cfg!(debug_assertions) && zoom <= 0.0 {
+            panic!("The zoom must be positive: {:?}", zoom);
+        }
+
+        let current = self.get_zoom_factor();
+        if current > 0.0 {
+            let correction = zoom / current;
+            self.transform = types::Transform2D::scale(&types::Point::new(correction, correction))
+                * self.transform;
+        }
+    }
+
+    /// Sets the range the zoom is kept within when zooming with the keys
+    ///
+    /// # Parameters
+    ///
+    /// min: The smallest allowed zoom
+    ///
+    /// max: The largest allowed zoom
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if min is not positive or is larger than max
+    pub fn set_zoom_limits(&mut self, min: f64, max: f64) {
+        if cfg!(debug_assertions) && (min <= 0.0 || min > max) {
+            panic!("Invalid zoom limits: {:?} to {:?}", min, max);
+        }
+
+        self.zoom_min = min;
+        self.zoom_max = max;
+    }
+
+    /// Moves the camera such that the center of the given tile is at the
+    /// center of the screen while keeping the zoom and rotation
+    ///
+    /// # Parameters
+    ///
+    /// index: The tile index of the tile to center on
+    pub fn snap_to_tile(&mut self, index: &types::Index) {
+        self.transform.center = map::tile_to_coordinate(index);
+    }
+
+    /// Starts a smooth transition from the current transform to a new
+    /// transform, the keys are ignored until the transition has finished
+    ///
+    /// # Parameters
+    ///
+    /// target: The transform to end at
+    ///
+    /// duration_secs: The number of seconds the transition should take, a
+    /// duration which is not positive or not finite moves the camera instantly
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if the duration is not finite
+    pub fn set_transform_animated(&mut self, target: types::Transform2D, duration_secs: f64) {
+        if cfg!(debug_assertions) && !duration_secs.is_finite() {
+            panic!("The duration must be finite: {:?}", duration_secs);
+        }
+
+        let duration = if duration_secs.is_finite() {
+            Duration::try_from_secs_f64(duration_secs.max(0.0)).unwrap_or(Duration::MAX)
+        } else {
+            Duration::ZERO
+        };
+        self.animation = Some(CameraAnimation {
+            start: self.transform,
+            target,
+            start_time: Instant::now(),
+            duration,
+        });
+    }
+
+    /// Smoothly moves the camera back to the transform it started with
+    pub fn reset_home(&mut self) {
+        self.set_transform_animated(self.transform_home, CAMERA_ANIMATION_DURATION);
+    }
+
+    /// Smoothly moves the camera such that the given view fills the screen,
+    /// the rotation is reset and the zoom is kept within the zoom limits
+    ///
+    /// # Parameters
+    ///
+    /// view: The view in world coordinates to show
+    pub fn fit_to_view(&mut self, view: &types::View) {
+        self.focus_on_bbox_animated(view, CAMERA_ANIMATION_DURATION);
+    }
+
+    /// Starts a transition to the transform where the bounding box fills the
+    /// current window, the rotation is reset and the zoom is kept within the
+    /// zoom limits, calling it every frame with a short duration makes the
+    /// camera smoothly track a moving bounding box
+    ///
+    /// # Parameters
+    ///
+    /// bbox: The bounding box in world coordinates to show
+    ///
+    /// duration_secs: The number of seconds the transition should take
+    pub fn focus_on_bbox_animated(&mut self, bbox: &types::View, duration_secs: f64) {
+        // The aspect transform scales x by height / width
+        let aspect = self.transform_aspect.center_transform.values[0][0];
+        let size = bbox.get_size();
+        let zoom = (2.0 / size.h)
+            .min(2.0 / (aspect * size.w))
+            .clamp(self.zoom_min, self.zoom_max);
+
+        let target =
+            types::Transform2D::from_matrix(types::Matrix::scale(zoom, zoom), *bbox.get_center());
+        self.set_transform_animated(target, duration_secs);
+    }
+
+    /// Update the transform using the current input or the running
+    /// animation, should be run once per frame
     ///
     /// Returns true if the transform has updated
     pub fn update_transform(&mut self) -> bool {
+        if let Some(animation) = self.animation {
+            // Find how far the animation has progressed
+            let t = if animation.duration.is_zero() {
+                1.0
+            } else {
+                animation.start_time.elapsed().as_secs_f64() / animation.duration.as_secs_f64()
+            };
+
+            // Finish the animation and return control to the keys
+            if t >= 1.0 {
+                self.transform = animation.target;
+                self.animation = None;
+                return true;
+            }
+
+            // Interpolate using a smooth step
+            self.transform = animation.start.lerp_eased(&animation.target, t, |t| {
+                return t * t * (3.0 - 2.0 * t);
+            });
+            return true;
+        }
+
         if !self.active {
             return false;
         }
 
         self.transform = self.transform_update * self.transform;
 
+        // Keep the zoom within the limits
+        let zoom = self.get_zoom_factor();
+        let clamped_zoom = zoom.clamp(self.zoom_min, self.zoom_max);
+        if zoom > 0.0 && clamped_zoom != zoom {
+            self.set_zoom_factor(clamped_zoom);
+        }
+
         return true;
     }
 
@@ -207,6 +531,39 @@ impl HexCamera {
     }
 }
 
+/// A smooth transition of the camera between two transforms
+#[derive(Clone, Copy, Debug)]
+struct CameraAnimation {
+    /// The transform at the start of the animation
+    start: types::Transform2D,
+    /// The transform at the end of the animation
+    target: types::Transform2D,
+    /// The time the animation started
+    start_time: Instant,
+    /// The total time the animation takes
+    duration: Duration,
+}
+
+/// The part of the camera which is saved to a file
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct HexCameraState {
+    /// All the settings
+    settings: HexCameraSettings,
+    /// The current transform
+    transform: types::Transform2D,
+}
+
+/// The error types for when saving or loading a camera
+#[derive(Error, Debug)]
+pub enum CameraFileError {
+    /// The file could not be read or written
+    #[error("Unable to access the camera file: {:?}", .0)]
+    Io(#[from] io::Error),
+    /// The file content could not be converted
+    #[error("Unable to convert the camera state: {:?}", .0)]
+    Json(#[from] serde_json::Error),
+}
+
 const KEY_DIRECTION_HEX: [types::Point; 6] = [
     types::Point { x: 1.0, y: 0.0 },
     types::Point {
@@ -255,7 +612,7 @@ impl<'map> Drop for HexCameraSettingsMut<'map> {
 }
 
 /// All settings for a camera
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct HexCameraSettings {
     /// The speed of movement
     pub speed_move: f64,
@@ -318,3 +675,142 @@ impl HexCameraSettings {
         return self;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The tolerance used when comparing floating point results
+    const EPS: f64 = 1e-9;
+
+    /// Creates a camera at the origin without any zoom
+    fn test_camera() -> HexCamera {
+        return HexCamera::new(
+            &HexCameraSettings::default(),
+            &types::Transform2D::identity(),
+            &winit::dpi::PhysicalSize {
+                width: 1,
+                height: 1,
+            },
+        );
+    }
+
+    #[test]
+    fn zoom_in_never_exceeds_max_zoom() {
+        let mut camera = test_camera();
+        camera.active_zoom[0] = true;
+        camera.reload_transform();
+
+        (0..2000).for_each(|_| {
+            camera.update_transform();
+            assert!(camera.get_zoom_factor() <= MAX_ZOOM + EPS);
+        });
+        assert!((camera.get_zoom_factor() - MAX_ZOOM).abs() < EPS);
+    }
+
+    #[test]
+    fn zoom_out_never_goes_below_min_zoom() {
+        let mut camera = test_camera();
+        camera.active_zoom[1] = true;
+        camera.reload_transform();
+
+        (0..2000).for_each(|_| {
+            camera.update_transform();
+            assert!(camera.get_zoom_factor() >= MIN_ZOOM - EPS);
+        });
+        assert!((camera.get_zoom_factor() - MIN_ZOOM).abs() < EPS);
+    }
+
+    #[test]
+    fn set_zoom_factor_keeps_rotation_and_center() {
+        let mut camera = test_camera();
+        let transform = types::Transform2D::from_matrix(
+            types::Matrix::rotation(0.7) * 1.8,
+            types::Point::new(3.0, -2.0),
+        );
+        camera.set_transform(&transform);
+
+        // Setting the current zoom changes nothing
+        camera.set_zoom_factor(camera.get_zoom_factor());
+        assert!(camera.transform.approx_eq(&transform, EPS));
+
+        // The world point at the center of the screen and the rotation are kept
+        camera.set_zoom_factor(0.25);
+        assert!((camera.get_zoom_factor() - 0.25).abs() < EPS);
+        let center = types::Point::new(0.0, 0.0);
+        assert!((&camera.transform.inv() * &center).approx_eq(&(&transform.inv() * &center), EPS));
+        let expected = types::Matrix::rotation(0.7) * 0.25;
+        assert!(camera.transform.center_transform.approx_eq(&expected, EPS));
+    }
+
+    #[test]
+    fn world_and_screen_coordinates_with_known_transform() {
+        // A window twice as wide as high scales x by 0.5, the transform
+        // rotates by a quarter turn and zooms by 2 around (1, 0)
+        let mut camera = HexCamera::new(
+            &HexCameraSettings::default(),
+            &types::Transform2D::from_matrix(
+                types::Matrix::rotation(std::f64::consts::FRAC_PI_2) * 2.0,
+                types::Point::new(1.0, 0.0),
+            ),
+            &winit::dpi::PhysicalSize {
+                width: 200,
+                height: 100,
+            },
+        );
+        [
+            (types::Point::new(1.0, 0.0), types::Point::new(0.0, 0.0)),
+            (types::Point::new(2.0, 0.0), types::Point::new(0.0, 2.0)),
+            (types::Point::new(1.0, 0.5), types::Point::new(-0.5, 0.0)),
+            (types::Point::new(0.5, 1.0), types::Point::new(-1.0, -1.0)),
+            (types::Point::new(1.5, -1.0), types::Point::new(1.0, 1.0)),
+        ]
+        .iter()
+        .for_each(|(world, screen)| {
+            assert!(camera.world_to_screen(world).approx_eq(screen, EPS));
+            assert!(camera.screen_to_world(screen).approx_eq(world, EPS));
+        });
+
+        // The two conversions are inverse of each other after resizing
+        camera.resize(&winit::dpi::PhysicalSize {
+            width: 300,
+            height: 400,
+        });
+        [
+            types::Point::new(0.3, -7.0),
+            types::Point::new(-2.5, 4.0),
+            types::Point::new(1e3, 1e-3),
+        ]
+        .iter()
+        .for_each(|point| {
+            let world = camera.screen_to_world(&camera.world_to_screen(point));
+            assert!(world.approx_eq(point, EPS));
+            let screen = camera.world_to_screen(&camera.screen_to_world(point));
+            assert!(screen.approx_eq(point, EPS));
+        });
+    }
+
+    #[test]
+    fn focus_on_bbox_animated_ends_at_the_fitted_view() {
+        let bbox = types::View::new(&types::Point::new(3.0, -1.0), &types::Size::new(4.0, 2.0));
+        let mut camera = test_camera();
+        camera.set_transform(&types::Transform2D::from_matrix(
+            types::Matrix::rotation(0.4) * 0.5,
+            types::Point::new(-2.0, 5.0),
+        ));
+
+        // The target of fitting the view without a custom duration
+        let mut fitted = camera;
+        fitted.fit_to_view(&bbox);
+        let expected = fitted.animation.expect("Should not happen").target;
+
+        let duration = 0.05;
+        camera.focus_on_bbox_animated(&bbox, duration);
+        let start = Instant::now();
+        while camera.animation.is_some() {
+            assert!(camera.update_transform());
+            assert!(start.elapsed().as_secs_f64() < 100.0 * duration);
+        }
+        assert!(camera.transform.approx_eq(&expected, EPS));
+    }
+}
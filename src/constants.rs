@@ -1,4 +1,34 @@
-pub const INV_SQRT_3: f64 = 0.5773502691896257645091487805019574556476017512701268760186023264;
 pub const SQRT_3: f64 = 1.73205080756887729352744634150587236694280525381038062805580697945193301690;
+pub const INV_SQRT_3: f64 = 1.0 / SQRT_3;
 pub const CHUNK_SIZE: usize = 4;
 pub const FRAMERATE: f64 = 60.0;
+pub const MAP_HISTORY_LENGTH: usize = 100;
+pub const RESOURCE_RELAXATION_RATE: f64 = 0.1;
+pub const ORGANISM_UPKEEP: f64 = 0.05;
+pub const ORGANISM_MOVEMENT_COST: f64 = 0.02;
+pub const CAMERA_ANIMATION_DURATION: f64 = 0.3;
+pub const CAMERA_HOME_DURATION: f64 = 0.5;
+pub const CAMERA_FOLLOW_DURATION: f64 = 0.05;
+pub const CAMERA_FOLLOW_SIZE: f64 = 10.0;
+pub const MIN_ZOOM: f64 = 0.01;
+pub const MAX_ZOOM: f64 = 10.0;
+pub const STATISTICS_HISTORY_LENGTH: usize = 1000;
+pub const FRAMERATE_PRESETS: [f64; 4] = [15.0, 30.0, 60.0, 120.0];
+pub const TITLE_MESSAGE_DURATION: f64 = 3.0;
+pub const TERRAIN_SLOPE_FACTOR: f64 = 0.5;
+
+// Validate the constants at compile time
+const _: () = assert!(SQRT_3 * SQRT_3 - 3.0 < 1e-12 && 3.0 - SQRT_3 * SQRT_3 < 1e-12);
+const _: () = assert!(SQRT_3 * INV_SQRT_3 - 1.0 < 1e-12 && 1.0 - SQRT_3 * INV_SQRT_3 < 1e-12);
+const _: () = assert!(CHUNK_SIZE >= 2);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constants_are_correct() {
+        assert!((SQRT_3 * SQRT_3 - 3.0).abs() < 1e-12);
+        assert!((SQRT_3 * INV_SQRT_3 - 1.0).abs() < 1e-12);
+    }
+}
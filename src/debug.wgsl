@@ -0,0 +1,46 @@
+// Uniforms
+// The transform to apply to each vertex
+@group(0) @binding(0)
+var<uniform> transform: mat2x2<f32>;
+
+// The center of the coordinate system which is subtracted before transforming
+@group(0) @binding(3)
+var<uniform> offset: vec2<f32>;
+
+// The glyph atlas
+@group(1) @binding(0)
+var glyph_texture: texture_2d<f32>;
+
+// The sampler for the glyph atlas
+@group(1) @binding(1)
+var glyph_sampler: sampler;
+
+// Type definitions
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+// Vertex shader
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) tex_coords: vec2<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    var pos = transform * (position - offset);
+    out.clip_position = vec4<f32>(pos, 0.0, 1.0);
+    out.tex_coords = tex_coords;
+    return out;
+}
+
+// Fragment shader
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    if textureSample(glyph_texture, glyph_sampler, in.tex_coords).r < 0.5 {
+        discard;
+    }
+    return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+}
@@ -1,4 +1,16 @@
-use crate::{constants::INV_SQRT_3, render, types};
+use crate::{
+    constants::{INV_SQRT_3, SQRT_3},
+    map, render, simulation, types,
+};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fs, io,
+    ops::Range,
+    path::Path,
+    sync::mpsc,
+};
+use thiserror::Error;
 use wgpu::util::DeviceExt;
 
 /// All settings for rendering
@@ -6,8 +18,237 @@ use wgpu::util::DeviceExt;
 pub struct Settings {
     /// The color of the background
     pub color_background: wgpu::Color,
-    /// The color of the edges
-    pub color_edge: wgpu::Color,
+    /// Which edges to draw and their colors
+    pub grid_lines: GridLines,
+    /// The number of samples per pixel used for anti-aliasing, 1 disables it
+    pub sample_count: u32,
+    /// True if the tile index of each tile should be shown on top of it
+    pub show_debug_indices: bool,
+    /// The factor to scale each hexagon by around its center at a zoom of 1, values below 1 leave a gap between tiles which stays constant on the screen at any zoom
+    pub tile_scale: f64,
+    /// The width of the border of each hexagon as a fraction of the distance from its center to its edge
+    pub hex_border_width: f64,
+    /// True if the x and y axes of the world should be shown
+    pub show_axes: bool,
+    /// The length of each axis in world coordinates measured from the origin
+    pub axes_length: f64,
+}
+
+impl Settings {
+    /// Changes the tile scale and returns the updated object
+    ///
+    /// # Parameters
+    ///
+    /// factor: The factor to scale each hexagon by around its center at a zoom of 1
+    pub fn with_tile_scale(mut self, factor: f64) -> Self {
+        self.tile_scale = factor;
+        return self;
+    }
+
+    /// Changes the border width of the hexagons and returns the updated object
+    ///
+    /// # Parameters
+    ///
+    /// width_fraction: The width of the border as a fraction of the distance from the center to the edge
+    pub fn with_hex_border_width(mut self, width_fraction: f64) -> Self {
+        self.hex_border_width = width_fraction;
+        return self;
+    }
+
+    /// Retrieves the border width to draw with, it is 0 if the tile edges are hidden
+    fn get_visible_border_width(&self) -> f64 {
+        return if self.grid_lines.show_tile_edges {
+            self.hex_border_width
+        } else {
+            0.0
+        };
+    }
+}
+
+/// The settings for drawing the edges of the tiles and the boundaries of the chunks
+#[derive(Clone, Copy, Debug)]
+pub struct GridLines {
+    /// True if the edges of all tiles should be drawn
+    pub show_tile_edges: bool,
+    /// True if the boundaries between chunks should be drawn
+    pub show_chunk_edges: bool,
+    /// The color of the tile edges
+    pub tile_edge_color: wgpu::Color,
+    /// The color of the chunk boundaries
+    pub chunk_edge_color: wgpu::Color,
+}
+
+/// The different ways the resources of a tile can be shown as a color
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileColorMode {
+    /// Shows the nutrients using the colormap of the nutrients
+    Nutrients,
+    /// Shows the energy using the colormap of the energy
+    Energy,
+    /// Shows the water using the colormap of the water
+    Water,
+    /// Shows the nutrients, energy and water in the red, green and blue channels
+    Composite,
+    /// Shows the dominant resource as the hue, how dominant it is as the
+    /// saturation and the amount as the value
+    Biome,
+    /// Shows the magnitude of the gradient of the given resource relative to
+    /// the largest magnitude on the map using the viridis colormap
+    GradientMagnitude(map::ResourceType),
+}
+
+/// A mapping from values between 0 and 1 to colors by linearly interpolating
+/// between evenly spaced control points
+#[derive(Clone, Debug, PartialEq)]
+pub enum Colormap {
+    /// The perceptually uniform viridis colormap from dark blue to yellow
+    Viridis,
+    /// The perceptually uniform plasma colormap from dark blue to yellow
+    Plasma,
+    /// A linear colormap from black to white
+    Greyscale,
+    /// A colormap with the given control points, it is created with Colormap::custom
+    Custom(ControlPoints),
+}
+
+/// The control points of a custom colormap, there is always at least one
+#[derive(Clone, Debug, PartialEq)]
+pub struct ControlPoints(Vec<[f32; 4]>);
+
+impl ControlPoints {
+    /// Retrieves the control points
+    pub fn get(&self) -> &[[f32; 4]] {
+        return &self.0;
+    }
+}
+
+impl Colormap {
+    /// The control points of the viridis colormap
+    const VIRIDIS: [[f32; 4]; 9] = [
+        [0.267, 0.005, 0.329, 1.0],
+        [0.278, 0.176, 0.482, 1.0],
+        [0.231, 0.322, 0.545, 1.0],
+        [0.173, 0.447, 0.557, 1.0],
+        [0.129, 0.569, 0.549, 1.0],
+        [0.157, 0.682, 0.502, 1.0],
+        [0.369, 0.788, 0.384, 1.0],
+        [0.678, 0.863, 0.188, 1.0],
+        [0.992, 0.906, 0.145, 1.0],
+    ];
+    /// The control points of the plasma colormap
+    const PLASMA: [[f32; 4]; 9] = [
+        [0.051, 0.031, 0.529, 1.0],
+        [0.298, 0.008, 0.631, 1.0],
+        [0.494, 0.012, 0.659, 1.0],
+        [0.663, 0.137, 0.584, 1.0],
+        [0.800, 0.278, 0.471, 1.0],
+        [0.898, 0.420, 0.365, 1.0],
+        [0.973, 0.580, 0.255, 1.0],
+        [0.992, 0.765, 0.157, 1.0],
+        [0.941, 0.976, 0.129, 1.0],
+    ];
+    /// The control points of the greyscale colormap
+    const GREYSCALE: [[f32; 4]; 2] = [[0.0, 0.0, 0.0, 1.0], [1.0, 1.0, 1.0, 1.0]];
+
+    /// Creates a custom colormap with the given evenly spaced control points
+    ///
+    /// # Parameters
+    ///
+    /// points: The control points from the color at 0 to the color at 1
+    ///
+    /// # Errors
+    ///
+    /// See ColormapError for a description of the different errors which may occur
+    pub fn custom(points: Vec<[f32; 4]>) -> Result<Self, ColormapError> {
+        if points.is_empty() {
+            return Err(ColormapError::NoControlPoints);
+        }
+
+        return Ok(Colormap::Custom(ControlPoints(points)));
+    }
+
+    /// Retrieves the control points
+    fn get_control_points(&self) -> &[[f32; 4]] {
+        return match self {
+            Colormap::Viridis => &Self::VIRIDIS,
+            Colormap::Plasma => &Self::PLASMA,
+            Colormap::Greyscale => &Self::GREYSCALE,
+            Colormap::Custom(points) => points.get(),
+        };
+    }
+
+    /// Finds the color for the given value by linearly interpolating between
+    /// the two nearest control points
+    ///
+    /// # Parameters
+    ///
+    /// t: The value to find the color for, it is clamped to [0, 1]
+    pub fn sample(&self, t: f64) -> [f32; 4] {
+        let points = self.get_control_points();
+
+        // Find the two control points to interpolate between
+        let position = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) } * (points.len() - 1) as f64;
+        let id = (position.floor() as usize).min(points.len() - 1);
+        let next_id = (id + 1).min(points.len() - 1);
+        let fraction = (position - id as f64) as f32;
+
+        let mut color = [0.0; 4];
+        color.iter_mut().enumerate().for_each(|(channel, value)| {
+            *value = points[id][channel] * (1.0 - fraction) + points[next_id][channel] * fraction;
+        });
+
+        return color;
+    }
+}
+
+/// The colormap for each resource type
+#[derive(Clone, Debug)]
+struct Colormaps {
+    /// The colormap for the nutrients
+    nutrients: Colormap,
+    /// The colormap for the energy
+    energy: Colormap,
+    /// The colormap for the water
+    water: Colormap,
+}
+
+impl Default for Colormaps {
+    /// Creates greyscale colormaps for all resource types
+    fn default() -> Self {
+        return Self {
+            nutrients: Colormap::Greyscale,
+            energy: Colormap::Greyscale,
+            water: Colormap::Greyscale,
+        };
+    }
+}
+
+impl Colormaps {
+    /// Retrieves the colormap for the given resource type
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to get the colormap for
+    fn get(&self, resource_type: map::ResourceType) -> &Colormap {
+        return match resource_type {
+            map::ResourceType::Nutrients => &self.nutrients,
+            map::ResourceType::Energy => &self.energy,
+            map::ResourceType::Water => &self.water,
+        };
+    }
+
+    /// Retrieves the mutable colormap for the given resource type
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to get the colormap for
+    fn get_mut(&mut self, resource_type: map::ResourceType) -> &mut Colormap {
+        return match resource_type {
+            map::ResourceType::Nutrients => &mut self.nutrients,
+            map::ResourceType::Energy => &mut self.energy,
+            map::ResourceType::Water => &mut self.water,
+        };
+    }
 }
 
 /// A complete state for rendering
@@ -20,9 +261,48 @@ pub struct State {
     uniforms: Uniforms,
     /// The buffers for drawing hexagons
     buffers_hex: BuffersHex,
+    /// The buffer holding the instance data for all tiles
+    buffers_instance: BuffersInstance,
+    /// The geometry of the chunks of the rendered map
+    geometry: map::ChunkGeometry,
+    /// All chunks which have been updated on the gpu since the last render
+    dirty: DirtySet,
+    /// The multisampled texture to render to before resolving
+    multisample: Multisample,
+    /// The way the resources of the tiles are shown
+    color_mode: TileColorMode,
+    /// The colormaps used when showing a single resource
+    colormaps: Colormaps,
+    /// The magnitude of the gradient for all tiles in the order of the
+    /// instance buffer normalized by the largest magnitude, it is empty
+    /// unless a gradient is shown
+    gradient_magnitudes: Vec<f64>,
+    /// The overlay showing the tile indices
+    debug_overlay: DebugOverlay,
+    /// The lines showing the boundaries between chunks
+    chunk_lines: ChunkLines,
+    /// The bars drawn in screen space on top of the map
+    hud: HudRenderer,
+    /// The rectangle shown while dragging a selection
+    selection_rect: SelectionRect,
+    /// The x and y axes of the world
+    axes: AxesOverlay,
+    /// The circles showing the positions of the organisms
+    organisms: OrganismRenderer,
+    /// The timestamps written around the tile and chunk line passes, None if
+    /// the gpu does not support timestamp queries
+    timestamps: Option<render::TimestampQuerySet>,
+    /// The current selection rectangle in normalized device coordinates,
+    /// None if nothing is being selected
+    selection: Option<types::View>,
 }
 
 impl State {
+    /// The timestamps written at the start and end of the tile pass
+    const TIMESTAMPS_TILES: (u32, u32) = (0, 1);
+    /// The timestamps written at the start and end of the chunk line pass
+    const TIMESTAMPS_CHUNK_LINES: (u32, u32) = (2, 3);
+
     /// Constructs a new graphics state
     ///
     /// # Parameters
@@ -30,69 +310,146 @@ impl State {
     /// render_state: The render state to use for rendering
     ///
     /// settings: The settings for this state
-    pub fn new(render_state: &render::RenderState, settings: Settings) -> Self {
+    ///
+    /// map: The map to render
+    pub fn new(render_state: &render::RenderState, settings: Settings, map: &map::Map) -> Self {
         // Create pipelines
-        let pipelines = Pipelines::new(render_state);
+        let pipelines = Pipelines::new(render_state, settings.sample_count);
 
         // Create the uniforms
         let uniforms = Uniforms::new(render_state);
-        uniforms.write_edge_color(render_state, &settings.color_edge);
+        uniforms.write_edge_color(render_state, &settings.grid_lines.tile_edge_color);
+        uniforms.write_tile_scale(render_state, settings.tile_scale);
+        uniforms.write_border_width(render_state, settings.get_visible_border_width());
 
         // Create the hex buffers
         let buffers_hex = BuffersHex::new(render_state);
 
+        // Create the instance buffers and upload all chunks
+        let color_mode = TileColorMode::Composite;
+        let colormaps = Colormaps::default();
+        let mut buffers_instance = BuffersInstance::new(render_state, map);
+        let geometry = map.get_geometry().clone();
+        let mut dirty = DirtySet::new();
+        map.get_data().get_chunks().for_each(|chunk| {
+            buffers_instance.write_chunk(chunk, &geometry, color_mode, &colormaps, &[]);
+            dirty.insert(chunk);
+        });
+
+        // Create the multisampled texture
+        let multisample = Multisample::new(render_state, settings.sample_count);
+
+        // Create the debug overlay with the labels for all tiles
+        let mut debug_overlay = DebugOverlay::new(render_state, settings.sample_count);
+        let labels = map
+            .iter_tiles()
+            .map(|(_, center)| return (map::coordinate_to_tile(&center), center))
+            .collect::<Vec<(types::Index, types::Point)>>();
+        debug_overlay.upload_labels(render_state, &labels);
+
+        // Create the boundary lines for all chunks
+        let chunk_lines = ChunkLines::new(render_state, settings.sample_count, map);
+        chunk_lines.write_color(render_state, &settings.grid_lines.chunk_edge_color);
+
+        // Create the hud
+        let hud = HudRenderer::new(render_state, settings.sample_count);
+
+        // Create the selection rectangle
+        let selection_rect = SelectionRect::new(render_state, settings.sample_count);
+
+        // Create the axes
+        let axes = AxesOverlay::new(render_state, settings.sample_count);
+
+        // Create the organism circles
+        let organisms = OrganismRenderer::new(render_state, settings.sample_count);
+
+        // Create the timestamps for profiling
+        let timestamps = render_state.create_timestamp_query_set(4);
+
         return Self {
             settings,
             pipelines,
             uniforms,
             buffers_hex,
+            buffers_instance,
+            geometry,
+            dirty,
+            multisample,
+            color_mode,
+            colormaps,
+            gradient_magnitudes: Vec::new(),
+            debug_overlay,
+            chunk_lines,
+            hud,
+            selection_rect,
+            axes,
+            organisms,
+            timestamps,
+            selection: None,
         };
     }
 
-    /// Sets the color of the background
+    /// Recreates all resources depending on the size of the surface, must be
+    /// run after the render state has been resized
     ///
     /// # Parameters
     ///
-    /// color: The color to set for the background
-    pub fn set_color_background(&mut self, color: wgpu::Color) {
-        self.settings.color_background = color;
+    /// render_state: The render state to use for rendering
+    pub fn on_resize(&mut self, render_state: &render::RenderState) {
+        self.multisample = Multisample::new(render_state, self.settings.sample_count);
     }
 
-    /// Sets the color of the edges
+    /// Updates the instance data for all tiles of a single chunk and marks
+    /// the chunk as dirty, the data is uploaded to the gpu at the next render
     ///
     /// # Parameters
     ///
-    /// render_state: The render state to use for rendering
-    ///
-    /// color: The new color for the edges
-    pub fn set_color_edge(&mut self, render_state: &render::RenderState, color: wgpu::Color) {
-        self.settings.color_edge = color;
+    /// chunk: The chunk to update
+    pub fn update_chunk(&mut self, chunk: &map::Chunk) {
+        self.buffers_instance.write_chunk(
+            chunk,
+            &self.geometry,
+            self.color_mode,
+            &self.colormaps,
+            &self.gradient_magnitudes,
+        );
+        self.dirty.insert(chunk);
+    }
 
-        // Update the gpu data
-        self.uniforms
-            .write_edge_color(render_state, &self.settings.color_edge);
+    /// Retrieves the hud for queueing bars to draw on top of the map at the
+    /// next render
+    pub fn get_hud(&self) -> &HudRenderer {
+        return &self.hud;
     }
 
-    /// Renders the state onto the given view
+    /// Queues a bar showing the amount of a resource at the next render, the
+    /// bar has the color of the resource in the composite color mode
     ///
     /// # Parameters
     ///
-    /// render_state: The render state to use for rendering
+    /// resource: The resource type to show
     ///
-    /// view: The texture view to render to
+    /// position: The center of the bar in normalized device coordinates
     ///
-    /// transform: The transform to go from world to screen coordinates
-    pub fn render(
+    /// value: The amount of the resource between 0 and 1
+    pub fn render_resource_bar(
         &self,
-        render_state: &render::RenderState,
-        view: &wgpu::TextureView,
-        transform: &types::Transform2D,
+        resource: map::ResourceType,
+        position: types::Point,
+        value: f64,
     ) {
-        self.render_single(render_state, view, transform, DrawMode::Fill);
-        self.render_single(render_state, view, transform, DrawMode::Edge);
+        let color = match resource {
+            map::ResourceType::Nutrients => [1.0, 0.0, 0.0, 1.0],
+            map::ResourceType::Energy => [0.0, 1.0, 0.0, 1.0],
+            map::ResourceType::Water => [0.0, 0.0, 1.0, 1.0],
+        };
+        self.hud
+            .add_bar(position.get_data(), 0.1, 0.015, value as f32, color);
     }
 
-    /// Renders the state onto the given view
+    /// Renders all organisms as circles on top of the view, the circles are
+    /// colored by the resource the organism consumes the most of and
+    /// organisms outside of the screen are skipped
     ///
     /// # Parameters
     ///
@@ -102,16 +459,21 @@ impl State {
     ///
     /// transform: The transform to go from world to screen coordinates
     ///
-    /// draw_mode: Describes wether to draw with fill or outline mode
-    fn render_single(
+    /// organisms: The organisms to draw
+    pub fn render_organism_positions(
         &self,
         render_state: &render::RenderState,
         view: &wgpu::TextureView,
         transform: &types::Transform2D,
-        draw_mode: DrawMode,
+        organisms: &[simulation::Organism],
     ) {
-        // Set the draw mode and transform
-        self.uniforms.write_draw_mode(render_state, draw_mode);
+        // Upload the visible organisms
+        let count = self
+            .organisms
+            .write_organisms(render_state, transform, organisms);
+        if count == 0 {
+            return;
+        }
         self.uniforms.write_transform(render_state, transform);
 
         // Create the encoder
@@ -119,21 +481,18 @@ impl State {
             render_state
                 .get_device()
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Command Encoder"),
+                    label: Some("Command Encoder Organisms"),
                 });
 
         // Initialize the render pass
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
+                label: Some("Render Pass Organisms"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
+                    view: self.multisample.get_view().unwrap_or(view),
+                    resolve_target: self.multisample.get_view().map(|_| view),
                     ops: wgpu::Operations {
-                        load: match draw_mode {
-                            DrawMode::Fill => wgpu::LoadOp::Clear(self.settings.color_background),
-                            DrawMode::Edge => wgpu::LoadOp::Load,
-                        },
+                        load: wgpu::LoadOp::Load,
                         store: wgpu::StoreOp::Store,
                     },
                 })],
@@ -142,17 +501,11 @@ impl State {
                 occlusion_query_set: None,
             });
 
-            // Set the pipeline for fill
-            self.pipelines.set(&mut render_pass, draw_mode);
-
             // Set the main uniforms
             self.uniforms.set(&mut render_pass);
 
-            // Set vertices for a single hexagon
-            let index_count = self.buffers_hex.set(&mut render_pass, draw_mode);
-
-            // Draw
-            render_pass.draw_indexed(0..index_count, 0, 0..1);
+            // Draw the organisms
+            self.organisms.draw(&mut render_pass, count);
         }
 
         // Submit
@@ -160,325 +513,2779 @@ impl State {
             .get_queue()
             .submit(std::iter::once(encoder.finish()));
     }
-}
 
-/// Holds all render pipelines
-struct Pipelines {
-    /// The render pipeline for filling
-    fill: wgpu::RenderPipeline,
-    /// The render pipeline for the outline
-    outline: wgpu::RenderPipeline,
-}
+    /// Retrieves the current selection rectangle in normalized device
+    /// coordinates, None if nothing is being selected
+    pub fn get_selection_rect(&self) -> Option<&types::View> {
+        return self.selection.as_ref();
+    }
 
-impl Pipelines {
-    /// Constructs a new set of render pipelines
+    /// Sets the selection rectangle to draw on top of the map, it is given in
+    /// normalized device coordinates and is not affected by the camera,
+    /// rectangles without an area are not drawn
     ///
     /// # Parameters
     ///
     /// render_state: The render state to use for rendering
-    fn new(render_state: &render::RenderState) -> Self {
-        // Create the shader
-        let shader = wgpu::include_wgsl!("shader.wgsl");
-        let shader = render_state.get_device().create_shader_module(shader);
+    ///
+    /// rect: The rectangle to draw, None to hide it
+    pub fn set_selection_rect(
+        &mut self,
+        render_state: &render::RenderState,
+        rect: Option<types::View>,
+    ) {
+        self.selection = rect.filter(|rect| {
+            return rect.get_size().w.abs() > 0.0 && rect.get_size().h.abs() > 0.0;
+        });
 
-        // Create render pipeline
-        let layout =
-            render_state
-                .get_device()
-                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("Pipeline Layout Descriptor"),
-                    bind_group_layouts: &[&Uniforms::bind_group_layout(render_state)],
-                    push_constant_ranges: &[],
-                });
+        // Update the gpu data
+        if let Some(rect) = &self.selection {
+            self.selection_rect.write_rect(render_state, rect);
+        }
+    }
 
-        // Create the fill pipeline
-        let fill =
-            render_state
-                .get_device()
-                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                    label: Some("Render Pipeline Fill"),
-                    layout: Some(&layout),
-                    vertex: wgpu::VertexState {
-                        module: &shader,
-                        entry_point: Some("vs_main"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        buffers: &[Vertex::desc_hex()],
-                    },
-                    fragment: Some(wgpu::FragmentState {
-                        module: &shader,
-                        entry_point: Some("fs_main"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        targets: &[Some(wgpu::ColorTargetState {
-                            format: render_state.get_config().format,
-                            blend: Some(wgpu::BlendState::REPLACE),
-                            write_mask: wgpu::ColorWrites::ALL,
-                        })],
-                    }),
-                    primitive: wgpu::PrimitiveState {
-                        topology: wgpu::PrimitiveTopology::TriangleList,
-                        strip_index_format: None,
-                        front_face: wgpu::FrontFace::Ccw,
-                        cull_mode: Some(wgpu::Face::Back),
-                        polygon_mode: wgpu::PolygonMode::Fill,
-                        unclipped_depth: false,
-                        conservative: false,
-                    },
-                    depth_stencil: None,
-                    multisample: wgpu::MultisampleState {
-                        count: 1,
-                        mask: !0,
-                        alpha_to_coverage_enabled: false,
-                    },
-                    multiview: None,
-                    cache: None,
-                });
+    /// Retrieves the way the resources of the tiles are shown
+    pub fn get_color_mode(&self) -> TileColorMode {
+        return self.color_mode;
+    }
 
-        // Create the outline pipeline
-        let outline =
-            render_state
-                .get_device()
-                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                    label: Some("Render Pipeline Outline"),
-                    layout: Some(&layout),
-                    vertex: wgpu::VertexState {
-                        module: &shader,
-                        entry_point: Some("vs_main"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        buffers: &[Vertex::desc_hex()],
-                    },
-                    fragment: Some(wgpu::FragmentState {
-                        module: &shader,
-                        entry_point: Some("fs_main"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        targets: &[Some(wgpu::ColorTargetState {
-                            format: render_state.get_config().format,
-                            blend: Some(wgpu::BlendState::REPLACE),
-                            write_mask: wgpu::ColorWrites::ALL,
-                        })],
-                    }),
-                    primitive: wgpu::PrimitiveState {
-                        topology: wgpu::PrimitiveTopology::LineStrip,
-                        strip_index_format: None,
-                        front_face: wgpu::FrontFace::Ccw,
-                        cull_mode: Some(wgpu::Face::Back),
-                        polygon_mode: wgpu::PolygonMode::Fill,
-                        unclipped_depth: false,
-                        conservative: false,
-                    },
-                    depth_stencil: None,
-                    multisample: wgpu::MultisampleState {
-                        count: 1,
-                        mask: !0,
-                        alpha_to_coverage_enabled: false,
-                    },
-                    multiview: None,
-                    cache: None,
-                });
+    /// Sets the way the resources of the tiles are shown and updates all
+    /// chunks again with the new colors
+    ///
+    /// # Parameters
+    ///
+    /// map: The map to update the chunks from, its gradient is calculated if a gradient is shown
+    ///
+    /// mode: The new color mode
+    pub fn set_color_mode(&mut self, map: &mut map::Map, mode: TileColorMode) {
+        if mode == self.color_mode {
+            return;
+        }
 
-        Self { fill, outline }
+        self.color_mode = mode;
+        self.update_gradient(map);
+        map.get_data().get_chunks().for_each(|chunk| {
+            self.update_chunk(chunk);
+        });
     }
 
-    /// Sets the correct pipeline for the render pass
+    /// Recalculates the gradient magnitudes shown for the tiles from the
+    /// current resources of the map, it does nothing unless a gradient is
+    /// shown, the chunks must be uploaded afterwards for it to take effect
     ///
     /// # Parameters
     ///
-    /// render_pass: The render pass to draw to
-    ///
-    /// draw_mode: Describes wether to draw with fill or outline mode
-    fn set<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, draw_mode: DrawMode) {
-        match draw_mode {
-            DrawMode::Fill => render_pass.set_pipeline(&self.fill),
-            DrawMode::Edge => render_pass.set_pipeline(&self.outline),
+    /// map: The map to calculate the gradient for
+    pub fn update_gradient(&mut self, map: &mut map::Map) {
+        let resource = match self.color_mode {
+            TileColorMode::GradientMagnitude(resource) => resource,
+            _ => {
+                self.gradient_magnitudes.clear();
+                return;
+            }
         };
+
+        // Normalize by the largest magnitude
+        let gradient = map.compute_gradient().get(resource);
+        let max = gradient
+            .iter()
+            .fold(0.0_f64, |prev, next| return prev.max(next.norm()));
+        self.gradient_magnitudes = gradient
+            .iter()
+            .map(|value| {
+                return if max > 0.0 { value.norm() / max } else { 0.0 };
+            })
+            .collect();
     }
-}
 
-/// Holds all of the global uniforms for the shader and the bind group for them
-struct Uniforms {
-    transform: wgpu::Buffer,
-    /// The draw mode buffer
-    draw_mode: wgpu::Buffer,
-    /// The edge color buffer
-    edge_color: wgpu::Buffer,
-    /// The bind group for all uniforms
-    bind_group: wgpu::BindGroup,
-}
+    /// Retrieves the colormap used when showing the given resource type
+    ///
+    /// # Parameters
+    ///
+    /// resource: The resource type to get the colormap for
+    pub fn get_colormap(&self, resource: map::ResourceType) -> &Colormap {
+        return self.colormaps.get(resource);
+    }
 
-impl Uniforms {
-    /// Creates a new set of uniforms for the gpu
+    /// Sets the colormap used when showing the given resource type and
+    /// updates all chunks if that resource is currently shown
     ///
     /// # Parameters
     ///
-    /// render_state: The render state to use for rendering
-    fn new(render_state: &render::RenderState) -> Self {
-        // Create transform buffer
-        let transform = render_state
-            .get_device()
-            .create_buffer(&wgpu::BufferDescriptor {
-                label: Some("Transform Buffer"),
-                size: (std::mem::size_of::<f32>() * 4) as u64,
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            });
+    /// map: The map to update the chunks from
+    ///
+    /// resource: The resource type to set the colormap for
+    ///
+    /// colormap: The new colormap
+    pub fn set_colormap(
+        &mut self,
+        map: &map::Map,
+        resource: map::ResourceType,
+        colormap: Colormap,
+    ) {
+        *self.colormaps.get_mut(resource) = colormap;
 
-        // Create draw mode buffer
-        let draw_mode = render_state
-            .get_device()
-            .create_buffer(&wgpu::BufferDescriptor {
-                label: Some("Draw Mode Buffer"),
-                size: std::mem::size_of::<u32>() as u64,
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
+        // Update the gpu data
+        let shown = match self.color_mode {
+            TileColorMode::Nutrients => Some(map::ResourceType::Nutrients),
+            TileColorMode::Energy => Some(map::ResourceType::Energy),
+            TileColorMode::Water => Some(map::ResourceType::Water),
+            TileColorMode::Composite
+            | TileColorMode::Biome
+            | TileColorMode::GradientMagnitude(_) => None,
+        };
+        if shown == Some(resource) {
+            map.get_data().get_chunks().for_each(|chunk| {
+                self.update_chunk(chunk);
             });
+        }
+    }
 
-        // Create edge color buffer
-        let edge_color = render_state
-            .get_device()
-            .create_buffer(&wgpu::BufferDescriptor {
-                label: Some("Draw Mode Buffer"),
-                size: (std::mem::size_of::<f32>() * 4) as u64,
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            });
+    /// Clears all dirty chunks, returns true if any chunks were dirty such
+    /// that the screen must be redrawn
+    pub fn resolve_dirty(&mut self) -> bool {
+        let dirty = !self.dirty.is_empty();
+        self.dirty.clear();
+        return dirty;
+    }
 
-        // Create bind group for the uniforms
-        let bind_group = render_state
-            .get_device()
-            .create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Bind Group Uniforms"),
-                layout: &Self::bind_group_layout(render_state),
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: transform.as_entire_binding(),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: draw_mode.as_entire_binding(),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: edge_color.as_entire_binding(),
-                    },
-                ],
-            });
+    /// Sets the color of the background
+    ///
+    /// # Parameters
+    ///
+    /// color: The color to set for the background
+    pub fn set_color_background(&mut self, color: wgpu::Color) {
+        self.settings.color_background = color;
+    }
 
-        Self {
-            transform,
-            draw_mode,
-            edge_color,
-            bind_group,
-        }
+    /// Sets the color of the edges
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// color: The new color for the edges
+    pub fn set_color_edge(&mut self, render_state: &render::RenderState, color: wgpu::Color) {
+        self.settings.grid_lines.tile_edge_color = color;
+
+        // Update the gpu data
+        self.uniforms
+            .write_edge_color(render_state, &self.settings.grid_lines.tile_edge_color);
     }
 
-    /// Update the transform, this must be run once before the first rendering as it is not initialized
+    /// Retrieves the settings for the tile edges and chunk boundaries
+    pub fn get_grid_lines(&self) -> &GridLines {
+        return &self.settings.grid_lines;
+    }
+
+    /// Sets which tile edges and chunk boundaries are drawn and their colors
     ///
     /// # Parameters
     ///
     /// render_state: The render state to use for rendering
     ///
-    /// transform: The transform to apply to all vertices going from world coordinates to screen coordinates
-    fn write_transform(&self, render_state: &render::RenderState, transform: &types::Transform2D) {
-        render_state.get_queue().write_buffer(
-            &self.transform,
-            0,
-            bytemuck::cast_slice(&[transform.get_data_center_transform()]),
-        );
+    /// grid_lines: The new settings for the lines
+    pub fn set_grid_lines(&mut self, render_state: &render::RenderState, grid_lines: GridLines) {
+        self.settings.grid_lines = grid_lines;
+
+        // Update the gpu data
+        self.uniforms
+            .write_edge_color(render_state, &self.settings.grid_lines.tile_edge_color);
+        self.uniforms
+            .write_border_width(render_state, self.settings.get_visible_border_width());
+        self.chunk_lines
+            .write_color(render_state, &self.settings.grid_lines.chunk_edge_color);
+    }
+
+    /// Retrieves whether the tile indices are shown
+    pub fn get_show_debug_indices(&self) -> bool {
+        return self.settings.show_debug_indices;
+    }
+
+    /// Sets whether the tile indices are shown
+    ///
+    /// # Parameters
+    ///
+    /// show: True if the tile indices should be shown
+    pub fn set_show_debug_indices(&mut self, show: bool) {
+        self.settings.show_debug_indices = show;
+    }
+
+    /// Retrieves whether the axes of the world are shown
+    pub fn get_show_axes(&self) -> bool {
+        return self.settings.show_axes;
+    }
+
+    /// Sets whether the axes of the world are shown
+    ///
+    /// # Parameters
+    ///
+    /// show: True if the axes should be shown
+    pub fn set_show_axes(&mut self, show: bool) {
+        self.settings.show_axes = show;
+    }
+
+    /// Sets the length of each axis in world coordinates
+    ///
+    /// # Parameters
+    ///
+    /// length: The length of each axis measured from the origin
+    pub fn set_axes_length(&mut self, length: f64) {
+        self.settings.axes_length = length;
     }
 
-    /// Update the draw mode, this must be run once before the first rendering as it is not initialized
+    /// Sets the factor to scale each hexagon by around its center
     ///
     /// # Parameters
     ///
     /// render_state: The render state to use for rendering
     ///
-    /// draw_mode: The draw mode to tell the shader if it is drawing edges or filling
-    fn write_draw_mode(&self, render_state: &render::RenderState, draw_mode: DrawMode) {
-        render_state.get_queue().write_buffer(
-            &self.draw_mode,
-            0,
-            bytemuck::cast_slice(&[draw_mode.get_data()]),
-        );
+    /// factor: The new factor to scale each hexagon by around its center at a zoom of 1
+    pub fn set_tile_scale(&mut self, render_state: &render::RenderState, factor: f64) {
+        self.settings.tile_scale = factor;
+
+        // Update the gpu data
+        self.uniforms
+            .write_tile_scale(render_state, self.settings.tile_scale);
     }
 
-    /// Update the edge color, this must be run once before the first rendering as it is not initialized
+    /// Sets the width of the border drawn inside each hexagon
     ///
     /// # Parameters
     ///
     /// render_state: The render state to use for rendering
     ///
-    /// draw_mode: The draw mode to tell the shader if it is drawing edges or filling
-    fn write_edge_color(&self, render_state: &render::RenderState, edge_color: &wgpu::Color) {
-        render_state.get_queue().write_buffer(
-            &self.edge_color,
-            0,
-            bytemuck::cast_slice(&[get_color_data(edge_color)]),
-        );
+    /// width_fraction: The width of the border as a fraction of the distance from the center to the edge
+    pub fn set_hex_border_width(
+        &mut self,
+        render_state: &render::RenderState,
+        width_fraction: f64,
+    ) {
+        self.settings.hex_border_width = width_fraction;
+
+        // Update the gpu data
+        self.uniforms
+            .write_border_width(render_state, self.settings.get_visible_border_width());
     }
 
-    /// Binds the uniforms to the given render pass
+    /// Renders the state onto the given view
     ///
     /// # Parameters
     ///
-    /// render_pass: The render pass to draw to
-    fn set<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
-        render_pass.set_bind_group(0, &self.bind_group, &[]);
+    /// render_state: The render state to use for rendering
+    ///
+    /// view: The texture view to render to
+    ///
+    /// transform: The transform to go from world to screen coordinates
+    pub fn render(
+        &self,
+        render_state: &render::RenderState,
+        view: &wgpu::TextureView,
+        transform: &types::Transform2D,
+    ) {
+        self.render_tiles(render_state, view, transform);
+        if self.settings.grid_lines.show_chunk_edges {
+            self.render_chunk_lines(render_state, view);
+        }
+        if self.settings.show_axes {
+            self.render_axes(render_state, view, transform);
+        }
+        self.hud.flush(render_state, view, &self.multisample);
+        if self.settings.show_debug_indices {
+            self.render_debug(render_state, view);
+        }
+        if self.selection.is_some() {
+            self.render_selection(render_state, view);
+        }
+        if let Some(timestamps) = &self.timestamps {
+            timestamps.resolve(render_state);
+        }
     }
 
-    /// Creates the bind group layout for a set of uniforms
+    /// Recompiles the tile shader from the wgsl file at the given path, the
+    /// current shader is kept if it fails
     ///
     /// # Parameters
     ///
     /// render_state: The render state to use for rendering
-    fn bind_group_layout(render_state: &render::RenderState) -> wgpu::BindGroupLayout {
-        render_state
+    ///
+    /// path: The path of the wgsl file
+    ///
+    /// # Errors
+    ///
+    /// See ShaderError for a description of the different errors which may occur
+    pub fn reload_shader(
+        &mut self,
+        render_state: &render::RenderState,
+        path: &Path,
+    ) -> Result<(), ShaderError> {
+        return self.pipelines.reload_shader(render_state, path);
+    }
+
+    /// Reads back how long the gpu spent on the passes of the latest render,
+    /// it blocks until the gpu has finished, returns None if the gpu does not
+    /// support timestamp queries
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state used for rendering
+    pub fn read_gpu_timings(&self, render_state: &render::RenderState) -> Option<GpuTimings> {
+        let timestamps = self.timestamps.as_ref()?;
+        let (begin, end) = Self::TIMESTAMPS_TILES;
+        let tiles_us = timestamps.read_elapsed_us(render_state, begin, end)?;
+        let chunk_lines_us = if self.settings.grid_lines.show_chunk_edges {
+            let (begin, end) = Self::TIMESTAMPS_CHUNK_LINES;
+            timestamps.read_elapsed_us(render_state, begin, end)
+        } else {
+            None
+        };
+
+        return Some(GpuTimings {
+            tiles_us,
+            chunk_lines_us,
+        });
+    }
+
+    /// Renders the state and the organisms on top into a new texture with the
+    /// same size as the surface and reads it back from the gpu as an image
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// transform: The transform to go from world to screen coordinates
+    ///
+    /// organisms: The organisms to draw
+    ///
+    /// # Errors
+    ///
+    /// See CaptureError for a description of the different errors which may occur
+    pub fn capture(
+        &self,
+        render_state: &render::RenderState,
+        transform: &types::Transform2D,
+        organisms: &[simulation::Organism],
+    ) -> Result<image::RgbaImage, CaptureError> {
+        // Make sure the format can be converted to rgba
+        let config = render_state.get_config();
+        let swap_channels = match config.format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => return Err(CaptureError::UnsupportedFormat(format)),
+        };
+
+        // Render to a texture which can be copied from
+        let size = wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = render_state
             .get_device()
-            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Bind Group Uniform Layout"),
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::VERTEX,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 2,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Capture Texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: config.format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.render(render_state, &view, transform);
+        self.render_organism_positions(render_state, &view, transform, organisms);
+
+        // Read back the pixels and convert to rgba
+        let mut data = read_texture(render_state, &texture, size, 4)?;
+        if swap_channels {
+            data.chunks_mut(4).for_each(|pixel| pixel.swap(0, 2));
+        }
+
+        return image::RgbaImage::from_raw(size.width, size.height, data)
+            .ok_or(CaptureError::InvalidSize);
+    }
+
+    /// Renders the given resource of all tiles into a grayscale texture with
+    /// one pixel per tile, reads it back from the gpu and encodes it as a png
+    ///
+    /// Each chunk is placed in a single row of pixels such that the row is
+    /// the chunk y and the column is the chunk x times the number of tiles in
+    /// a chunk plus the index of the tile within the chunk, both chunk
+    /// coordinates are taken relative to the smallest ones of the map. The
+    /// index of a tile within the chunk counts the tiles of the bulk first,
+    /// then the edges and then the vertices in the order of ChunkType::all.
+    /// Pixels which do not correspond to any tile are 0
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// map: The map to export the resource of
+    ///
+    /// resource: The resource to export, the value is clamped to [0, 1]
+    ///
+    /// # Errors
+    ///
+    /// See CaptureError for a description of the different errors which may occur
+    pub fn export_resource_texture(
+        &self,
+        render_state: &render::RenderState,
+        map: &map::Map,
+        resource: map::ResourceType,
+    ) -> Result<Vec<u8>, CaptureError> {
+        // Find the position of the first tile of each chunk type within a chunk
+        let chunk_size = map.get_chunk_size();
+        let mut tiles_per_chunk = 0;
+        let type_offsets = map::ChunkType::all()
+            .into_iter()
+            .map(|chunk_type| {
+                let offset = tiles_per_chunk;
+                tiles_per_chunk += map::ChunkGeometry::tile_count(&chunk_type, chunk_size);
+                return (chunk_type, offset);
+            })
+            .collect::<HashMap<map::ChunkType, usize>>();
+
+        // Find the chunk coordinates and the index within the chunk of all tiles
+        let tiles = map
+            .get_data()
+            .get_chunks()
+            .flat_map(|chunk| {
+                let coordinates = map
+                    .get_data()
+                    .get_coordinates(chunk.get_chunk_type(), chunk.get_index());
+                let type_offset = type_offsets[chunk.get_chunk_type()];
+                return chunk
+                    .get_tiles()
+                    .iter()
+                    .enumerate()
+                    .map(move |(tile_id, tile)| {
+                        return (
+                            coordinates,
+                            type_offset + tile_id,
+                            tile.current_resources.get(resource),
+                        );
+                    });
+            })
+            .collect::<Vec<_>>();
+        let min_x = tiles.iter().map(|(index, _, _)| return index.x).min();
+        let max_x = tiles.iter().map(|(index, _, _)| return index.x).max();
+        let min_y = tiles.iter().map(|(index, _, _)| return index.y).min();
+        let max_y = tiles.iter().map(|(index, _, _)| return index.y).max();
+        let (min_x, max_x, min_y, max_y) = match (min_x, max_x, min_y, max_y) {
+            (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) => (min_x, max_x, min_y, max_y),
+            _ => return Err(CaptureError::InvalidSize),
+        };
+
+        // Make sure the texture fits on the gpu
+        let size = wgpu::Extent3d {
+            width: ((max_x - min_x + 1) as usize * tiles_per_chunk) as u32,
+            height: (max_y - min_y + 1) as u32,
+            depth_or_array_layers: 1,
+        };
+        let max_size = render_state.get_device().limits().max_texture_dimension_2d;
+        if size.width > max_size || size.height > max_size {
+            return Err(CaptureError::TooLarge(size.width, size.height));
+        }
+
+        // Place a point in the center of the pixel of each tile
+        let pixels = tiles
+            .iter()
+            .map(|(coordinates, tile_id, value)| {
+                let column = ((coordinates.x - min_x) as usize * tiles_per_chunk + tile_id) as f64;
+                let row = (coordinates.y - min_y) as f64;
+                return ResourcePixel {
+                    position: [
+                        ((column + 0.5) / size.width as f64 * 2.0 - 1.0) as f32,
+                        (1.0 - (row + 0.5) / size.height as f64 * 2.0) as f32,
+                    ],
+                    value: value.clamp(0.0, 1.0) as f32,
+                };
+            })
+            .collect::<Vec<_>>();
+        let vertices =
+            render_state
+                .get_device()
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Vertex Buffer Resource Texture"),
+                    contents: bytemuck::cast_slice(&pixels),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+
+        // Render to a texture which can be copied from
+        let texture = render_state
+            .get_device()
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Resource Texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let pipeline = ResourcePixel::pipeline(render_state);
+        let mut encoder =
+            render_state
+                .get_device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Command Encoder Resource Texture"),
+                });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass Resource Texture"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
                     },
-                ],
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&pipeline);
+            render_pass.set_vertex_buffer(0, vertices.slice(..));
+            render_pass.draw(0..pixels.len() as u32, 0..1);
+        }
+        render_state
+            .get_queue()
+            .submit(std::iter::once(encoder.finish()));
+
+        // Read back the pixels and encode them
+        let data = read_texture(render_state, &texture, size, 1)?;
+        let image = image::GrayImage::from_raw(size.width, size.height, data)
+            .ok_or(CaptureError::InvalidSize)?;
+        let mut png = Vec::new();
+        image.write_to(&mut io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+
+        return Ok(png);
+    }
+
+    /// Renders the chunk boundaries on top of the view, the transform must
+    /// already have been written to the uniforms
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// view: The texture view to render to
+    fn render_chunk_lines(&self, render_state: &render::RenderState, view: &wgpu::TextureView) {
+        // Create the encoder
+        let mut encoder =
+            render_state
+                .get_device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Command Encoder Chunk Lines"),
+                });
+
+        // Initialize the render pass
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass Chunk Lines"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: self.multisample.get_view().unwrap_or(view),
+                    resolve_target: self.multisample.get_view().map(|_| view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: self.timestamps.as_ref().map(|timestamps| {
+                    let (begin, end) = Self::TIMESTAMPS_CHUNK_LINES;
+                    return timestamps.wrap_pass(begin, end);
+                }),
+                occlusion_query_set: None,
+            });
+
+            // Set the main uniforms
+            self.uniforms.set(&mut render_pass);
+
+            // Draw the lines
+            self.chunk_lines.draw(&mut render_pass);
+        }
+
+        // Submit
+        render_state
+            .get_queue()
+            .submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Renders the selection rectangle on top of the view
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// view: The texture view to render to
+    fn render_selection(&self, render_state: &render::RenderState, view: &wgpu::TextureView) {
+        // Create the encoder
+        let mut encoder =
+            render_state
+                .get_device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Command Encoder Selection"),
+                });
+
+        // Initialize the render pass
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass Selection"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: self.multisample.get_view().unwrap_or(view),
+                    resolve_target: self.multisample.get_view().map(|_| view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            // Draw the rectangle
+            self.selection_rect.draw(&mut render_pass);
+        }
+
+        // Submit
+        render_state
+            .get_queue()
+            .submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Renders the x axis in red and the y axis in blue through the origin of
+    /// the world, the axes fade out when the camera is zoomed far in
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// view: The texture view to render to
+    ///
+    /// transform: The transform to go from world to screen coordinates
+    fn render_axes(
+        &self,
+        render_state: &render::RenderState,
+        view: &wgpu::TextureView,
+        transform: &types::Transform2D,
+    ) {
+        // Skip the axes if they are invisible
+        if !self
+            .axes
+            .write_axes(render_state, transform, self.settings.axes_length)
+        {
+            return;
+        }
+
+        // Create the encoder
+        let mut encoder =
+            render_state
+                .get_device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Command Encoder Axes"),
+                });
+
+        // Initialize the render pass
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass Axes"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: self.multisample.get_view().unwrap_or(view),
+                    resolve_target: self.multisample.get_view().map(|_| view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            // Draw the axes
+            self.axes.draw(&mut render_pass);
+        }
+
+        // Submit
+        render_state
+            .get_queue()
+            .submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Renders the debug overlay on top of the view, the transform must
+    /// already have been written to the uniforms
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// view: The texture view to render to
+    fn render_debug(&self, render_state: &render::RenderState, view: &wgpu::TextureView) {
+        // Create the encoder
+        let mut encoder =
+            render_state
+                .get_device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Command Encoder Debug"),
+                });
+
+        // Initialize the render pass
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass Debug"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: self.multisample.get_view().unwrap_or(view),
+                    resolve_target: self.multisample.get_view().map(|_| view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            // Set the main uniforms
+            self.uniforms.set(&mut render_pass);
+
+            // Draw the labels
+            self.debug_overlay.draw(&mut render_pass);
+        }
+
+        // Submit
+        render_state
+            .get_queue()
+            .submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Renders all tiles including their borders onto the given view after
+    /// clearing it
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// view: The texture view to render to
+    ///
+    /// transform: The transform to go from world to screen coordinates
+    fn render_tiles(
+        &self,
+        render_state: &render::RenderState,
+        view: &wgpu::TextureView,
+        transform: &types::Transform2D,
+    ) {
+        // Set the transform and upload any updated tiles
+        self.uniforms.write_transform(render_state, transform);
+        self.buffers_instance.flush(render_state);
+
+        // Create the encoder
+        let mut encoder =
+            render_state
+                .get_device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Command Encoder"),
+                });
+
+        // Initialize the render pass
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: self.multisample.get_view().unwrap_or(view),
+                    resolve_target: self.multisample.get_view().map(|_| view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(self.settings.color_background),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: self.timestamps.as_ref().map(|timestamps| {
+                    let (begin, end) = Self::TIMESTAMPS_TILES;
+                    return timestamps.wrap_pass(begin, end);
+                }),
+                occlusion_query_set: None,
+            });
+
+            // Set the pipeline for the tiles
+            self.pipelines.set(&mut render_pass);
+
+            // Set the main uniforms
+            self.uniforms.set(&mut render_pass);
+
+            // Set vertices for a single hexagon
+            let index_count = self.buffers_hex.set(&mut render_pass);
+
+            // Set the instance data for all tiles
+            let instance_count = self.buffers_instance.set(&mut render_pass);
+
+            // Draw
+            render_pass.draw_indexed(0..index_count, 0, 0..instance_count);
+        }
+
+        // Submit
+        render_state
+            .get_queue()
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+/// The error types for when creating a colormap
+#[derive(Error, Debug, Clone)]
+pub enum ColormapError {
+    /// A custom colormap was given no control points
+    #[error("A colormap must have at least one control point")]
+    NoControlPoints,
+}
+
+/// The error types for when adding items to an instance buffer
+#[derive(Error, Debug, Clone)]
+pub enum InstanceBufferError {
+    /// The buffer already holds as many items as it has room for
+    #[error("The instance buffer is full with a capacity of {:?}", .0)]
+    Full(usize),
+}
+
+/// The error types for when loading a shader from a file
+#[derive(Error, Debug)]
+pub enum ShaderError {
+    /// The file could not be read
+    #[error("Unable to read the shader: {:?}", .0)]
+    Io(#[from] io::Error),
+    /// The shader or the pipelines using it are invalid
+    #[error("{}", .0)]
+    Compile(String),
+}
+
+/// The time the gpu spent on the different passes of a render
+#[derive(Clone, Copy, Debug)]
+pub struct GpuTimings {
+    /// The microseconds spent filling the tiles and drawing their borders
+    pub tiles_us: u64,
+    /// The microseconds spent drawing the chunk boundaries, None if they
+    /// are not shown
+    pub chunk_lines_us: Option<u64>,
+}
+
+/// The error types for when capturing the rendered image
+#[derive(Error, Debug)]
+pub enum CaptureError {
+    /// The surface format cannot be converted to rgba
+    #[error("The texture format {:?} is not supported", .0)]
+    UnsupportedFormat(wgpu::TextureFormat),
+    /// The buffer could not be mapped for reading
+    #[error("Unable to read the buffer: {:?}", .0)]
+    Map(#[from] wgpu::BufferAsyncError),
+    /// The gpu stopped before the buffer was mapped
+    #[error("The gpu did not respond")]
+    Disconnected,
+    /// The data read does not match the size of the image
+    #[error("The size of the data does not match the size of the image")]
+    InvalidSize,
+    /// The texture is larger than the gpu supports
+    #[error("The texture size {:?}x{:?} is too large", .0, .1)]
+    TooLarge(u32, u32),
+    /// The image could not be encoded
+    #[error("Unable to encode the image: {:?}", .0)]
+    Encode(#[from] image::ImageError),
+}
+
+/// Holds all render pipelines
+struct Pipelines {
+    /// The render pipeline for filling the tiles and drawing their borders
+    fill: wgpu::RenderPipeline,
+    /// The number of samples per pixel the pipelines were created for
+    sample_count: u32,
+}
+
+impl Pipelines {
+    /// Constructs a new set of render pipelines
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// sample_count: The number of samples per pixel
+    fn new(render_state: &render::RenderState, sample_count: u32) -> Self {
+        let shader = wgpu::include_wgsl!("shader.wgsl");
+        let shader = render_state.get_device().create_shader_module(shader);
+
+        return Self::from_shader(render_state, sample_count, &shader);
+    }
+
+    /// Constructs a new set of render pipelines using the shader in the wgsl
+    /// file at the given path instead of the built in shader
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// sample_count: The number of samples per pixel
+    ///
+    /// path: The path of the wgsl file
+    ///
+    /// # Errors
+    ///
+    /// See ShaderError for a description of the different errors which may occur
+    fn new_with_shader_from_path(
+        render_state: &render::RenderState,
+        sample_count: u32,
+        path: &Path,
+    ) -> Result<Self, ShaderError> {
+        let code = fs::read_to_string(path)?;
+
+        // Catch the validation errors instead of panicking
+        render_state
+            .get_device()
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = render_state
+            .get_device()
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Shader From File"),
+                source: wgpu::ShaderSource::Wgsl(code.into()),
+            });
+        let pipelines = Self::from_shader(render_state, sample_count, &shader);
+        if let Some(error) = pollster::block_on(render_state.get_device().pop_error_scope()) {
+            return Err(ShaderError::Compile(error.to_string()));
+        }
+
+        return Ok(pipelines);
+    }
+
+    /// Recompiles the shader from the wgsl file at the given path and
+    /// recreates the pipelines, the current pipelines are kept if it fails
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// path: The path of the wgsl file
+    ///
+    /// # Errors
+    ///
+    /// See ShaderError for a description of the different errors which may occur
+    fn reload_shader(
+        &mut self,
+        render_state: &render::RenderState,
+        path: &Path,
+    ) -> Result<(), ShaderError> {
+        *self = Self::new_with_shader_from_path(render_state, self.sample_count, path)?;
+
+        return Ok(());
+    }
+
+    /// Constructs the render pipelines for a compiled shader
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// sample_count: The number of samples per pixel
+    ///
+    /// shader: The shader with the vs_main and fs_main entry points
+    fn from_shader(
+        render_state: &render::RenderState,
+        sample_count: u32,
+        shader: &wgpu::ShaderModule,
+    ) -> Self {
+        // Create render pipeline
+        let layout =
+            render_state
+                .get_device()
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Pipeline Layout Descriptor"),
+                    bind_group_layouts: &[&Uniforms::bind_group_layout(render_state)],
+                    push_constant_ranges: &[],
+                });
+
+        // Create the fill pipeline
+        let fill =
+            render_state
+                .get_device()
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Render Pipeline Fill"),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module: shader,
+                        entry_point: Some("vs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        buffers: &[Vertex::desc_hex(), Instance::desc()],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: shader,
+                        entry_point: Some("fs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: render_state.get_config().format,
+                            blend: Some(wgpu::BlendState::REPLACE),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: Some(wgpu::Face::Back),
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState {
+                        count: sample_count,
+                        mask: !0,
+                        alpha_to_coverage_enabled: false,
+                    },
+                    multiview: None,
+                    cache: None,
+                });
+
+        Self { fill, sample_count }
+    }
+
+    /// Sets the pipeline for the render pass
+    ///
+    /// # Parameters
+    ///
+    /// render_pass: The render pass to draw to
+    fn set<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_pipeline(&self.fill);
+    }
+}
+
+/// Holds all of the global uniforms for the shader and the bind group for them
+struct Uniforms {
+    /// The transform buffer
+    transform: wgpu::Buffer,
+    /// The offset buffer
+    offset: wgpu::Buffer,
+    /// The border width buffer
+    border_width: wgpu::Buffer,
+    /// The edge color buffer
+    edge_color: wgpu::Buffer,
+    /// The tile scale buffer
+    tile_scale: wgpu::Buffer,
+    /// The bind group for all uniforms
+    bind_group: wgpu::BindGroup,
+}
+
+impl Uniforms {
+    /// Creates a new set of uniforms for the gpu
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    fn new(render_state: &render::RenderState) -> Self {
+        // Create transform buffer
+        let transform = render_state
+            .get_device()
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Transform Buffer"),
+                size: (std::mem::size_of::<f32>() * 4) as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        // Create offset buffer
+        let offset = render_state
+            .get_device()
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Offset Buffer"),
+                size: (std::mem::size_of::<f32>() * 2) as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        // Create border width buffer
+        let border_width = render_state
+            .get_device()
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Border Width Buffer"),
+                size: std::mem::size_of::<f32>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        // Create edge color buffer
+        let edge_color = render_state
+            .get_device()
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Edge Color Buffer"),
+                size: (std::mem::size_of::<f32>() * 4) as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        // Create tile scale buffer
+        let tile_scale = render_state
+            .get_device()
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Tile Scale Buffer"),
+                size: std::mem::size_of::<f32>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        // Create bind group for the uniforms
+        let bind_group = render_state
+            .get_device()
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Bind Group Uniforms"),
+                layout: &Self::bind_group_layout(render_state),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: transform.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: border_width.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: edge_color.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: offset.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: tile_scale.as_entire_binding(),
+                    },
+                ],
+            });
+
+        Self {
+            transform,
+            offset,
+            border_width,
+            edge_color,
+            tile_scale,
+            bind_group,
+        }
+    }
+
+    /// Update the transform, this must be run once before the first rendering as it is not initialized
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// transform: The transform to apply to all vertices going from world coordinates to screen coordinates
+    fn write_transform(&self, render_state: &render::RenderState, transform: &types::Transform2D) {
+        render_state.get_queue().write_buffer(
+            &self.transform,
+            0,
+            bytemuck::cast_slice(&[transform.get_data_center_transform()]),
+        );
+        render_state.get_queue().write_buffer(
+            &self.offset,
+            0,
+            bytemuck::cast_slice(&[transform.get_data_offset()]),
+        );
+    }
+
+    /// Update the border width, this must be run once before the first rendering as it is not initialized
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// border_width: The width of the border as a fraction of the distance from the center to the edge
+    fn write_border_width(&self, render_state: &render::RenderState, border_width: f64) {
+        render_state.get_queue().write_buffer(
+            &self.border_width,
+            0,
+            bytemuck::cast_slice(&[border_width as f32]),
+        );
+    }
+
+    /// Update the edge color, this must be run once before the first rendering as it is not initialized
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// edge_color: The color to draw the borders of the tiles with
+    fn write_edge_color(&self, render_state: &render::RenderState, edge_color: &wgpu::Color) {
+        render_state.get_queue().write_buffer(
+            &self.edge_color,
+            0,
+            bytemuck::cast_slice(&[get_color_data(edge_color)]),
+        );
+    }
+
+    /// Update the tile scale, this must be run once before the first rendering as it is not initialized
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// tile_scale: The factor to scale each hexagon by around its center
+    fn write_tile_scale(&self, render_state: &render::RenderState, tile_scale: f64) {
+        render_state.get_queue().write_buffer(
+            &self.tile_scale,
+            0,
+            bytemuck::cast_slice(&[tile_scale as f32]),
+        );
+    }
+
+    /// Binds the uniforms to the given render pass
+    ///
+    /// # Parameters
+    ///
+    /// render_pass: The render pass to draw to
+    fn set<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+    }
+
+    /// Creates the bind group layout for a set of uniforms
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    fn bind_group_layout(render_state: &render::RenderState) -> wgpu::BindGroupLayout {
+        render_state
+            .get_device()
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Bind Group Uniform Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            })
+    }
+}
+
+/// Holds the multisampled texture which is rendered to before it is
+/// resolved onto the output texture
+struct Multisample {
+    /// The view of the multisampled texture, None if multisampling is disabled
+    view: Option<wgpu::TextureView>,
+}
+
+impl Multisample {
+    /// Creates a new multisampled texture with the same size and format as
+    /// the surface
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// sample_count: The number of samples per pixel
+    fn new(render_state: &render::RenderState, sample_count: u32) -> Self {
+        if sample_count <= 1 {
+            return Self { view: None };
+        }
+
+        let size = render_state.get_surface_size();
+        let texture = render_state
+            .get_device()
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Multisample Texture"),
+                size: wgpu::Extent3d {
+                    width: size.width,
+                    height: size.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format: render_state.get_config().format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            });
+
+        return Self {
+            view: Some(texture.create_view(&wgpu::TextureViewDescriptor::default())),
+        };
+    }
+
+    /// Retrieves the view to render to, None if multisampling is disabled
+    fn get_view(&self) -> Option<&wgpu::TextureView> {
+        return self.view.as_ref();
+    }
+}
+
+/// Draws text labels with the tile index on top of tiles using a glyph atlas
+/// of 8x8 bitmap characters
+pub struct DebugOverlay {
+    /// The render pipeline for the labels
+    pipeline: wgpu::RenderPipeline,
+    /// The bind group for the glyph atlas and its sampler
+    bind_group: wgpu::BindGroup,
+    /// The buffer holding the quads of all characters, None if no labels have been uploaded
+    vertices: Option<wgpu::Buffer>,
+    /// The number of vertices in the vertex buffer
+    count: u32,
+}
+
+impl DebugOverlay {
+    /// The characters in the glyph atlas in order
+    const GLYPH_CHARS: &'static str = "0123456789-,()";
+    /// The height of a character in world units
+    const CHAR_HEIGHT: f64 = 0.2;
+    /// The width of a character in world units
+    const CHAR_WIDTH: f64 = 0.125;
+
+    /// Creates a new debug overlay without any labels
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// sample_count: The number of samples per pixel
+    pub fn new(render_state: &render::RenderState, sample_count: u32) -> Self {
+        // Create the glyph atlas, each glyph is stored as 8 rows where the lowest bit is the leftmost pixel
+        let glyph_count = Self::GLYPH_CHARS.len();
+        let mut atlas = vec![0u8; 64 * glyph_count];
+        GLYPHS.iter().enumerate().for_each(|(glyph_id, glyph)| {
+            glyph.iter().enumerate().for_each(|(row, bits)| {
+                (0..8).for_each(|column| {
+                    if (bits >> column) & 1 == 1 {
+                        atlas[row * 8 * glyph_count + glyph_id * 8 + column] = 255;
+                    }
+                });
+            });
+        });
+        let size = wgpu::Extent3d {
+            width: 8 * glyph_count as u32,
+            height: 8,
+            depth_or_array_layers: 1,
+        };
+        let texture = render_state
+            .get_device()
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Glyph Texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+        render_state.get_queue().write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &atlas,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(size.width),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+        let sampler = render_state
+            .get_device()
+            .create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Glyph Sampler"),
+                mag_filter: wgpu::FilterMode::Nearest,
+                min_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            });
+
+        // Create the bind group for the glyph atlas
+        let bind_group_layout =
+            render_state
+                .get_device()
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Bind Group Glyph Layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                            count: None,
+                        },
+                    ],
+                });
+        let bind_group = render_state
+            .get_device()
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Bind Group Glyph"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(
+                            &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                        ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                ],
+            });
+
+        // Create the pipeline
+        let shader = wgpu::include_wgsl!("debug.wgsl");
+        let shader = render_state.get_device().create_shader_module(shader);
+        let layout =
+            render_state
+                .get_device()
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Pipeline Layout Descriptor Debug"),
+                    bind_group_layouts: &[
+                        &Uniforms::bind_group_layout(render_state),
+                        &bind_group_layout,
+                    ],
+                    push_constant_ranges: &[],
+                });
+        let pipeline =
+            render_state
+                .get_device()
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Render Pipeline Debug"),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: Some("vs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        buffers: &[LabelVertex::desc()],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: Some("fs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: render_state.get_config().format,
+                            blend: Some(wgpu::BlendState::REPLACE),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: None,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState {
+                        count: sample_count,
+                        mask: !0,
+                        alpha_to_coverage_enabled: false,
+                    },
+                    multiview: None,
+                    cache: None,
+                });
+
+        return Self {
+            pipeline,
+            bind_group,
+            vertices: None,
+            count: 0,
+        };
+    }
+
+    /// Replaces all labels with labels showing the given tile indices
+    /// centered on the given points
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// tiles: The tile index to show and the center of the tile for each label
+    pub fn upload_labels(
+        &mut self,
+        render_state: &render::RenderState,
+        tiles: &[(types::Index, types::Point)],
+    ) {
+        // Create a quad for each character
+        let vertices = tiles
+            .iter()
+            .flat_map(|(index, center)| {
+                let text = format!("{},{}", index.x, index.y);
+                let start = center.x - 0.5 * Self::CHAR_WIDTH * text.len() as f64;
+
+                return text
+                    .chars()
+                    .enumerate()
+                    .filter_map(|(char_id, character)| {
+                        let glyph_id = Self::GLYPH_CHARS.find(character)?;
+                        return Some(LabelVertex::quad(
+                            &types::Point::new(
+                                start + Self::CHAR_WIDTH * char_id as f64,
+                                center.y - 0.5 * Self::CHAR_HEIGHT,
+                            ),
+                            &types::Size::new(Self::CHAR_WIDTH, Self::CHAR_HEIGHT),
+                            glyph_id,
+                            Self::GLYPH_CHARS.len(),
+                        ));
+                    })
+                    .flatten()
+                    .collect::<Vec<LabelVertex>>();
+            })
+            .collect::<Vec<LabelVertex>>();
+
+        // Upload
+        self.count = vertices.len() as u32;
+        self.vertices =
+            if vertices.is_empty() {
+                None
+            } else {
+                Some(render_state.get_device().create_buffer_init(
+                    &wgpu::util::BufferInitDescriptor {
+                        label: Some("Label Vertex Buffer"),
+                        contents: bytemuck::cast_slice(&vertices),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    },
+                ))
+            };
+    }
+
+    /// Draws all labels in the given render pass, the uniforms must already be set
+    ///
+    /// # Parameters
+    ///
+    /// render_pass: The render pass to draw to
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        let vertices = match &self.vertices {
+            Some(value) => value,
+            None => return,
+        };
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, vertices.slice(..));
+        render_pass.draw(0..self.count, 0..1);
+    }
+}
+
+/// Draws the boundaries between chunks as thick lines along the outer edges
+/// of the tiles of each chunk, the lines are generated once from the chunks
+/// of the map
+struct ChunkLines {
+    /// The render pipeline for the lines
+    pipeline: wgpu::RenderPipeline,
+    /// The line color buffer
+    color: wgpu::Buffer,
+    /// The bind group for the line color
+    bind_group: wgpu::BindGroup,
+    /// The buffer holding the quads of all line segments
+    vertices: wgpu::Buffer,
+    /// The number of vertices in the vertex buffer
+    count: u32,
+}
+
+impl ChunkLines {
+    /// The width of the lines in world units
+    const LINE_WIDTH: f64 = 0.15;
+
+    /// Creates the lines for all chunks of the map, the color must be
+    /// written before the first rendering as it is not initialized
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// sample_count: The number of samples per pixel
+    ///
+    /// map: The map to draw the chunk boundaries for
+    fn new(render_state: &render::RenderState, sample_count: u32, map: &map::Map) -> Self {
+        // Find the chunk index of all chunks
+        let data = map.get_data();
+        let indices = data
+            .get_chunks()
+            .filter(|chunk| return *chunk.get_chunk_type() == map::ChunkType::Bulk)
+            .map(|chunk| return data.get_coordinates(chunk.get_chunk_type(), chunk.get_index()))
+            .collect::<Vec<types::Index>>();
+
+        // Edge k of a tile lies between corner k and k + 1 and faces the neighbour in direction k
+        let corners = (0..6)
+            .map(|id| {
+                let angle = std::f64::consts::PI / 3.0 * id as f64;
+                return types::Point::new(angle.cos(), angle.sin()) * INV_SQRT_3;
+            })
+            .collect::<Vec<types::Point>>();
+        let directions = (0..6)
+            .map(|id| {
+                let angle = std::f64::consts::PI / 6.0 + std::f64::consts::PI / 3.0 * id as f64;
+                return types::Point::new(angle.cos(), angle.sin());
+            })
+            .collect::<Vec<types::Point>>();
+
+        let vertices = indices
+            .iter()
+            .flat_map(|index| {
+                // Find all tiles of the bulk and the edges and vertices belonging to it
+                let center = map.chunk_to_coordinate(index);
+                let tiles = map::ChunkType::all()
+                    .iter()
+                    .filter(|chunk_type| return data.get_index(chunk_type, *index).is_some())
+                    .flat_map(|chunk_type| return map.get_tile_centers(chunk_type).iter())
+                    .map(|tile_center| return map::coordinate_to_tile(&(center + tile_center)))
+                    .collect::<HashSet<types::Index>>();
+
+                // Draw every tile edge facing a tile outside of the chunk
+                return tiles
+                    .iter()
+                    .flat_map(|tile| {
+                        let tile_center = map::tile_to_coordinate(tile);
+
+                        return (0..6)
+                            .filter(|id| {
+                                return !tiles.contains(&map::coordinate_to_tile(
+                                    &(tile_center + directions[*id]),
+                                ));
+                            })
+                            .flat_map(|id| {
+                                return Vertex::line_segment(
+                                    &(tile_center + corners[id]),
+                                    &(tile_center + corners[(id + 1) % 6]),
+                                    Self::LINE_WIDTH,
+                                );
+                            })
+                            .collect::<Vec<Vertex>>();
+                    })
+                    .collect::<Vec<Vertex>>();
+            })
+            .collect::<Vec<Vertex>>();
+        let count = vertices.len() as u32;
+        let vertices =
+            render_state
+                .get_device()
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Chunk Line Vertex Buffer"),
+                    contents: bytemuck::cast_slice(&vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+
+        // Create the color buffer and its bind group
+        let color = render_state
+            .get_device()
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Chunk Line Color Buffer"),
+                size: (std::mem::size_of::<f32>() * 4) as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+        let bind_group_layout =
+            render_state
+                .get_device()
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Bind Group Chunk Line Layout"),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                });
+        let bind_group = render_state
+            .get_device()
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Bind Group Chunk Line"),
+                layout: &bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: color.as_entire_binding(),
+                }],
+            });
+
+        // Create the pipeline
+        let shader = wgpu::include_wgsl!("grid.wgsl");
+        let shader = render_state.get_device().create_shader_module(shader);
+        let layout =
+            render_state
+                .get_device()
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Pipeline Layout Descriptor Chunk Lines"),
+                    bind_group_layouts: &[
+                        &Uniforms::bind_group_layout(render_state),
+                        &bind_group_layout,
+                    ],
+                    push_constant_ranges: &[],
+                });
+        let pipeline =
+            render_state
+                .get_device()
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Render Pipeline Chunk Lines"),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: Some("vs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        buffers: &[Vertex::desc_hex()],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: Some("fs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: render_state.get_config().format,
+                            blend: Some(wgpu::BlendState::REPLACE),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: None,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState {
+                        count: sample_count,
+                        mask: !0,
+                        alpha_to_coverage_enabled: false,
+                    },
+                    multiview: None,
+                    cache: None,
+                });
+
+        return Self {
+            pipeline,
+            color,
+            bind_group,
+            vertices,
+            count,
+        };
+    }
+
+    /// Update the line color
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// color: The color to draw the lines with
+    fn write_color(&self, render_state: &render::RenderState, color: &wgpu::Color) {
+        render_state.get_queue().write_buffer(
+            &self.color,
+            0,
+            bytemuck::cast_slice(&[get_color_data(color)]),
+        );
+    }
+
+    /// Draws all lines in the given render pass, the uniforms must already be set
+    ///
+    /// # Parameters
+    ///
+    /// render_pass: The render pass to draw to
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if self.count == 0 {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertices.slice(..));
+        render_pass.draw(0..self.count, 0..1);
+    }
+}
+
+/// Draws the rectangle used for selecting multiple tiles by dragging, the
+/// rectangle is given in screen space and is not affected by the camera
+struct SelectionRect {
+    /// The render pipeline for the rectangle
+    pipeline: wgpu::RenderPipeline,
+    /// The bind group for the color of the inside of the rectangle
+    bind_group_fill: wgpu::BindGroup,
+    /// The bind group for the color of the outline of the rectangle
+    bind_group_outline: wgpu::BindGroup,
+    /// The buffer holding the two triangles of the inside followed by the
+    /// quads of the four sides of the outline
+    vertices: wgpu::Buffer,
+}
+
+impl SelectionRect {
+    /// The color of the inside of the rectangle
+    const COLOR_FILL: wgpu::Color = wgpu::Color {
+        r: 0.3,
+        g: 0.6,
+        b: 1.0,
+        a: 0.25,
+    };
+    /// The color of the outline of the rectangle
+    const COLOR_OUTLINE: wgpu::Color = wgpu::Color {
+        r: 0.3,
+        g: 0.6,
+        b: 1.0,
+        a: 1.0,
+    };
+    /// The width of the outline in normalized device coordinates
+    const OUTLINE_WIDTH: f64 = 0.005;
+    /// The number of vertices for the inside of the rectangle
+    const COUNT_FILL: u32 = 6;
+    /// The number of vertices for the outline of the rectangle
+    const COUNT_OUTLINE: u32 = 24;
+
+    /// Creates the pipeline and buffers for the rectangle, the vertices must
+    /// be written before the first rendering as they are not initialized
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// sample_count: The number of samples per pixel
+    fn new(render_state: &render::RenderState, sample_count: u32) -> Self {
+        // Create the vertex buffer
+        let vertices = render_state
+            .get_device()
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Selection Vertex Buffer"),
+                size: (std::mem::size_of::<Vertex>()
+                    * (Self::COUNT_FILL + Self::COUNT_OUTLINE) as usize)
+                    as u64,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        // Create the color buffers and their bind groups
+        let bind_group_layout =
+            render_state
+                .get_device()
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Bind Group Selection Layout"),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                });
+        let create_bind_group = |label: &str, color: &wgpu::Color| {
+            let buffer =
+                render_state
+                    .get_device()
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some(label),
+                        contents: bytemuck::cast_slice(&[get_color_data(color)]),
+                        usage: wgpu::BufferUsages::UNIFORM,
+                    });
+            return render_state
+                .get_device()
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some(label),
+                    layout: &bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding(),
+                    }],
+                });
+        };
+        let bind_group_fill = create_bind_group("Selection Fill Color", &Self::COLOR_FILL);
+        let bind_group_outline = create_bind_group("Selection Outline Color", &Self::COLOR_OUTLINE);
+
+        // Create the pipeline
+        let shader = wgpu::include_wgsl!("selection.wgsl");
+        let shader = render_state.get_device().create_shader_module(shader);
+        let layout =
+            render_state
+                .get_device()
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Pipeline Layout Descriptor Selection"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                });
+        let pipeline =
+            render_state
+                .get_device()
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Render Pipeline Selection"),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: Some("vs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        buffers: &[Vertex::desc_hex()],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: Some("fs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: render_state.get_config().format,
+                            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: None,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState {
+                        count: sample_count,
+                        mask: !0,
+                        alpha_to_coverage_enabled: false,
+                    },
+                    multiview: None,
+                    cache: None,
+                });
+
+        return Self {
+            pipeline,
+            bind_group_fill,
+            bind_group_outline,
+            vertices,
+        };
+    }
+
+    /// Uploads the vertices for the given rectangle
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// rect: The rectangle in normalized device coordinates, it must have a
+    /// non-zero width and height
+    fn write_rect(&self, render_state: &render::RenderState, rect: &types::View) {
+        // Find the corners going counter clockwise from the bottom left
+        let center = rect.get_center();
+        let half_size = *rect.get_size() * 0.5;
+        let corners = [
+            types::Point::new(center.x - half_size.w, center.y - half_size.h),
+            types::Point::new(center.x + half_size.w, center.y - half_size.h),
+            types::Point::new(center.x + half_size.w, center.y + half_size.h),
+            types::Point::new(center.x - half_size.w, center.y + half_size.h),
+        ];
+
+        // The inside is two triangles followed by the four sides
+        let fill = [0, 1, 2, 0, 2, 3].map(|id| {
+            return Vertex {
+                position: corners[id].get_data(),
+            };
+        });
+        let vertices = fill
+            .into_iter()
+            .chain((0..4).flat_map(|id| {
+                return Vertex::line_segment(
+                    &corners[id],
+                    &corners[(id + 1) % 4],
+                    Self::OUTLINE_WIDTH,
+                );
+            }))
+            .collect::<Vec<Vertex>>();
+
+        render_state
+            .get_queue()
+            .write_buffer(&self.vertices, 0, bytemuck::cast_slice(&vertices));
+    }
+
+    /// Draws the inside and then the outline of the rectangle in the given render pass
+    ///
+    /// # Parameters
+    ///
+    /// render_pass: The render pass to draw to
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertices.slice(..));
+
+        // Draw the inside
+        render_pass.set_bind_group(0, &self.bind_group_fill, &[]);
+        render_pass.draw(0..Self::COUNT_FILL, 0..1);
+
+        // Draw the outline
+        render_pass.set_bind_group(0, &self.bind_group_outline, &[]);
+        render_pass.draw(
+            Self::COUNT_FILL..Self::COUNT_FILL + Self::COUNT_OUTLINE,
+            0..1,
+        );
+    }
+}
+
+/// Draws the x and y axes of the world as lines through the origin with an
+/// arrow head at the positive end, the lines are given a constant width on
+/// the screen
+struct AxesOverlay {
+    /// The render pipeline for the axes
+    pipeline: wgpu::RenderPipeline,
+    /// The buffer holding the color of the x axis
+    color_x: wgpu::Buffer,
+    /// The buffer holding the color of the y axis
+    color_y: wgpu::Buffer,
+    /// The bind group for the color of the x axis
+    bind_group_x: wgpu::BindGroup,
+    /// The bind group for the color of the y axis
+    bind_group_y: wgpu::BindGroup,
+    /// The buffer holding the segments of the x axis followed by the
+    /// segments of the y axis
+    vertices: wgpu::Buffer,
+}
+
+impl AxesOverlay {
+    /// The color of the x axis
+    const COLOR_X: wgpu::Color = wgpu::Color {
+        r: 1.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    };
+    /// The color of the y axis
+    const COLOR_Y: wgpu::Color = wgpu::Color {
+        r: 0.0,
+        g: 0.0,
+        b: 1.0,
+        a: 1.0,
+    };
+    /// The width of the lines in normalized device coordinates
+    const LINE_WIDTH: f64 = 0.005;
+    /// The length of the sides of the arrow heads in normalized device coordinates
+    const ARROW_LENGTH: f64 = 0.04;
+    /// The angle between each side of the arrow heads and the axis
+    const ARROW_ANGLE: f64 = std::f64::consts::PI / 6.0;
+    /// The length of an axis in normalized device coordinates at which the
+    /// axes start to fade out
+    const FADE_START: f64 = 20.0;
+    /// The length of an axis in normalized device coordinates at which the
+    /// axes are no longer visible
+    const FADE_END: f64 = 40.0;
+    /// The number of vertices for each axis, the axis and the two sides of
+    /// the arrow head
+    const COUNT_AXIS: u32 = 18;
+
+    /// Creates the pipeline and buffers for the axes, the vertices must be
+    /// written before the first rendering as they are not initialized
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// sample_count: The number of samples per pixel
+    fn new(render_state: &render::RenderState, sample_count: u32) -> Self {
+        // Create the vertex buffer
+        let vertices = render_state
+            .get_device()
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Axes Vertex Buffer"),
+                size: (std::mem::size_of::<Vertex>() * 2 * Self::COUNT_AXIS as usize) as u64,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        // Create the color buffers and their bind groups
+        let bind_group_layout =
+            render_state
+                .get_device()
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Bind Group Axes Layout"),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                });
+        let create_color = |label: &str, color: &wgpu::Color| {
+            let buffer =
+                render_state
+                    .get_device()
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some(label),
+                        contents: bytemuck::cast_slice(&[get_color_data(color)]),
+                        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    });
+            let bind_group =
+                render_state
+                    .get_device()
+                    .create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some(label),
+                        layout: &bind_group_layout,
+                        entries: &[wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: buffer.as_entire_binding(),
+                        }],
+                    });
+            return (buffer, bind_group);
+        };
+        let (color_x, bind_group_x) = create_color("Axes X Color", &Self::COLOR_X);
+        let (color_y, bind_group_y) = create_color("Axes Y Color", &Self::COLOR_Y);
+
+        // Create the pipeline, the axes are drawn in screen space like the selection
+        let shader = wgpu::include_wgsl!("selection.wgsl");
+        let shader = render_state.get_device().create_shader_module(shader);
+        let layout =
+            render_state
+                .get_device()
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Pipeline Layout Descriptor Axes"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                });
+        let pipeline =
+            render_state
+                .get_device()
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Render Pipeline Axes"),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: Some("vs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        buffers: &[Vertex::desc_hex()],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: Some("fs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: render_state.get_config().format,
+                            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: None,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState {
+                        count: sample_count,
+                        mask: !0,
+                        alpha_to_coverage_enabled: false,
+                    },
+                    multiview: None,
+                    cache: None,
+                });
+
+        return Self {
+            pipeline,
+            color_x,
+            color_y,
+            bind_group_x,
+            bind_group_y,
+            vertices,
+        };
+    }
+
+    /// Uploads the vertices and the faded colors of the axes for the given
+    /// transform, returns false if the axes are not visible and nothing was
+    /// uploaded
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// transform: The transform to go from world to screen coordinates
+    ///
+    /// length: The length of each axis in world coordinates
+    fn write_axes(
+        &self,
+        render_state: &render::RenderState,
+        transform: &types::Transform2D,
+        length: f64,
+    ) -> bool {
+        // Fade out the axes when they become much larger than the screen
+        let screen_length = transform.get_center_transform().det().abs().sqrt() * length;
+        let alpha = ((Self::FADE_END - screen_length) / (Self::FADE_END - Self::FADE_START))
+            .clamp(0.0, 1.0);
+        if alpha <= 0.0 || screen_length <= 0.0 {
+            return false;
+        }
+
+        // Create the line through the origin and the arrow head at the positive end
+        let axis_vertices = |direction: types::Point| {
+            let start = transform * (direction * -length);
+            let end = transform * (direction * length);
+            let back = (start - end) * (Self::ARROW_LENGTH / start.distance_to(&end));
+            let arrow_side = |angle: f64| {
+                return types::Point::new(
+                    back.x * angle.cos() - back.y * angle.sin(),
+                    back.x * angle.sin() + back.y * angle.cos(),
+                );
+            };
+
+            return [
+                Vertex::line_segment(&start, &end, Self::LINE_WIDTH),
+                Vertex::line_segment(
+                    &end,
+                    &(end + arrow_side(Self::ARROW_ANGLE)),
+                    Self::LINE_WIDTH,
+                ),
+                Vertex::line_segment(
+                    &end,
+                    &(end + arrow_side(-Self::ARROW_ANGLE)),
+                    Self::LINE_WIDTH,
+                ),
+            ];
+        };
+        let vertices = axis_vertices(types::Point::new(1.0, 0.0))
+            .into_iter()
+            .chain(axis_vertices(types::Point::new(0.0, 1.0)))
+            .flatten()
+            .collect::<Vec<Vertex>>();
+
+        render_state
+            .get_queue()
+            .write_buffer(&self.vertices, 0, bytemuck::cast_slice(&vertices));
+
+        // Update the colors
+        let faded = |color: wgpu::Color| {
+            return get_color_data(&wgpu::Color {
+                a: color.a * alpha,
+                ..color
+            });
+        };
+        render_state.get_queue().write_buffer(
+            &self.color_x,
+            0,
+            bytemuck::cast_slice(&[faded(Self::COLOR_X)]),
+        );
+        render_state.get_queue().write_buffer(
+            &self.color_y,
+            0,
+            bytemuck::cast_slice(&[faded(Self::COLOR_Y)]),
+        );
+
+        return true;
+    }
+
+    /// Draws the x axis and then the y axis in the given render pass
+    ///
+    /// # Parameters
+    ///
+    /// render_pass: The render pass to draw to
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertices.slice(..));
+
+        // Draw the x axis
+        render_pass.set_bind_group(0, &self.bind_group_x, &[]);
+        render_pass.draw(0..Self::COUNT_AXIS, 0..1);
+
+        // Draw the y axis
+        render_pass.set_bind_group(0, &self.bind_group_y, &[]);
+        render_pass.draw(Self::COUNT_AXIS..2 * Self::COUNT_AXIS, 0..1);
+    }
+}
+
+/// Draws bars in screen space on top of the map, like the resource bars of
+/// organisms, the bars are queued and drawn together in a single draw call
+pub struct HudRenderer {
+    /// The render pipeline for the bars
+    pipeline: wgpu::RenderPipeline,
+    /// The buffer holding the two triangles of a unit square
+    vertices: wgpu::Buffer,
+    /// All bars queued for the next draw
+    bars: RefCell<Vec<BarInstance>>,
+}
+
+impl HudRenderer {
+    /// Creates the pipeline and buffers for the bars
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// sample_count: The number of samples per pixel
+    fn new(render_state: &render::RenderState, sample_count: u32) -> Self {
+        // Create the unit square
+        let corners = [
+            [0.0, 0.0],
+            [1.0, 0.0],
+            [1.0, 1.0],
+            [0.0, 0.0],
+            [1.0, 1.0],
+            [0.0, 1.0],
+        ]
+        .map(|position| return Vertex { position });
+        let vertices =
+            render_state
+                .get_device()
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Hud Vertex Buffer"),
+                    contents: bytemuck::cast_slice(&corners),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+
+        // Create the pipeline
+        let shader = wgpu::include_wgsl!("hud.wgsl");
+        let shader = render_state.get_device().create_shader_module(shader);
+        let layout =
+            render_state
+                .get_device()
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Pipeline Layout Descriptor Hud"),
+                    bind_group_layouts: &[],
+                    push_constant_ranges: &[],
+                });
+        let pipeline =
+            render_state
+                .get_device()
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Render Pipeline Hud"),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: Some("vs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        buffers: &[Vertex::desc_hex(), BarInstance::desc()],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: Some("fs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: render_state.get_config().format,
+                            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: None,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState {
+                        count: sample_count,
+                        mask: !0,
+                        alpha_to_coverage_enabled: false,
+                    },
+                    multiview: None,
+                    cache: None,
+                });
+
+        return Self {
+            pipeline,
+            vertices,
+            bars: RefCell::new(Vec::new()),
+        };
+    }
+
+    /// Queues a bar to be drawn at the next flush
+    ///
+    /// # Parameters
+    ///
+    /// center_ndc: The center of the bar in normalized device coordinates
+    ///
+    /// width: The width of the bar in normalized device coordinates
+    ///
+    /// height: The height of the bar in normalized device coordinates
+    ///
+    /// fill: The fraction of the bar to fill from the left, it is clamped to [0, 1]
+    ///
+    /// color: The color of the filled part of the bar
+    pub fn add_bar(
+        &self,
+        center_ndc: [f32; 2],
+        width: f32,
+        height: f32,
+        fill: f32,
+        color: [f32; 4],
+    ) {
+        self.bars.borrow_mut().push(BarInstance {
+            center: center_ndc,
+            size: [width, height],
+            fill: fill.clamp(0.0, 1.0),
+            color,
+        });
+    }
+
+    /// Draws all queued bars in a single instanced draw call on top of the
+    /// view and clears the queue
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// view: The texture view to render to
+    ///
+    /// multisample: The multisampled texture to render to before resolving
+    fn flush(
+        &self,
+        render_state: &render::RenderState,
+        view: &wgpu::TextureView,
+        multisample: &Multisample,
+    ) {
+        let bars = std::mem::take(&mut *self.bars.borrow_mut());
+        if bars.is_empty() {
+            return;
+        }
+
+        // Upload the bars
+        let instances =
+            render_state
+                .get_device()
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Hud Instance Buffer"),
+                    contents: bytemuck::cast_slice(&bars),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+
+        // Create the encoder
+        let mut encoder =
+            render_state
+                .get_device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Command Encoder Hud"),
+                });
+
+        // Initialize the render pass
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass Hud"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: multisample.get_view().unwrap_or(view),
+                    resolve_target: multisample.get_view().map(|_| view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            // Draw all bars
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_vertex_buffer(0, self.vertices.slice(..));
+            render_pass.set_vertex_buffer(1, instances.slice(..));
+            render_pass.draw(0..6, 0..bars.len() as u32);
+        }
+
+        // Submit
+        render_state
+            .get_queue()
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+/// Draws organisms as circles at their positions in the world, the
+/// instances are uploaded every frame as the organisms move
+struct OrganismRenderer {
+    /// The render pipeline for the circles
+    pipeline: wgpu::RenderPipeline,
+    /// The buffer holding the triangles of an octagon around the unit circle
+    vertices: wgpu::Buffer,
+    /// The instances of the latest upload, it is replaced by a larger buffer
+    /// when there are more visible organisms than it can hold
+    instances: RefCell<InstanceBuffer<OrganismVertex>>,
+}
+
+impl OrganismRenderer {
+    /// The radius of an organism in world units
+    const RADIUS: f64 = 0.2;
+    /// The number of vertices in the triangle list, the octagon is drawn as
+    /// a fan of 6 triangles
+    const COUNT_VERTEX: u32 = 18;
+    /// The number of organisms the instance buffer can hold initially
+    const INITIAL_CAPACITY: usize = 1024;
+
+    /// Creates the pipeline and buffers for the circles
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// sample_count: The number of samples per pixel
+    fn new(render_state: &render::RenderState, sample_count: u32) -> Self {
+        // Create a fan of triangles for an octagon with the unit circle inside
+        let radius = 1.0 / (std::f64::consts::PI / 8.0).cos();
+        let corners = (0..8)
+            .map(|id| {
+                let angle = std::f64::consts::PI / 4.0 * id as f64;
+                return Vertex {
+                    position: (types::Point::new(angle.cos(), angle.sin()) * radius).get_data(),
+                };
+            })
+            .collect::<Vec<Vertex>>();
+        let vertices = (1..7)
+            .flat_map(|id| return [corners[0], corners[id], corners[id + 1]])
+            .collect::<Vec<Vertex>>();
+        let vertices =
+            render_state
+                .get_device()
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Organism Vertex Buffer"),
+                    contents: bytemuck::cast_slice(&vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+
+        // Create the pipeline
+        let shader = wgpu::include_wgsl!("organism.wgsl");
+        let shader = render_state.get_device().create_shader_module(shader);
+        let layout =
+            render_state
+                .get_device()
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Pipeline Layout Descriptor Organisms"),
+                    bind_group_layouts: &[&Uniforms::bind_group_layout(render_state)],
+                    push_constant_ranges: &[],
+                });
+        let pipeline =
+            render_state
+                .get_device()
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Render Pipeline Organisms"),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: Some("vs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        buffers: &[Vertex::desc_hex(), OrganismVertex::desc()],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: Some("fs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: render_state.get_config().format,
+                            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: None,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState {
+                        count: sample_count,
+                        mask: !0,
+                        alpha_to_coverage_enabled: false,
+                    },
+                    multiview: None,
+                    cache: None,
+                });
+
+        return Self {
+            pipeline,
+            vertices,
+            instances: RefCell::new(InstanceBuffer::new(
+                render_state.get_device(),
+                Self::INITIAL_CAPACITY,
+            )),
+        };
+    }
+
+    /// Uploads the organisms which are at least partly on the screen and
+    /// returns the number of uploaded organisms
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// transform: The transform to go from world to screen coordinates
+    ///
+    /// organisms: The organisms to upload
+    fn write_organisms(
+        &self,
+        render_state: &render::RenderState,
+        transform: &types::Transform2D,
+        organisms: &[simulation::Organism],
+    ) -> u32 {
+        // The largest radius on the screen is at most the radius scaled by the frobenius norm
+        let matrix = transform.get_center_transform();
+        let scale = matrix
+            .values
+            .iter()
+            .flatten()
+            .map(|value| return value * value)
+            .sum::<f64>()
+            .sqrt();
+        let margin = 1.0 + Self::RADIUS * scale;
+
+        // Cull the organisms outside the screen
+        let instances = organisms
+            .iter()
+            .filter(|organism| {
+                let position = *transform * organism.position;
+                return position.x.abs() <= margin && position.y.abs() <= margin;
             })
+            .map(|organism| {
+                return OrganismVertex {
+                    center: organism.position.get_data(),
+                    color: Self::get_color(&organism.traits),
+                    radius: Self::RADIUS as f32,
+                };
+            })
+            .collect::<Vec<OrganismVertex>>();
+
+        // Grow the buffer if the organisms do not fit
+        let mut buffer = self.instances.borrow_mut();
+        if instances.len() > buffer.get_capacity() {
+            *buffer = InstanceBuffer::new(
+                render_state.get_device(),
+                instances.len().max(2 * buffer.get_capacity()),
+            );
+        }
+
+        buffer.clear();
+        instances.iter().for_each(|instance| {
+            buffer.push(*instance).expect("Should not happen");
+        });
+        buffer.flush(render_state.get_queue());
+
+        return instances.len() as u32;
+    }
+
+    /// Finds the color of an organism from the resource it consumes the most
+    /// of using the same colors as the composite color mode
+    ///
+    /// # Parameters
+    ///
+    /// traits: The traits of the organism
+    fn get_color(traits: &simulation::OrganismTraits) -> [f32; 4] {
+        if traits.consumption_nutrients >= traits.consumption_energy
+            && traits.consumption_nutrients >= traits.consumption_water
+        {
+            return [0.8, 0.0, 0.0, 1.0];
+        }
+        if traits.consumption_energy >= traits.consumption_water {
+            return [0.0, 0.8, 0.0, 1.0];
+        }
+        return [0.0, 0.0, 0.8, 1.0];
+    }
+
+    /// Draws the latest uploaded organisms, the main uniforms must already be
+    /// set
+    ///
+    /// # Parameters
+    ///
+    /// render_pass: The render pass to draw to
+    ///
+    /// count: The number of organisms returned by write_organisms
+    fn draw(&self, render_pass: &mut wgpu::RenderPass, count: u32) {
+        let instances = self.instances.borrow();
+        if instances.get_data().is_empty() {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertices.slice(..));
+        render_pass.set_vertex_buffer(1, instances.get_buffer().slice(..));
+        render_pass.draw(0..Self::COUNT_VERTEX, 0..count);
     }
 }
 
+/// The 8x8 bitmaps for the characters of the debug overlay in the same order
+/// as DebugOverlay::GLYPH_CHARS, each byte is a row from the top and the
+/// lowest bit is the leftmost pixel
+const GLYPHS: [[u8; 8]; 14] = [
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00],
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00],
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00],
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00],
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00],
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00],
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00],
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00],
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00],
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00],
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06],
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00],
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00],
+];
+
 /// Holds GPU buffers for the vertex data to draw a single hexagon
 struct BuffersHex {
     /// The buffer holding all six vertices of the hex
     vertices: wgpu::Buffer,
     /// The 12 indices describing all 4 triangles of the hex
     indices_bulk: wgpu::Buffer,
-    /// The 7 indices describing all 6 edge pieces of the hex
-    indices_edge: wgpu::Buffer,
 }
 
 impl BuffersHex {
@@ -508,48 +3315,348 @@ impl BuffersHex {
                     usage: wgpu::BufferUsages::INDEX,
                 });
 
-        // Create the indices for the bulk
-        let indices_edge =
-            render_state
-                .get_device()
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Hex Edge Index Buffer"),
-                    contents: bytemuck::cast_slice(&Vertex::indices_edge_hex()),
-                    usage: wgpu::BufferUsages::INDEX,
-                });
-
         Self {
             vertices,
             indices_bulk,
-            indices_edge,
         }
     }
 
-    /// Sets the hexagon vertex information for the given render pass
+    /// Sets the hexagon vertex information for the given render pass and
+    /// returns the number of indices
     ///
     /// # Parameters
     ///
     /// render_pass: The render pass to set the vertex info for
-    ///
-    /// draw_mode: The mode describing whether to draw in fill or edge mode
-    fn set<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, draw_mode: DrawMode) -> u32 {
-        // Set the vertex buffer
+    fn set<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) -> u32 {
+        // Set the vertex and index buffers
         render_pass.set_vertex_buffer(0, self.vertices.slice(..));
+        render_pass.set_index_buffer(self.indices_bulk.slice(..), wgpu::IndexFormat::Uint16);
 
-        // Set the index buffer and return the number of indices
-        return match draw_mode {
-            DrawMode::Fill => {
-                render_pass
-                    .set_index_buffer(self.indices_bulk.slice(..), wgpu::IndexFormat::Uint16);
-                Vertex::COUNT_INDEX_BULK_HEX as u32
-            }
-            DrawMode::Edge => {
-                render_pass
-                    .set_index_buffer(self.indices_edge.slice(..), wgpu::IndexFormat::Uint16);
-                Vertex::COUNT_INDEX_EDGE_HEX as u32
+        return Vertex::COUNT_INDEX_BULK_HEX as u32;
+    }
+}
+
+/// A gpu buffer of instance data together with a copy of the data on the
+/// cpu, changes to the data are only uploaded when the buffer is flushed and
+/// only the items which have been marked as dirty are uploaded
+pub struct InstanceBuffer<T: bytemuck::Pod> {
+    /// The buffer on the gpu
+    buffer: wgpu::Buffer,
+    /// The data on the cpu, it never holds more items than the capacity
+    data: Vec<T>,
+    /// The ranges of items which have changed since they were last uploaded
+    dirty: RefCell<Vec<Range<usize>>>,
+}
+
+impl<T: bytemuck::Pod> InstanceBuffer<T> {
+    /// Creates a new empty instance buffer, the size of T must be a multiple
+    /// of 4 bytes to allow uploading single items
+    ///
+    /// # Parameters
+    ///
+    /// device: The device to create the buffer on
+    ///
+    /// capacity: The largest number of items the buffer can hold
+    pub fn new(device: &wgpu::Device, capacity: usize) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: (std::mem::size_of::<T>() * capacity) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        return Self {
+            buffer,
+            data: Vec::with_capacity(capacity),
+            dirty: RefCell::new(Vec::new()),
+        };
+    }
+
+    /// Adds an item to the end of the data and marks it as dirty
+    ///
+    /// # Parameters
+    ///
+    /// item: The item to add
+    ///
+    /// # Errors
+    ///
+    /// See InstanceBufferError for a description of the different errors
+    /// which may occur
+    pub fn push(&mut self, item: T) -> Result<(), InstanceBufferError> {
+        if self.data.len() >= self.get_capacity() {
+            return Err(InstanceBufferError::Full(self.get_capacity()));
+        }
+
+        self.data.push(item);
+        self.mark_dirty_range(self.data.len() - 1..self.data.len());
+
+        return Ok(());
+    }
+
+    /// Removes all items, nothing has to be uploaded afterwards
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.dirty.borrow_mut().clear();
+    }
+
+    /// Marks all items as changed such that they are uploaded at the next
+    /// flush
+    pub fn mark_dirty(&self) {
+        self.mark_dirty_range(0..self.data.len());
+    }
+
+    /// Marks a range of items as changed such that they are uploaded at the
+    /// next flush
+    ///
+    /// # Parameters
+    ///
+    /// range: The indices of the changed items
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if the range goes beyond the data
+    pub fn mark_dirty_range(&self, range: Range<usize>) {
+        if cfg!(debug_assertions) && range.end > self.data.len() {
+            panic!(
+                "The dirty range {:?} goes beyond the {:?} items",
+                range,
+                self.data.len()
+            );
+        }
+
+        if !range.is_empty() {
+            self.dirty.borrow_mut().push(range);
+        }
+    }
+
+    /// Uploads the items which have changed since the last upload, ranges
+    /// which overlap or touch are uploaded together
+    ///
+    /// # Parameters
+    ///
+    /// queue: The queue to upload with
+    pub fn flush(&self, queue: &wgpu::Queue) {
+        let mut ranges = self.dirty.take();
+        ranges.sort_by_key(|range| return range.start);
+
+        // Merge the ranges
+        let merged = ranges
+            .into_iter()
+            .fold(Vec::<Range<usize>>::new(), |mut merged, range| {
+                match merged.last_mut() {
+                    Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                    _ => merged.push(range),
+                }
+                return merged;
+            });
+
+        // Upload each range, the data may have been cleared since it was marked
+        let item_size = std::mem::size_of::<T>();
+        merged.into_iter().for_each(|range| {
+            let range = range.start.min(self.data.len())..range.end.min(self.data.len());
+            if range.is_empty() {
+                return;
             }
+
+            queue.write_buffer(
+                &self.buffer,
+                (range.start * item_size) as u64,
+                bytemuck::cast_slice(&self.data[range]),
+            );
+        });
+    }
+
+    /// Retrieves the buffer on the gpu
+    pub fn get_buffer(&self) -> &wgpu::Buffer {
+        return &self.buffer;
+    }
+
+    /// Retrieves the data on the cpu
+    pub fn get_data(&self) -> &[T] {
+        return &self.data;
+    }
+
+    /// Retrieves the data on the cpu as mutable, mark_dirty or
+    /// mark_dirty_range must be run after changing it for the changes to be
+    /// uploaded
+    pub fn get_data_mut(&mut self) -> &mut [T] {
+        return &mut self.data;
+    }
+
+    /// Retrieves the largest number of items the buffer can hold
+    pub fn get_capacity(&self) -> usize {
+        return self.buffer.size() as usize / std::mem::size_of::<T>();
+    }
+}
+
+/// Holds the instance data for all tiles and the location of each chunk
+/// within it
+struct BuffersInstance {
+    /// The instance data for all tiles
+    instances: InstanceBuffer<Instance>,
+    /// The location of each chunk in the instance buffer
+    slots: HashMap<(map::ChunkType, usize), ChunkSlot>,
+}
+
+impl BuffersInstance {
+    /// Creates a new instance buffer large enough to hold all tiles of the map
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    ///
+    /// map: The map to create the instance buffer for
+    fn new(render_state: &render::RenderState, map: &map::Map) -> Self {
+        // Find the location of all chunks
+        let mut count = 0;
+        let slots = map
+            .get_data()
+            .get_chunks()
+            .map(|chunk| {
+                let slot = ChunkSlot {
+                    offset: count,
+                    center: map.chunk_to_coordinate(
+                        &map.get_data()
+                            .get_coordinates(chunk.get_chunk_type(), chunk.get_index()),
+                    ),
+                };
+                count += chunk.get_tiles().len();
+
+                return ((*chunk.get_chunk_type(), chunk.get_index()), slot);
+            })
+            .collect::<HashMap<(map::ChunkType, usize), ChunkSlot>>();
+
+        // Create the buffer, the tiles are filled in when the chunks are written
+        let mut instances = InstanceBuffer::new(render_state.get_device(), count);
+        (0..count).for_each(|_| {
+            instances
+                .push(bytemuck::Zeroable::zeroed())
+                .expect("Should not happen");
+        });
+
+        return Self { instances, slots };
+    }
+
+    /// Writes the instance data for all tiles of the given chunk, it is
+    /// uploaded at the next flush
+    ///
+    /// # Parameters
+    ///
+    /// chunk: The chunk to write
+    ///
+    /// geometry: The geometry of the chunks of the map
+    ///
+    /// color_mode: The way to convert the resources of the tiles to colors
+    ///
+    /// colormaps: The colormaps used when showing a single resource
+    ///
+    /// gradient_magnitudes: The normalized gradient magnitudes of all tiles in
+    /// the order of the instance buffer, only used when showing a gradient
+    fn write_chunk(
+        &mut self,
+        chunk: &map::Chunk,
+        geometry: &map::ChunkGeometry,
+        color_mode: TileColorMode,
+        colormaps: &Colormaps,
+        gradient_magnitudes: &[f64],
+    ) {
+        // Find the location of the chunk
+        let slot = match self
+            .slots
+            .get(&(*chunk.get_chunk_type(), chunk.get_index()))
+        {
+            Some(value) => value,
+            None => return,
+        };
+
+        // Get the instance data
+        let instances = chunk
+            .iter_tiles_with_center(geometry)
+            .enumerate()
+            .map(|(tile_id, (tile, center))| {
+                let gradient_magnitude = gradient_magnitudes
+                    .get(slot.offset + tile_id)
+                    .copied()
+                    .unwrap_or(0.0);
+
+                return Instance {
+                    center: (slot.center + center).get_data(),
+                    color: get_tile_color_data(tile, color_mode, colormaps, gradient_magnitude),
+                };
+            })
+            .collect::<Vec<Instance>>();
+
+        // Store and upload only the tiles of this chunk
+        let range = slot.offset..slot.offset + instances.len();
+        self.instances.get_data_mut()[range.clone()].copy_from_slice(&instances);
+        self.instances.mark_dirty_range(range);
+    }
+
+    /// Uploads the instance data of all tiles if any chunks have been written
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to use for rendering
+    fn flush(&self, render_state: &render::RenderState) {
+        self.instances.flush(render_state.get_queue());
+    }
+
+    /// Sets the instance buffer for the given render pass and returns the
+    /// number of instances
+    ///
+    /// # Parameters
+    ///
+    /// render_pass: The render pass to set the instance buffer for
+    fn set<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) -> u32 {
+        render_pass.set_vertex_buffer(1, self.instances.get_buffer().slice(..));
+
+        return self.instances.get_data().len() as u32;
+    }
+}
+
+/// The location of a single chunk in the instance buffer
+#[derive(Clone, Copy, Debug)]
+struct ChunkSlot {
+    /// The index of the first tile of the chunk
+    offset: usize,
+    /// The center of the chunk in world coordinates
+    center: types::Point,
+}
+
+/// Keeps track of all chunks which have been updated on the gpu
+#[derive(Clone, Debug)]
+struct DirtySet {
+    /// The type and index of all dirty chunks
+    chunks: HashSet<(map::ChunkType, usize)>,
+}
+
+impl DirtySet {
+    /// Creates a new empty dirty set
+    fn new() -> Self {
+        return Self {
+            chunks: HashSet::new(),
         };
     }
+
+    /// Marks a chunk as dirty
+    ///
+    /// # Parameters
+    ///
+    /// chunk: The chunk to mark
+    fn insert(&mut self, chunk: &map::Chunk) {
+        self.chunks
+            .insert((*chunk.get_chunk_type(), chunk.get_index()));
+    }
+
+    /// Checks if no chunks are dirty
+    fn is_empty(&self) -> bool {
+        return self.chunks.is_empty();
+    }
+
+    /// Removes all dirty chunks
+    fn clear(&mut self) {
+        self.chunks.clear();
+    }
 }
 
 /// Describes a single vertex in the gpu
@@ -563,7 +3670,6 @@ pub struct Vertex {
 impl Vertex {
     const COUNT_VERTEX_HEX: usize = 6;
     const COUNT_INDEX_BULK_HEX: usize = 12;
-    const COUNT_INDEX_EDGE_HEX: usize = 7;
 
     /// Gets the memory description of a hex vertex
     fn desc_hex() -> wgpu::VertexBufferLayout<'static> {
@@ -602,32 +3708,403 @@ impl Vertex {
         ]
     }
 
+    /// Generates the two triangles for a thick line segment, the segment is
+    /// extended by half the width at both ends such that connected segments
+    /// overlap at the corners
+    ///
+    /// # Parameters
+    ///
+    /// start: The start point of the segment
+    ///
+    /// end: The end point of the segment
+    ///
+    /// width: The width of the line
+    fn line_segment(start: &types::Point, end: &types::Point, width: f64) -> [Self; 6] {
+        let direction = (*end - *start) * (0.5 * width / start.distance_to(end));
+        let normal = direction.perpendicular();
+        let vertex = |point: types::Point| {
+            return Self {
+                position: point.get_data(),
+            };
+        };
+        let start_right = vertex(*start - direction - normal);
+        let start_left = vertex(*start - direction + normal);
+        let end_right = vertex(*end + direction - normal);
+        let end_left = vertex(*end + direction + normal);
+
+        return [
+            start_right,
+            end_right,
+            end_left,
+            start_right,
+            end_left,
+            start_left,
+        ];
+    }
+
     /// Generates indices for the vertices for the bulk of a hexagon
     const fn indices_bulk_hex() -> [u16; Self::COUNT_INDEX_BULK_HEX] {
         [2, 3, 4, 2, 4, 5, 1, 2, 5, 0, 1, 5]
     }
+}
+
+/// Describes a single tile when exporting a resource texture
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ResourcePixel {
+    /// The center of the pixel in normalized device coordinates
+    position: [f32; 2],
+    /// The value of the resource
+    value: f32,
+}
+
+impl ResourcePixel {
+    /// Gets the memory description of a resource pixel
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ],
+        }
+    }
+
+    /// Creates the pipeline drawing each resource pixel as a single point
+    /// into a R8Unorm texture
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state to create the pipeline for
+    fn pipeline(render_state: &render::RenderState) -> wgpu::RenderPipeline {
+        let shader = wgpu::include_wgsl!("resource.wgsl");
+        let shader = render_state.get_device().create_shader_module(shader);
+        let layout =
+            render_state
+                .get_device()
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Pipeline Layout Descriptor Resource Texture"),
+                    bind_group_layouts: &[],
+                    push_constant_ranges: &[],
+                });
+        return render_state
+            .get_device()
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Render Pipeline Resource Texture"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[Self::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_main"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: wgpu::TextureFormat::R8Unorm,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::PointList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+                cache: None,
+            });
+    }
+}
+
+/// Describes the instance data for a single tile in the gpu
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Instance {
+    /// The center of the tile
+    center: [f32; 2],
+    /// The color to fill the tile with
+    color: [f32; 4],
+}
+
+impl Instance {
+    /// Gets the memory description of a tile instance
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Describes the instance data for a single organism in the gpu
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct OrganismVertex {
+    /// The position of the organism in world coordinates
+    center: [f32; 2],
+    /// The color to fill the circle with
+    color: [f32; 4],
+    /// The radius of the circle in world units
+    radius: f32,
+}
+
+impl OrganismVertex {
+    /// Gets the memory description of an organism instance
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ],
+        }
+    }
+}
+
+/// Describes the instance data for a single bar of the hud in the gpu
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct BarInstance {
+    /// The center of the bar in normalized device coordinates
+    center: [f32; 2],
+    /// The width and height of the bar in normalized device coordinates
+    size: [f32; 2],
+    /// The fraction of the bar which is filled
+    fill: f32,
+    /// The color of the filled part of the bar
+    color: [f32; 4],
+}
 
-    /// Generates indices for the vertices for the edge of a hexagon
-    const fn indices_edge_hex() -> [u16; Self::COUNT_INDEX_EDGE_HEX] {
-        [0, 1, 2, 3, 4, 5, 0]
+impl BarInstance {
+    /// Gets the memory description of a bar instance
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
     }
 }
 
-/// Describes if rendering should be done on the filling or outline of hexagons
-#[derive(Copy, Clone, Debug)]
-enum DrawMode {
-    Fill,
-    Edge,
+/// Describes a single vertex of a character quad in the debug overlay
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct LabelVertex {
+    /// The position in world coordinates
+    position: [f32; 2],
+    /// The coordinates in the glyph atlas
+    tex_coords: [f32; 2],
 }
 
-impl DrawMode {
-    /// Retrieves the code for the gpu for this mode
-    fn get_data(&self) -> u32 {
-        match *self {
-            Self::Fill => 0,
-            Self::Edge => 1,
+impl LabelVertex {
+    /// Gets the memory description of a label vertex
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+            ],
         }
     }
+
+    /// Generates the two triangles for a single character
+    ///
+    /// # Parameters
+    ///
+    /// corner: The lower left corner of the character
+    ///
+    /// size: The size of the character
+    ///
+    /// glyph_id: The index of the glyph in the atlas
+    ///
+    /// glyph_count: The total number of glyphs in the atlas
+    fn quad(
+        corner: &types::Point,
+        size: &types::Size,
+        glyph_id: usize,
+        glyph_count: usize,
+    ) -> [Self; 6] {
+        let left = glyph_id as f32 / glyph_count as f32;
+        let right = (glyph_id + 1) as f32 / glyph_count as f32;
+        let vertex = |x: f64, y: f64, u: f32, v: f32| {
+            return Self {
+                position: [(corner.x + x) as f32, (corner.y + y) as f32],
+                tex_coords: [u, v],
+            };
+        };
+        let lower_left = vertex(0.0, 0.0, left, 1.0);
+        let lower_right = vertex(size.w, 0.0, right, 1.0);
+        let upper_left = vertex(0.0, size.h, left, 0.0);
+        let upper_right = vertex(size.w, size.h, right, 0.0);
+
+        return [
+            lower_left,
+            lower_right,
+            upper_right,
+            lower_left,
+            upper_right,
+            upper_left,
+        ];
+    }
+}
+
+/// Copies the first layer of a texture to the cpu and removes the row padding
+///
+/// # Parameters
+///
+/// render_state: The render state to use for copying
+///
+/// texture: The texture to read, it must have been created with COPY_SRC
+///
+/// size: The size of the texture
+///
+/// bytes_per_pixel: The number of bytes for each pixel of the texture format
+///
+/// # Errors
+///
+/// See CaptureError for a description of the different errors which may occur
+fn read_texture(
+    render_state: &render::RenderState,
+    texture: &wgpu::Texture,
+    size: wgpu::Extent3d,
+    bytes_per_pixel: u32,
+) -> Result<Vec<u8>, CaptureError> {
+    // Copy the texture to a buffer, the rows must be aligned
+    let bytes_per_row = bytes_per_pixel * size.width;
+    let padded_bytes_per_row = bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+        * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let buffer = render_state
+        .get_device()
+        .create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Buffer"),
+            size: (padded_bytes_per_row * size.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+    let mut encoder =
+        render_state
+            .get_device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Command Encoder Capture"),
+            });
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(size.height),
+            },
+        },
+        size,
+    );
+    render_state
+        .get_queue()
+        .submit(std::iter::once(encoder.finish()));
+
+    // Wait for the buffer to be readable
+    let slice = buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    render_state.get_device().poll(wgpu::Maintain::Wait);
+    receiver.recv().map_err(|_| CaptureError::Disconnected)??;
+
+    // Remove the padding
+    let data = slice
+        .get_mapped_range()
+        .chunks(padded_bytes_per_row as usize)
+        .flat_map(|row| return row[..bytes_per_row as usize].iter().copied())
+        .collect::<Vec<u8>>();
+    buffer.unmap();
+
+    return Ok(data);
 }
 
 fn get_color_data(color: &wgpu::Color) -> [f32; 4] {
@@ -638,3 +4115,87 @@ fn get_color_data(color: &wgpu::Color) -> [f32; 4] {
         color.a as f32,
     ];
 }
+
+/// Retrieves the fill color of a tile for the gpu
+///
+/// # Parameters
+///
+/// tile: The tile to get the color for
+///
+/// color_mode: The way to convert the resources to a color
+///
+/// colormaps: The colormaps used when showing a single resource
+///
+/// gradient_magnitude: The normalized magnitude of the gradient at the tile,
+/// only used when showing a gradient
+fn get_tile_color_data(
+    tile: &map::Tile,
+    color_mode: TileColorMode,
+    colormaps: &Colormaps,
+    gradient_magnitude: f64,
+) -> [f32; 4] {
+    let resources = &tile.current_resources;
+    let (r, g, b) = match color_mode {
+        TileColorMode::Nutrients => {
+            return colormaps
+                .get(map::ResourceType::Nutrients)
+                .sample(resources.nutrients);
+        }
+        TileColorMode::Energy => {
+            return colormaps
+                .get(map::ResourceType::Energy)
+                .sample(resources.energy);
+        }
+        TileColorMode::Water => {
+            return colormaps
+                .get(map::ResourceType::Water)
+                .sample(resources.water);
+        }
+        TileColorMode::Composite => (resources.nutrients, resources.energy, resources.water),
+        TileColorMode::Biome => get_biome_color(resources),
+        TileColorMode::GradientMagnitude(_) => {
+            return Colormap::Viridis.sample(gradient_magnitude);
+        }
+    };
+
+    return [r as f32, g as f32, b as f32, 1.0];
+}
+
+/// Converts the resources to a color by placing nutrients, energy and water at
+/// hues of 0, 120 and 240 degrees respectively and using the combined
+/// direction as the hue
+///
+/// # Parameters
+///
+/// resources: The resources to convert
+fn get_biome_color(resources: &map::Resources) -> (f64, f64, f64) {
+    let max = resources
+        .nutrients
+        .max(resources.energy)
+        .max(resources.water);
+    if max <= 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+
+    // Find the combined direction on the color wheel
+    let x = resources.nutrients - 0.5 * (resources.energy + resources.water);
+    let y = 0.5 * SQRT_3 * (resources.energy - resources.water);
+    let hue = y.atan2(x).rem_euclid(2.0 * std::f64::consts::PI) / (std::f64::consts::PI / 3.0);
+    let saturation = ((x * x + y * y).sqrt() / max).clamp(0.0, 1.0);
+    let value = max.clamp(0.0, 1.0);
+
+    // Convert from hsv to rgb
+    let chroma = value * saturation;
+    let secondary = chroma * (1.0 - (hue.rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match hue.floor() as u32 {
+        0 => (chroma, secondary, 0.0),
+        1 => (secondary, chroma, 0.0),
+        2 => (0.0, chroma, secondary),
+        3 => (0.0, secondary, chroma),
+        4 => (secondary, 0.0, chroma),
+        _ => (chroma, 0.0, secondary),
+    };
+    let lightness = value - chroma;
+
+    return (r + lightness, g + lightness, b + lightness);
+}
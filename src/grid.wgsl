@@ -0,0 +1,36 @@
+// Uniforms
+// The transform to apply to each vertex
+@group(0) @binding(0)
+var<uniform> transform: mat2x2<f32>;
+
+// The center of the coordinate system which is subtracted before transforming
+@group(0) @binding(3)
+var<uniform> offset: vec2<f32>;
+
+// The color to draw the lines with
+@group(1) @binding(0)
+var<uniform> line_color: vec4<f32>;
+
+// Type definitions
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+// Vertex shader
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    var pos = transform * (position - offset);
+    out.clip_position = vec4<f32>(pos, 0.0, 1.0);
+    return out;
+}
+
+// Fragment shader
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return line_color;
+}
@@ -0,0 +1,39 @@
+// Type definitions
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) local_x: f32,
+    @location(1) fill: f32,
+    @location(2) color: vec4<f32>,
+};
+
+// The color of the part of a bar which is not filled
+const background_color = vec4<f32>(0.2, 0.2, 0.2, 0.8);
+
+// Vertex shader, the positions are already in screen space
+@vertex
+fn vs_main(
+    @location(0) corner: vec2<f32>,
+    @location(1) center: vec2<f32>,
+    @location(2) size: vec2<f32>,
+    @location(3) fill: f32,
+    @location(4) color: vec4<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    var pos = center + (corner - vec2<f32>(0.5, 0.5)) * size;
+    out.clip_position = vec4<f32>(pos, 0.0, 1.0);
+    out.local_x = corner.x;
+    out.fill = fill;
+    out.color = color;
+    return out;
+}
+
+// Fragment shader
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    if in.local_x > in.fill {
+        return background_color;
+    }
+    return in.color;
+}
@@ -0,0 +1,14 @@
+pub mod application;
+pub mod camera;
+pub mod constants;
+pub mod graphics;
+pub mod map;
+pub mod render;
+pub mod simulation;
+pub mod types;
+
+pub use map::{
+    Chunk, ChunkEdgeType, ChunkGeometry, ChunkType, ChunkVertexType, MapBuilder, MapData, MapEvent,
+    MapEventError, MapFileError, MapFormat, MapLayout, NewChunkError, ResourceGradient,
+    ResourceType, Resources, Tile,
+};
@@ -1,16 +1,8 @@
-use std::{env, f64::consts::PI};
+use std::{env, f64::consts::PI, path::PathBuf};
 
-use constants::FRAMERATE;
+use growth_simulator::{application, camera, constants::FRAMERATE, graphics, map, types};
 use winit::dpi::PhysicalSize;
 
-pub mod application;
-pub mod camera;
-pub mod constants;
-pub mod graphics;
-pub mod map;
-pub mod render;
-pub mod types;
-
 fn main() {
     env::set_var("RUST_BACKTRACE", "1");
 
@@ -29,23 +21,39 @@ fn main() {
         b: 1.0,
         a: 1.0,
     };
-    let color_edge = wgpu::Color {
-        r: 0.1,
-        g: 0.1,
-        b: 0.1,
-        a: 1.0,
+    let grid_lines = graphics::GridLines {
+        show_tile_edges: true,
+        show_chunk_edges: true,
+        tile_edge_color: wgpu::Color {
+            r: 0.1,
+            g: 0.1,
+            b: 0.1,
+            a: 1.0,
+        },
+        chunk_edge_color: wgpu::Color {
+            r: 0.8,
+            g: 0.1,
+            b: 0.1,
+            a: 1.0,
+        },
     };
     let graphics_settings = graphics::Settings {
         color_background,
-        color_edge,
+        grid_lines,
+        sample_count: 4,
+        show_debug_indices: false,
+        tile_scale: 1.0,
+        hex_border_width: 0.05,
+        show_axes: false,
+        axes_length: 5.0,
     };
 
-    // Setup the map
+    // Setup the map, --map, --width, --height and --seed replace the default map
     let sources = map::SourceMap {
         nutrients: vec![map::Source::Gaussian(types::Gaussian::new(
             2.0 * PI * 1.0 * 1.0,
             types::Point::new(0.0, 0.0),
-            types::Matrix::new(&[[1.0, 0.0], [0.0, 1.0]]),
+            types::Matrix::identity(),
         ))],
         energy: vec![map::Source::Gaussian(types::Gaussian::new(
             2.0 * PI * 2.0 * 1.0,
@@ -58,8 +66,7 @@ fn main() {
             types::Matrix::new(&[[1.0, 0.5], [0.5, 1.0]]),
         ))],
     };
-    let map_data = Box::new(map::MapCyclic::new());
-    let map = map::Map::new(map_data, sources);
+    let map = load_map_from_cli(env::args(), map::MapBuilder::new(sources));
 
     // Setup the camera
     let camera_transform = types::Transform2D::scale(&types::Point::new(0.5, 0.5))
@@ -78,6 +85,182 @@ fn main() {
     let mut main_loop =
         application::MainLoop::new(name, FRAMERATE, size, graphics_settings, map, camera);
 
+    // Center on the tile given by --center x,y
+    if let Some(index) = parse_center_argument(env::args()) {
+        main_loop.get_camera_mut().snap_to_tile(&index);
+    }
+
+    // Append the statistics of every tick to a csv file if given --log-stats path
+    main_loop.set_stats_log(parse_log_stats_argument(env::args()));
+
+    // Load the tile shader from a file if given --shader path, with the notify feature it is reloaded when it changes
+    main_loop.set_shader_watch(parse_shader_argument(env::args()));
+
+    // Record a screenshot series when the window opens if given --record n prefix
+    main_loop.set_recording(parse_record_argument(env::args()));
+
     // Run the application
     application::run(&mut main_loop);
 }
+
+/// Creates the map given by the command line arguments, --map path loads a
+/// map file, --width n --height n creates a finite map and --seed n sets the
+/// seed, if no map is loaded the map is created from the builder, invalid
+/// arguments are reported and ignored
+///
+/// # Parameters
+///
+/// args: The command line arguments
+///
+/// builder: The builder for the default map
+fn load_map_from_cli(args: impl Iterator<Item = String>, builder: map::MapBuilder) -> map::Map {
+    let args = args.collect::<Vec<String>>();
+
+    // Find the value following an argument
+    let get_value = |name: &str| {
+        let position = args.iter().position(|arg| return arg == name)?;
+        return match args.get(position + 1) {
+            Some(value) if !value.starts_with("--") => Some(value.clone()),
+            _ => {
+                eprintln!("Missing value for {}", name);
+                None
+            }
+        };
+    };
+
+    // Load the map file
+    if let Some(path) = get_value("--map") {
+        if ["--width", "--height", "--seed"]
+            .iter()
+            .any(|name| return args.iter().any(|arg| return arg == name))
+        {
+            eprintln!("--width, --height and --seed are ignored when loading a map with --map");
+        }
+        match map::Map::load(&PathBuf::from(&path)) {
+            Ok((map, _)) => return map,
+            Err(error) => eprintln!("Unable to load the map {:?}: {}", path, error),
+        }
+    }
+
+    // Set the seed
+    let mut builder = builder;
+    if let Some(seed) = get_value("--seed") {
+        match seed.trim().parse::<u64>() {
+            Ok(seed) => builder = builder.with_seed(seed),
+            Err(_) => eprintln!("Invalid value for --seed, expected an integer: {:?}", seed),
+        }
+    }
+
+    // Set the size of a finite map
+    let parse_size = |name: &str| {
+        let value = get_value(name)?;
+        return match value.trim().parse::<usize>() {
+            Ok(size) if size > 0 => Some(size),
+            _ => {
+                eprintln!(
+                    "Invalid value for {}, expected a positive number of chunks: {:?}",
+                    name, value
+                );
+                None
+            }
+        };
+    };
+    match (parse_size("--width"), parse_size("--height")) {
+        (Some(width), Some(height)) => {
+            builder = builder.with_layout(map::MapLayout::Finite(width, height))
+        }
+        (None, None) => (),
+        _ => eprintln!("Both --width and --height must be given for a finite map"),
+    }
+
+    return builder.build();
+}
+
+/// Finds the tile index given by the command line argument --center x,y,
+/// returns None if the argument is not given or invalid
+///
+/// # Parameters
+///
+/// args: The command line arguments
+fn parse_center_argument(mut args: impl Iterator<Item = String>) -> Option<types::Index> {
+    args.find(|arg| return arg == "--center")?;
+    let value = args.next()?;
+    let (x, y) = match value.split_once(',') {
+        Some(value) => value,
+        None => {
+            eprintln!("Invalid value for --center, expected x,y: {:?}", value);
+            return None;
+        }
+    };
+
+    return match (x.trim().parse::<i64>(), y.trim().parse::<i64>()) {
+        (Ok(x), Ok(y)) => Some(types::Index::new(x, y)),
+        _ => {
+            eprintln!("Invalid value for --center, expected x,y: {:?}", value);
+            None
+        }
+    };
+}
+
+/// Finds the path of the shader given by the command line argument
+/// --shader path, returns None if the argument is not given
+///
+/// # Parameters
+///
+/// args: The command line arguments
+fn parse_shader_argument(mut args: impl Iterator<Item = String>) -> Option<PathBuf> {
+    args.find(|arg| return arg == "--shader")?;
+    return match args.next() {
+        Some(path) => Some(PathBuf::from(path)),
+        None => {
+            eprintln!("Missing value for --shader, expected a path to a wgsl file");
+            None
+        }
+    };
+}
+
+/// Finds the path of the csv file given by the command line argument
+/// --log-stats path, returns None if the argument is not given
+///
+/// # Parameters
+///
+/// args: The command line arguments
+fn parse_log_stats_argument(mut args: impl Iterator<Item = String>) -> Option<PathBuf> {
+    args.find(|arg| return arg == "--log-stats")?;
+    return match args.next() {
+        Some(path) => Some(PathBuf::from(path)),
+        None => {
+            eprintln!("Missing value for --log-stats, expected a path to a csv file");
+            None
+        }
+    };
+}
+
+/// Finds the number of frames and the path prefix given by the command line
+/// argument --record n prefix, returns None if the argument is not given or
+/// invalid
+///
+/// # Parameters
+///
+/// args: The command line arguments
+fn parse_record_argument(mut args: impl Iterator<Item = String>) -> Option<(u32, String)> {
+    args.find(|arg| return arg == "--record")?;
+    let (n, path_prefix) = match (args.next(), args.next()) {
+        (Some(n), Some(path_prefix)) => (n, path_prefix),
+        _ => {
+            eprintln!("Missing values for --record, expected n prefix");
+            return None;
+        }
+    };
+
+    return match n.trim().parse::<u32>() {
+        Ok(n) => Some((n, path_prefix)),
+        Err(_) => {
+            eprintln!(
+                "Invalid value for --record, expected a number of frames: {:?}",
+                n
+            );
+            None
+        }
+    };
+}
@@ -1,9 +1,25 @@
 use crate::{
-    constants::{CHUNK_SIZE, INV_SQRT_3, SQRT_3},
+    constants::{
+        CHUNK_SIZE, INV_SQRT_3, MAP_HISTORY_LENGTH, RESOURCE_RELAXATION_RATE, TERRAIN_SLOPE_FACTOR,
+    },
     types,
 };
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use once_cell::sync::Lazy;
-use std::{f64::consts::PI, fmt::Debug, iter};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::OnceCell,
+    collections::{HashMap, HashSet, VecDeque},
+    f64::consts::PI,
+    fmt::{self, Debug},
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    iter,
+    ops::{Add, Mul, Sub},
+    path::Path,
+    str::FromStr,
+};
 use thiserror::Error;
 
 /// Calculates what tile the given cartesian coordinate is within and returns its tile index,
@@ -13,68 +29,7 @@ use thiserror::Error;
 ///
 /// point: The cartesian coordinates to use
 pub fn coordinate_to_tile(point: &types::Point) -> types::Index {
-    // Calculate the skewed coordinates and index
-    let skew_point = types::Point::new(0.5 * (SQRT_3 * point.x - point.y), point.y);
-    let skew_index = types::Index::new(
-        (skew_point.x + 0.5).floor() as i64,
-        (skew_point.y + 0.5).floor() as i64,
-    );
-
-    // Figure out what strip of locations this index is part of
-    let strip = (skew_index.y - skew_index.x) % 3;
-    let strip = if strip < 0 { strip + 3 } else { strip };
-
-    // Get the relative coordinate
-    let rel_point = skew_point - types::Point::new(skew_index.x as f64, skew_index.y as f64);
-
-    // Find the correct tile
-    match strip {
-        0 => {
-            let main_index = types::Index::new(
-                -(4 * skew_index.x + 2 * skew_index.y) / 3,
-                (2 * skew_index.x + 4 * skew_index.y) / 3,
-            );
-
-            main_index
-                + if rel_point.x + rel_point.y < -0.5 {
-                    types::Index::new(1, -1)
-                } else if rel_point.x + rel_point.y > 0.5 {
-                    types::Index::new(-1, 1)
-                } else {
-                    types::Index::new(0, 0)
-                }
-        }
-        1 => {
-            let main_index = types::Index::new(
-                -(4 * skew_index.x + 2 * skew_index.y + 1) / 3,
-                (2 * skew_index.x + 4 * skew_index.y - 1) / 3,
-            );
-
-            main_index
-                + if rel_point.x > 0.0 && rel_point.y < 0.0 {
-                    types::Index::new(0, 0)
-                } else if rel_point.x + rel_point.y < 0.0 {
-                    types::Index::new(1, 0)
-                } else {
-                    types::Index::new(0, 1)
-                }
-        }
-        _ => {
-            let main_index = types::Index::new(
-                -(4 * skew_index.x + 2 * skew_index.y - 1) / 3,
-                (2 * skew_index.x + 4 * skew_index.y + 1) / 3,
-            );
-
-            main_index
-                + if rel_point.x < 0.0 && rel_point.y > 0.0 {
-                    types::Index::new(0, 0)
-                } else if rel_point.x + rel_point.y < 0.0 {
-                    types::Index::new(0, -1)
-                } else {
-                    types::Index::new(-1, 0)
-                }
-        }
-    }
+    return point.to_tile_index();
 }
 
 /// Calculates the center cartesian coordinate of the given tile,
@@ -84,8 +39,7 @@ pub fn coordinate_to_tile(point: &types::Point) -> types::Index {
 ///
 /// index: The index of the til to use
 pub fn tile_to_coordinate(index: &types::Index) -> types::Point {
-    return types::Point::new(-1.5 * INV_SQRT_3, 0.5) * (index.x as f64)
-        + types::Point::new(0.0, 1.0) * (index.y as f64);
+    return index.to_tile_center();
 }
 
 /// Calculates what chunk the given cartesian coordinate is within and returns its chunk index,
@@ -95,8 +49,20 @@ pub fn tile_to_coordinate(index: &types::Index) -> types::Point {
 ///
 /// point: The cartesian coordinates to use
 pub fn coordinate_to_chunk(point: &types::Point) -> types::Index {
+    return coordinate_to_chunk_with_size(point, CHUNK_SIZE);
+}
+
+/// Calculates what chunk the given cartesian coordinate is within for chunks
+/// of the given size
+///
+/// # Parameters
+///
+/// point: The cartesian coordinates to use
+///
+/// chunk_size: The size of the chunks
+fn coordinate_to_chunk_with_size(point: &types::Point, chunk_size: usize) -> types::Index {
     // Convert to rotated and scaled coordinates
-    let factor = INV_SQRT_3 / (CHUNK_SIZE as f64);
+    let factor = INV_SQRT_3 / (chunk_size as f64);
     let new_point = types::Point::new(-point.y, point.x) * factor;
 
     // Get the index
@@ -113,8 +79,218 @@ pub fn coordinate_to_chunk(point: &types::Point) -> types::Index {
 ///
 /// index: The index of the til to use
 pub fn chunk_to_coordinate(index: &types::Index) -> types::Point {
-    return types::Point::new(3.0 * INV_SQRT_3, 0.0) * (((CHUNK_SIZE as i64) * index.x) as f64)
-        + types::Point::new(1.5 * INV_SQRT_3, 1.5) * (((CHUNK_SIZE as i64) * index.y) as f64);
+    return chunk_to_coordinate_with_size(index, CHUNK_SIZE);
+}
+
+/// Calculates the center cartesian coordinate of the given chunk for chunks
+/// of the given size
+///
+/// # Parameters
+///
+/// index: The index of the chunk to use
+///
+/// chunk_size: The size of the chunks
+fn chunk_to_coordinate_with_size(index: &types::Index, chunk_size: usize) -> types::Point {
+    return types::Point::new(3.0 * INV_SQRT_3, 0.0) * (((chunk_size as i64) * index.x) as f64)
+        + types::Point::new(1.5 * INV_SQRT_3, 1.5) * (((chunk_size as i64) * index.y) as f64);
+}
+
+/// Finds the position of the first tile of each chunk when all tiles are
+/// listed in the order of the chunks given by the map data
+///
+/// # Parameters
+///
+/// data: The chunk data to find the offsets for
+fn chunk_offsets(data: &dyn MapData) -> HashMap<(ChunkType, usize), usize> {
+    let mut count = 0;
+    return data
+        .get_chunks()
+        .map(|chunk| {
+            let offset = count;
+            count += chunk.get_tiles().len();
+
+            return ((*chunk.get_chunk_type(), chunk.get_index()), offset);
+        })
+        .collect();
+}
+
+/// Evaluates a source at the tiles of all chunks within its range, chunks
+/// are included once for every periodic image of the source which reaches
+/// them, returns the chunk type, the storage index of the chunk and the value
+/// at each of its tiles
+///
+/// # Parameters
+///
+/// data: The chunk data to evaluate the source for
+///
+/// geometry: The geometry of the chunks
+///
+/// source: The source to evaluate
+fn evaluate_source_on_chunks(
+    data: &dyn MapData,
+    geometry: &ChunkGeometry,
+    source: &Source,
+) -> Vec<(ChunkType, usize, Vec<f64>)> {
+    // Get the range for the source in units of chunk widths
+    let range = (source.range() / (1.5 * geometry.get_chunk_size() as f64)).ceil() as i64;
+
+    // Get the current chunk
+    let center = geometry.coordinate_to_chunk(&source.center());
+
+    // Loop over relative y values to the current chunk
+    return (-range..range + 1)
+        .flat_map(|y| {
+            let (min_x, max_x) = if y < 0 {
+                (-range - y, range)
+            } else {
+                (-range, range - y)
+            };
+            return (min_x..max_x + 1).map(move |x| return center + types::Index::new(x, y));
+        })
+        .flat_map(|chunk_index| {
+            // Get the coordinates for the center of the chunk
+            let chunk_coords = geometry.chunk_to_coordinate(&chunk_index);
+
+            // Calculate the population of each tile of all chunk types
+            return ChunkType::all().into_iter().filter_map(move |chunk_type| {
+                let chunk_id = data.get_index(&chunk_type, chunk_index)?;
+                let chunk = data.get_chunk(&chunk_type, chunk_id);
+                let pop = source.evaluate(&chunk_coords, chunk.get_tile_centers(geometry));
+
+                return Some((chunk_type, chunk_id, pop));
+            });
+        })
+        .collect();
+}
+
+/// The different layouts of chunks a map can have
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MapLayout {
+    /// A single chunk repeating in all directions
+    Cyclic,
+    /// A grid of chunks with the given width and height, there are no chunks outside of it
+    Finite(usize, usize),
+    /// A grid of chunks with the given width and height repeating in all directions
+    Toroidal(usize, usize),
+}
+
+impl MapLayout {
+    /// Calculates the number of tiles stored for this layout, None if there
+    /// are no chunks or the number of tiles cannot be stored
+    ///
+    /// # Parameters
+    ///
+    /// chunk_size: The number of tiles from the center of a chunk to its edge
+    pub fn tile_count(&self, chunk_size: usize) -> Option<usize> {
+        let chunk_count = match self {
+            Self::Cyclic => 1,
+            Self::Finite(width, height) | Self::Toroidal(width, height) => {
+                width.checked_mul(*height)?
+            }
+        };
+        if chunk_count == 0 {
+            return None;
+        }
+
+        // The bulk, the three edges and the two vertices of a chunk hold 3 n^2 tiles together
+        return chunk_size
+            .checked_mul(chunk_size)?
+            .checked_mul(3)?
+            .checked_mul(chunk_count);
+    }
+}
+
+/// A builder for constructing a map
+#[derive(Clone, Debug)]
+pub struct MapBuilder {
+    /// The number of tiles from the center of a chunk to its edge
+    chunk_size: usize,
+    /// All sources of resources
+    sources: SourceMap,
+    /// The layout of the chunks
+    layout: MapLayout,
+    /// The seed for all random generation of the map
+    seed: u64,
+}
+
+impl MapBuilder {
+    /// Creates a new builder for a cyclic map with the default chunk size
+    ///
+    /// # Parameters
+    ///
+    /// sources: All sources of resources
+    pub fn new(sources: SourceMap) -> Self {
+        return Self {
+            chunk_size: CHUNK_SIZE,
+            sources,
+            layout: MapLayout::Cyclic,
+            seed: 0,
+        };
+    }
+
+    /// Changes the chunk size and returns the updated object
+    ///
+    /// # Parameters
+    ///
+    /// chunk_size: The new chunk size
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chunk size is 0
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        if chunk_size == 0 {
+            panic!("The chunk size must be at least 1");
+        }
+
+        self.chunk_size = chunk_size;
+        return self;
+    }
+
+    /// Changes the sources and returns the updated object
+    ///
+    /// # Parameters
+    ///
+    /// sources: The new sources
+    pub fn with_sources(mut self, sources: SourceMap) -> Self {
+        self.sources = sources;
+        return self;
+    }
+
+    /// Changes the layout and returns the updated object
+    ///
+    /// # Parameters
+    ///
+    /// layout: The new layout
+    pub fn with_layout(mut self, layout: MapLayout) -> Self {
+        self.layout = layout;
+        return self;
+    }
+
+    /// Changes the seed used for all random generation and returns the
+    /// updated object, maps built with the same seed and settings are identical
+    ///
+    /// # Parameters
+    ///
+    /// seed: The new seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        return self;
+    }
+
+    /// Constructs the map
+    pub fn build(self) -> Map {
+        let data: Box<dyn MapData> = match self.layout {
+            MapLayout::Cyclic => Box::new(MapCyclic::new(self.chunk_size)),
+            MapLayout::Finite(width, height) => {
+                Box::new(MapFinite::new(width, height, self.chunk_size))
+            }
+            MapLayout::Toroidal(width, height) => {
+                Box::new(MapToroidal::new(width, height, self.chunk_size))
+            }
+        };
+
+        return Map::new(data, self.sources, self.seed);
+    }
 }
 
 /// A map consiting of a single chunk with cyclic boundaries
@@ -129,16 +305,25 @@ pub struct MapCyclic {
 }
 
 impl MapCyclic {
-    pub fn new() -> Self {
-        let chunks_bulk = Chunk::new_empty(ChunkType::Bulk, 0);
+    /// Constructs a new cyclic map with all base resources set to 0
+    ///
+    /// # Parameters
+    ///
+    /// chunk_size: The number of tiles from the center of the chunk to its edge
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chunk size is 0
+    pub fn new(chunk_size: usize) -> Self {
+        let chunks_bulk = Chunk::new_empty(ChunkType::Bulk, 0, chunk_size);
         let chunks_edge = [
-            Chunk::new_empty(ChunkType::Edge(ChunkEdgeType::Top), 0),
-            Chunk::new_empty(ChunkType::Edge(ChunkEdgeType::Middle), 0),
-            Chunk::new_empty(ChunkType::Edge(ChunkEdgeType::Bottom), 0),
+            Chunk::new_empty(ChunkType::Edge(ChunkEdgeType::Top), 0, chunk_size),
+            Chunk::new_empty(ChunkType::Edge(ChunkEdgeType::Middle), 0, chunk_size),
+            Chunk::new_empty(ChunkType::Edge(ChunkEdgeType::Bottom), 0, chunk_size),
         ];
         let chunks_vertex = [
-            Chunk::new_empty(ChunkType::Vertex(ChunkVertexType::Top), 0),
-            Chunk::new_empty(ChunkType::Vertex(ChunkVertexType::Bottom), 0),
+            Chunk::new_empty(ChunkType::Vertex(ChunkVertexType::Top), 0, chunk_size),
+            Chunk::new_empty(ChunkType::Vertex(ChunkVertexType::Bottom), 0, chunk_size),
         ];
 
         return Self {
@@ -150,10 +335,22 @@ impl MapCyclic {
 }
 
 impl MapData for MapCyclic {
+    fn get_chunk_size(&self) -> usize {
+        return self.chunks_bulk.get_chunk_size();
+    }
+
+    fn get_layout(&self) -> Option<MapLayout> {
+        return Some(MapLayout::Cyclic);
+    }
+
     fn get_index(&self, _chunk_type: &ChunkType, _coordinates: types::Index) -> Option<usize> {
         return Some(0);
     }
 
+    fn get_coordinates(&self, _chunk_type: &ChunkType, _index: usize) -> types::Index {
+        return types::Index::new(0, 0);
+    }
+
     fn get_chunk(&self, chunk_type: &ChunkType, _index: usize) -> &Chunk {
         return match chunk_type {
             ChunkType::Bulk => &self.chunks_bulk,
@@ -185,569 +382,3668 @@ impl MapData for MapCyclic {
                 .chain(self.chunks_vertex.iter_mut()),
         );
     }
-}
-
-/// Holds all data for an entire map
-#[derive(Debug)]
-pub struct Map {
-    /// All chunk data, use different implementations of MapData for different chunk layouts
-    data: Box<dyn MapData>,
-    /// All sources of resources
-    sources: SourceMap,
-}
 
-impl Map {
-    pub fn new(data: Box<dyn MapData>, sources: SourceMap) -> Self {
-        // Create the map
-        let mut map = Self { data, sources };
-
-        // Populate
-        map.populate_resources();
-
-        return map;
+    fn chunk_count(&self) -> usize {
+        return ChunkType::all().len();
     }
 
-    /// Retrieves a reference to the chunk data
-    pub fn get_data(&self) -> &dyn MapData {
-        return self.data.as_ref();
+    fn all_chunk_types_and_indices(&self) -> Vec<(ChunkType, usize)> {
+        return ChunkType::all()
+            .into_iter()
+            .map(|chunk_type| return (chunk_type, 0))
+            .collect();
     }
+}
 
-    /// Retrieves a mutable reference to the chunk data
-    pub fn get_data_mut(&mut self) -> &mut dyn MapData {
-        return self.data.as_mut();
-    }
+/// A map consisting of a finite grid of chunks, there are no chunks outside of the grid
+#[derive(Clone, Debug)]
+pub struct MapFinite {
+    /// All the chunks
+    grid: ChunkGrid,
+}
 
-    /// Retrieves a reference to the source map
-    pub fn get_sources(&self) -> &SourceMap {
-        return &self.sources;
+impl MapFinite {
+    /// Constructs a new finite map with all base resources set to 0
+    ///
+    /// # Parameters
+    ///
+    /// width: The number of chunks in the x-direction
+    ///
+    /// height: The number of chunks in the y-direction
+    ///
+    /// chunk_size: The number of tiles from the center of a chunk to its edge
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chunk size is 0
+    pub fn new(width: usize, height: usize, chunk_size: usize) -> Self {
+        return Self {
+            grid: ChunkGrid::new(width, height, chunk_size),
+        };
     }
+}
 
-    /// Retrieves a mutable reference to the source map, once the mutator is
-    /// destroyed it will reload the base resources of the map
-    pub fn get_sources_mut(&mut self) -> SourceMapMut {
-        return SourceMapMut { map: self };
+impl MapData for MapFinite {
+    fn get_chunk_size(&self) -> usize {
+        return self.grid.chunk_size;
     }
 
-    /// Populates all tiles with the correct base resources as given by the sources
-    fn populate_resources(&mut self) {
-        self.populate_resource(
-            |source_map| return &source_map.nutrients,
-            |resources| return &mut resources.nutrients,
-        );
-        self.populate_resource(
-            |source_map| return &source_map.energy,
-            |resources| return &mut resources.energy,
-        );
-        self.populate_resource(
-            |source_map| return &source_map.water,
-            |resources| return &mut resources.water,
-        );
+    fn get_layout(&self) -> Option<MapLayout> {
+        return Some(MapLayout::Finite(self.grid.width, self.grid.height));
     }
 
-    /// Populates all tiles with the correct base nutrients as given by the sources
-    fn populate_resource<SourcesAccess, ResourcesAccess>(
-        &mut self,
-        sources_access: SourcesAccess,
-        resources_access: ResourcesAccess,
-    ) where
-        SourcesAccess: Fn(&SourceMap) -> &[Source],
-        ResourcesAccess: Fn(&mut Resources) -> &mut f64,
-    {
-        // Reset the nutrients and set modified to true
-        self.data.get_chunks_mut().into_iter().for_each(|chunk| {
-            chunk.modified = true;
-            chunk.tiles.iter_mut().for_each(|tile| {
-                *resources_access(&mut tile.base_resources) = 0.0;
-            });
-        });
+    fn get_index(&self, _chunk_type: &ChunkType, coordinates: types::Index) -> Option<usize> {
+        if coordinates.x < 0
+            || coordinates.y < 0
+            || coordinates.x >= self.grid.width as i64
+            || coordinates.y >= self.grid.height as i64
+        {
+            return None;
+        }
 
-        // Populate each source
-        sources_access(&self.sources).iter().for_each(|source| {
-            // Get the range for the source in units of chunk widths
-            let range = (source.range() / (1.5 * CHUNK_SIZE as f64)).ceil() as i64;
+        return Some(self.grid.get_index(&coordinates));
+    }
 
-            // Get the current chunk
-            let center = coordinate_to_chunk(&source.center());
+    fn get_coordinates(&self, _chunk_type: &ChunkType, index: usize) -> types::Index {
+        return self.grid.get_coordinates(index);
+    }
 
-            // Loop over relative y values to the current chunk
-            (-range..range + 1).for_each(|y| {
-                let (min_x, max_x) = if y < 0 {
-                    (-range - y, range)
-                } else {
-                    (-range, range - y)
-                };
-                (min_x..max_x + 1).for_each(|x| {
-                    // Get the coordinates for the center of the chunk
-                    let chunk_index = center + types::Index::new(x, y);
-                    let chunk_coords = chunk_to_coordinate(&chunk_index);
-
-                    // Loop over all chunk types
-                    [
-                        ChunkType::Bulk,
-                        ChunkType::Edge(ChunkEdgeType::Top),
-                        ChunkType::Edge(ChunkEdgeType::Middle),
-                        ChunkType::Edge(ChunkEdgeType::Bottom),
-                        ChunkType::Vertex(ChunkVertexType::Top),
-                        ChunkType::Vertex(ChunkVertexType::Bottom),
-                    ]
-                    .iter()
-                    .for_each(|chunk_type| {
-                        // Get the chunk
-                        let chunk = self.data.get_chunk_mut(
-                            chunk_type,
-                            match self.data.get_index(chunk_type, chunk_index) {
-                                Some(value) => value,
-                                None => return,
-                            },
-                        );
-
-                        // Calculate population for each tile
-                        let pop = source.evaluate(&chunk_coords, chunk_type.get_tile_centers());
-
-                        // Add the population
-                        pop.iter().zip(chunk.get_tiles_mut().iter_mut()).for_each(
-                            |(value, tile)| {
-                                *resources_access(&mut tile.base_resources) += value;
-                            },
-                        );
-                    });
-                });
-            });
-        });
+    fn get_chunk(&self, chunk_type: &ChunkType, index: usize) -> &Chunk {
+        return self.grid.get_chunk(chunk_type, index);
+    }
 
-        // Clamp all values
-        self.data.get_chunks_mut().into_iter().for_each(|chunk| {
-            chunk.tiles.iter_mut().for_each(|tile| {
-                *resources_access(&mut tile.base_resources) =
-                    resources_access(&mut tile.base_resources).clamp(0.0, 1.0);
-            });
-        });
+    fn get_chunk_mut(&mut self, chunk_type: &ChunkType, index: usize) -> &mut Chunk {
+        return self.grid.get_chunk_mut(chunk_type, index);
     }
-}
 
-/// A mutator for the source map
-#[derive(Debug)]
-pub struct SourceMapMut<'map> {
-    map: &'map mut Map,
-}
+    fn get_chunks(&self) -> Box<dyn Iterator<Item = &Chunk> + '_> {
+        return self.grid.get_chunks();
+    }
 
-impl<'map> SourceMapMut<'map> {
-    /// Retrieves a reference to the sources
-    pub fn get(&self) -> &SourceMap {
-        return &self.map.sources;
+    fn get_chunks_mut(&mut self) -> Box<dyn Iterator<Item = &mut Chunk> + '_> {
+        return self.grid.get_chunks_mut();
     }
 
-    /// Retrieves a mutable reference to the sources
-    pub fn get_mut(&mut self) -> &mut SourceMap {
-        return &mut self.map.sources;
+    fn chunk_count(&self) -> usize {
+        return self.grid.chunk_count();
     }
-}
 
-impl<'map> Drop for SourceMapMut<'map> {
-    fn drop(&mut self) {
-        // Force update the base resources for the map
-        self.map.populate_resources();
+    fn all_chunk_types_and_indices(&self) -> Vec<(ChunkType, usize)> {
+        return self.grid.all_chunk_types_and_indices();
     }
 }
 
-/// Holds all resource sources for an entire map
+/// A map consisting of a grid of chunks which repeats in all directions
 #[derive(Clone, Debug)]
-pub struct SourceMap {
-    /// The sources for nutrients
-    pub nutrients: Vec<Source>,
-    /// The sources for energy
-    pub energy: Vec<Source>,
-    /// The sources for water
-    pub water: Vec<Source>,
+pub struct MapToroidal {
+    /// All the chunks
+    grid: ChunkGrid,
 }
 
-/// The trait for any map of chunks, different layouts can be encoded in
-/// different types, all logic must go through this interface
-pub trait MapData: Debug {
-    /// Retrieves the index of the chunk at the given index coordinate, None if it is out of bounds
-    ///
-    /// # Parameters
-    ///
-    /// coordinates: The index coordinates to get the chunk for
-    fn get_index(&self, chunk_type: &ChunkType, coordinates: types::Index) -> Option<usize>;
-
-    /// Retrieves the chunk at the given index
+impl MapToroidal {
+    /// Constructs a new toroidal map with all base resources set to 0
     ///
     /// # Parameters
     ///
-    /// chunk_type: The type of chunk to retrieve
+    /// width: The number of chunks in the x-direction before repeating
     ///
-    /// index: The index of the chunk to retrieve
-    fn get_chunk(&self, chunk_type: &ChunkType, index: usize) -> &Chunk;
-
-    /// Retrieves the chunk at the given index as mutable
+    /// height: The number of chunks in the y-direction before repeating
     ///
-    /// # Parameters
+    /// chunk_size: The number of tiles from the center of a chunk to its edge
     ///
-    /// chunk_type: The type of chunk to retrieve
+    /// # Panics
     ///
-    /// index: The index of the chunk to retrieve
-    fn get_chunk_mut(&mut self, chunk_type: &ChunkType, index: usize) -> &mut Chunk;
+    /// Panics if the chunk size is 0
+    pub fn new(width: usize, height: usize, chunk_size: usize) -> Self {
+        return Self {
+            grid: ChunkGrid::new(width, height, chunk_size),
+        };
+    }
+}
 
-    /// Retrieves an iterator over all chunks
-    fn get_chunks(&self) -> Box<dyn Iterator<Item = &Chunk> + '_>;
+impl MapData for MapToroidal {
+    fn get_chunk_size(&self) -> usize {
+        return self.grid.chunk_size;
+    }
 
-    /// Retrieves an iterator over all mutable chunks
-    fn get_chunks_mut(&mut self) -> Box<dyn Iterator<Item = &mut Chunk> + '_>;
+    fn get_layout(&self) -> Option<MapLayout> {
+        return Some(MapLayout::Toroidal(self.grid.width, self.grid.height));
+    }
+
+    fn get_index(&self, _chunk_type: &ChunkType, coordinates: types::Index) -> Option<usize> {
+        if self.grid.width == 0 || self.grid.height == 0 {
+            return None;
+        }
+
+        // Wrap the coordinates around
+        let coordinates = types::Index::new(
+            coordinates.x.rem_euclid(self.grid.width as i64),
+            coordinates.y.rem_euclid(self.grid.height as i64),
+        );
+
+        return Some(self.grid.get_index(&coordinates));
+    }
+
+    fn get_coordinates(&self, _chunk_type: &ChunkType, index: usize) -> types::Index {
+        return self.grid.get_coordinates(index);
+    }
+
+    fn get_chunk(&self, chunk_type: &ChunkType, index: usize) -> &Chunk {
+        return self.grid.get_chunk(chunk_type, index);
+    }
+
+    fn get_chunk_mut(&mut self, chunk_type: &ChunkType, index: usize) -> &mut Chunk {
+        return self.grid.get_chunk_mut(chunk_type, index);
+    }
+
+    fn get_chunks(&self) -> Box<dyn Iterator<Item = &Chunk> + '_> {
+        return self.grid.get_chunks();
+    }
+
+    fn get_chunks_mut(&mut self) -> Box<dyn Iterator<Item = &mut Chunk> + '_> {
+        return self.grid.get_chunks_mut();
+    }
+
+    fn chunk_count(&self) -> usize {
+        return self.grid.chunk_count();
+    }
+
+    fn all_chunk_types_and_indices(&self) -> Vec<(ChunkType, usize)> {
+        return self.grid.all_chunk_types_and_indices();
+    }
 }
 
-/// A chunk of tiles clustered together can be used as the bulk, an edge or a vertex
+/// A rectangular grid of chunk positions, each position holds a bulk, all
+/// three edges and both vertices, the chunks are stored row by row
 #[derive(Clone, Debug)]
-pub struct Chunk {
-    /// All tiles for this chunk
-    tiles: Vec<Tile>,
-    /// The type of chunk stored
-    chunk_type: ChunkType,
-    /// The index of this chunk in the list of chunks
-    index: usize,
-    /// True if one of the tiles has been modified, false once the graphics has updated
-    modified: bool,
+struct ChunkGrid {
+    /// The number of chunks in the x-direction
+    width: usize,
+    /// The number of chunks in the y-direction
+    height: usize,
+    /// The number of tiles from the center of a chunk to its edge
+    chunk_size: usize,
+    /// The bulk of all the chunks
+    chunks_bulk: Vec<Chunk>,
+    /// All the different edges
+    chunks_edge: [Vec<Chunk>; 3],
+    /// All the different vertices
+    chunks_vertex: [Vec<Chunk>; 2],
 }
 
-impl Chunk {
-    /// Creates a new chunk from the given tiles
+impl ChunkGrid {
+    /// Constructs a new grid with all base resources set to 0
     ///
     /// # Parameters
     ///
-    /// chunk_type: The type of chunk which is being constructed
+    /// width: The number of chunks in the x-direction
     ///
-    /// index: The index of this chunk in the list of chunks
+    /// height: The number of chunks in the y-direction
     ///
-    /// tiles: The tiles to set for the new chunk
-    pub fn new(
-        chunk_type: ChunkType,
-        index: usize,
-        tiles: Vec<Tile>,
-    ) -> Result<Self, NewChunkError> {
-        if tiles.len() != chunk_type.get_tile_count() {
-            return Err(NewChunkError::InvalidSize(
-                tiles.len(),
-                chunk_type.get_tile_count(),
-            ));
-        }
+    /// chunk_size: The number of tiles from the center of a chunk to its edge
+    fn new(width: usize, height: usize, chunk_size: usize) -> Self {
+        let count = width * height;
+        let create = |chunk_type: ChunkType| {
+            return (0..count)
+                .map(|index| return Chunk::new_empty(chunk_type, index, chunk_size))
+                .collect::<Vec<Chunk>>();
+        };
 
-        return Ok(Self {
-            tiles,
-            chunk_type,
-            index,
-            modified: true,
-        });
+        return Self {
+            width,
+            height,
+            chunk_size,
+            chunks_bulk: create(ChunkType::Bulk),
+            chunks_edge: [
+                create(ChunkType::Edge(ChunkEdgeType::Top)),
+                create(ChunkType::Edge(ChunkEdgeType::Middle)),
+                create(ChunkType::Edge(ChunkEdgeType::Bottom)),
+            ],
+            chunks_vertex: [
+                create(ChunkType::Vertex(ChunkVertexType::Top)),
+                create(ChunkType::Vertex(ChunkVertexType::Bottom)),
+            ],
+        };
     }
 
-    /// Constructs a new chunk with all base resources set to 0
+    /// Retrieves the storage index of the chunk at the given coordinates,
+    /// the coordinates must be within the grid
     ///
     /// # Parameters
     ///
-    /// chunk_type: The type of chunk which is being constructed
-    ///
-    /// index: The index of this chunk in the list of chunks
-    pub fn new_empty(chunk_type: ChunkType, index: usize) -> Self {
-        // Get the number of tiles
-        let tile_count = chunk_type.get_tile_count();
-
-        // Create the tiles
-        let tiles = (0..tile_count)
-            .map(|_| {
-                return Tile::new(Resources {
-                    nutrients: 0.0,
-                    energy: 0.0,
-                    water: 0.0,
-                });
-            })
-            .collect::<Vec<Tile>>();
+    /// coordinates: The index coordinates of the chunk
+    fn get_index(&self, coordinates: &types::Index) -> usize {
+        return (coordinates.y as usize) * self.width + (coordinates.x as usize);
+    }
 
-        return Self::new(chunk_type, index, tiles).expect("Should not happen");
+    /// Retrieves the index coordinates of the chunk at the given storage index
+    ///
+    /// # Parameters
+    ///
+    /// index: The index of the chunk in the list of chunks
+    fn get_coordinates(&self, index: usize) -> types::Index {
+        return types::Index::new((index % self.width) as i64, (index / self.width) as i64);
     }
 
-    /// Checks if the chunk has been modified
-    pub fn is_modified(&self) -> bool {
-        return self.modified;
+    /// Retrieves the chunk at the given index
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of chunk to retrieve
+    ///
+    /// index: The index of the chunk to retrieve
+    fn get_chunk(&self, chunk_type: &ChunkType, index: usize) -> &Chunk {
+        return match chunk_type {
+            ChunkType::Bulk => &self.chunks_bulk[index],
+            ChunkType::Edge(edge_type) => &self.chunks_edge[edge_type.id()][index],
+            ChunkType::Vertex(vertex_type) => &self.chunks_vertex[vertex_type.id()][index],
+        };
     }
 
-    /// Sets the modified tag to false
-    pub fn resolved(&mut self) {
-        self.modified = false;
+    /// Retrieves the chunk at the given index as mutable
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of chunk to retrieve
+    ///
+    /// index: The index of the chunk to retrieve
+    fn get_chunk_mut(&mut self, chunk_type: &ChunkType, index: usize) -> &mut Chunk {
+        return match chunk_type {
+            ChunkType::Bulk => &mut self.chunks_bulk[index],
+            ChunkType::Edge(edge_type) => &mut self.chunks_edge[edge_type.id()][index],
+            ChunkType::Vertex(vertex_type) => &mut self.chunks_vertex[vertex_type.id()][index],
+        };
     }
 
-    /// Retrieves the index of this chunk in the list of chunks
-    pub fn get_index(&self) -> usize {
-        return self.index;
+    /// Retrieves an iterator over all chunks
+    fn get_chunks(&self) -> Box<dyn Iterator<Item = &Chunk> + '_> {
+        return Box::new(
+            self.chunks_bulk
+                .iter()
+                .chain(self.chunks_edge.iter().flatten())
+                .chain(self.chunks_vertex.iter().flatten()),
+        );
     }
 
-    /// Retrieves the type of this chunk
-    pub fn get_chunk_type(&self) -> &ChunkType {
-        return &self.chunk_type;
+    /// Retrieves an iterator over all mutable chunks
+    fn get_chunks_mut(&mut self) -> Box<dyn Iterator<Item = &mut Chunk> + '_> {
+        return Box::new(
+            self.chunks_bulk
+                .iter_mut()
+                .chain(self.chunks_edge.iter_mut().flatten())
+                .chain(self.chunks_vertex.iter_mut().flatten()),
+        );
     }
 
-    /// Retrieves a reference to the tiles
-    pub fn get_tiles(&self) -> &[Tile] {
-        return &self.tiles;
+    /// Retrieves the total number of stored chunks
+    fn chunk_count(&self) -> usize {
+        return ChunkType::all().len() * self.width * self.height;
     }
 
-    /// Retrieves a mutable reference to the tiles
-    pub fn get_tiles_mut(&mut self) -> &mut Vec<Tile> {
-        return &mut self.tiles;
+    /// Retrieves the chunk type and storage index of all stored chunks in the
+    /// same order as the chunks given by get_chunks
+    fn all_chunk_types_and_indices(&self) -> Vec<(ChunkType, usize)> {
+        let count = self.width * self.height;
+
+        return ChunkType::all()
+            .into_iter()
+            .flat_map(|chunk_type| return (0..count).map(move |index| return (chunk_type, index)))
+            .collect();
     }
 }
 
-/// The type of chunk
-#[derive(Clone, Copy, Debug)]
-pub enum ChunkType {
-    /// The largest type of chunk holding the bulk of the tiles
-    Bulk,
-    /// The edge chunk which surrounds the bulk
-    Edge(ChunkEdgeType),
-    /// The vertex chunk consisting only of a single tile
-    Vertex(ChunkVertexType),
+/// Holds all data for an entire map
+#[derive(Debug)]
+pub struct Map {
+    /// All chunk data, use different implementations of MapData for different chunk layouts
+    data: Box<dyn MapData>,
+    /// All sources of resources
+    sources: SourceMap,
+    /// The geometry of the chunks of the map
+    geometry: ChunkGeometry,
+    /// The entries which will undo the latest applied events, the last one is the newest
+    history: VecDeque<MapHistoryEntry>,
+    /// For each tile in the order of the chunks given by the map data, the
+    /// position in the same order of all six neighbours, None if the
+    /// neighbour is not part of the map
+    neighbours: Vec<[Option<usize>; 6]>,
+    /// The position of the first tile of each chunk when all tiles are
+    /// listed in the order of the chunks given by the map data
+    offsets: HashMap<(ChunkType, usize), usize>,
+    /// The gradient of the current resources, None if it must be recalculated
+    gradient: Option<ResourceGradient>,
+    /// The seed for all random generation, the random number generator is
+    /// reseeded every time the resources are populated
+    seed: u64,
+    /// The height of all tiles which reduces the contribution of the
+    /// sources, None if the map is flat
+    terrain: Option<TerrainMap>,
+    /// The factor from all applied noise layers for each resource of every
+    /// tile in the order of the chunks given by the map data, None if no
+    /// noise has been applied
+    noise: Option<Vec<Resources>>,
 }
 
-impl ChunkType {
-    /// Retrieves the number of tiles in the chunk
-    pub fn get_tile_count(&self) -> usize {
-        return match self {
-            ChunkType::Bulk => CHUNK_SIZE * (CHUNK_SIZE - 1) / 2 * 6 + 1,
-            ChunkType::Edge(_) => CHUNK_SIZE - 1,
-            ChunkType::Vertex(_) => 1,
+impl Map {
+    /// Constructs a new map and populates it with resources from the sources
+    ///
+    /// # Parameters
+    ///
+    /// data: The chunk data
+    ///
+    /// sources: All sources of resources
+    ///
+    /// seed: The seed for all random generation
+    pub fn new(data: Box<dyn MapData>, sources: SourceMap, seed: u64) -> Self {
+        // Create the map
+        let geometry = ChunkGeometry::new(data.get_chunk_size());
+        let mut map = Self {
+            data,
+            sources,
+            geometry,
+            history: VecDeque::new(),
+            neighbours: Vec::new(),
+            offsets: HashMap::new(),
+            gradient: None,
+            seed,
+            terrain: None,
+            noise: None,
         };
-    }
 
-    /// Retrieves centers relative to the center of the chunk for all tiles in
-    /// this chunk type, they are sorted in the same way that they are stored in
-    /// the chunk
-    pub fn get_tile_centers(&self) -> &[types::Point] {
-        return match self {
-            ChunkType::Bulk => CHUNK_CENTERS_BULK.as_slice(),
-            ChunkType::Edge(edge) => edge.get_tile_centers(),
-            ChunkType::Vertex(vertex) => vertex.get_tile_centers(),
-        };
-    }
-}
+        // Populate and start all current resources at the base resources
+        map.populate_resources();
+        map.reset_to_base();
 
-/// The location of the edge chunk compared to the bulk
-#[derive(Clone, Copy, Debug)]
-pub enum ChunkEdgeType {
-    /// This is the top left edge
-    Top,
-    /// This is the left edge
-    Middle,
-    /// This is the bottom left edge
-    Bottom,
-}
+        // Find the neighbours and the offsets of all tiles
+        map.neighbours = map.find_neighbours();
+        map.offsets = map.chunk_offsets();
 
-impl ChunkEdgeType {
-    /// Retrieves the id of this type of edge piece
-    pub fn id(&self) -> usize {
-        return match self {
-            ChunkEdgeType::Top => 0,
-            ChunkEdgeType::Middle => 1,
-            ChunkEdgeType::Bottom => 2,
-        };
+        return map;
     }
 
-    /// Retrieves centers relative to the center of the chunk for all tiles in
-    /// this chunk type, they are sorted in the same way that they are stored in
-    /// the chunk
-    pub fn get_tile_centers(&self) -> &[types::Point] {
-        return match self {
-            ChunkEdgeType::Top => CHUNK_CENTERS_EDGE_TOP.as_slice(),
-            ChunkEdgeType::Middle => CHUNK_CENTERS_EDGE_MIDDLE.as_slice(),
-            ChunkEdgeType::Bottom => CHUNK_CENTERS_EDGE_BOTTOM.as_slice(),
-        };
+    /// Creates a builder for an empty map with the given seed for all random
+    /// generation
+    ///
+    /// # Parameters
+    ///
+    /// seed: The seed for all random generation
+    pub fn with_seed(seed: u64) -> MapBuilder {
+        return MapBuilder::new(SourceMap::default()).with_seed(seed);
     }
-}
 
-/// The location of the vertec chunk compared to the bulk
-#[derive(Clone, Copy, Debug)]
-pub enum ChunkVertexType {
-    /// The top left vertex
-    Top,
-    /// The bottom left vertex
-    Bottom,
-}
+    /// Retrieves the seed used for all random generation
+    pub fn get_seed(&self) -> u64 {
+        return self.seed;
+    }
 
-impl ChunkVertexType {
-    /// Retrieves the id of this type of vertex
-    pub fn id(&self) -> usize {
-        return match self {
-            ChunkVertexType::Top => 0,
-            ChunkVertexType::Bottom => 0,
-        };
+    /// Resets the current resources of all tiles to their base resources
+    /// without evaluating the sources again, all chunks are marked as modified
+    pub fn reset_to_base(&mut self) {
+        self.gradient = None;
+        self.data.get_chunks_mut().for_each(|chunk| {
+            chunk.modified = true;
+            chunk.tiles.iter_mut().for_each(|tile| {
+                tile.current_resources = tile.base_resources;
+            });
+        });
     }
 
-    /// Retrieves centers relative to the center of the chunk for all tiles in
-    /// this chunk type, they are sorted in the same way that they are stored in
-    /// the chunk
-    pub fn get_tile_centers(&self) -> &[types::Point] {
-        return match self {
-            ChunkVertexType::Top => CHUNK_CENTERS_VERTEX_TOP.as_slice(),
-            ChunkVertexType::Bottom => CHUNK_CENTERS_VERTEX_BOTTOM.as_slice(),
-        };
+    /// Retrieves the terrain of the map, None if the map is flat
+    pub fn get_terrain(&self) -> Option<&TerrainMap> {
+        return self.terrain.as_ref();
     }
-}
 
-static CHUNK_CENTERS_BULK: Lazy<[types::Point; 3 * CHUNK_SIZE * (CHUNK_SIZE - 1) + 1]> =
-    Lazy::new(|| {
-        std::array::from_fn(|id| {
-            // Do id == 0 as a special case
-            if id == 0 {
-                return types::Point::new(0.0, 0.0);
+    /// Sets the terrain of the map and repopulates the base resources
+    ///
+    /// # Parameters
+    ///
+    /// terrain: The new terrain, the heights must be in the order of the
+    /// chunks given by the map data
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if the number of heights does not match the
+    /// number of tiles
+    pub fn set_terrain(&mut self, terrain: TerrainMap) {
+        if cfg!(debug_assertions) {
+            let count = self
+                .data
+                .get_chunks()
+                .map(|chunk| return chunk.get_tiles().len())
+                .sum::<usize>();
+            if terrain.heights.len() != count {
+                panic!(
+                    "The terrain has {:?} heights but the map has {:?} tiles",
+                    terrain.heights.len(),
+                    count
+                );
             }
+        }
 
-            // Get the layer and location
-            let layer =
-                (0.5 * (1.0 + (1.0 + 4.0 / 3.0 * ((id as f64) - 1.0)).sqrt())).floor() as usize;
-            let rel_id = id - (3 * layer * (layer - 1) + 1);
-            let slice_id = rel_id / layer;
-            let location_id = rel_id - slice_id * layer;
-
-            // Get the coordinates
-            let (start, dir) = match slice_id {
-                0 => (
-                    types::Point::new(0.0, 1.0),
-                    types::Point::new(-1.5 * INV_SQRT_3, -0.5),
-                ),
-                1 => (
-                    types::Point::new(-1.5 * INV_SQRT_3, 0.5),
-                    types::Point::new(0.0, -1.0),
-                ),
-                2 => (
-                    types::Point::new(-1.5 * INV_SQRT_3, -0.5),
-                    types::Point::new(1.5 * INV_SQRT_3, -0.5),
-                ),
-                3 => (
-                    types::Point::new(0.0, -1.0),
-                    types::Point::new(1.5 * INV_SQRT_3, 0.5),
-                ),
-                4 => (
-                    types::Point::new(1.5 * INV_SQRT_3, -0.5),
-                    types::Point::new(0.0, 1.0),
-                ),
-                _ => (
-                    types::Point::new(1.5 * INV_SQRT_3, 0.5),
-                    types::Point::new(-1.5 * INV_SQRT_3, 0.5),
-                ),
-            };
+        self.terrain = Some(terrain);
+        self.populate_resources();
+    }
 
-            return start * (layer as f64) + dir * (location_id as f64);
-        })
-    });
-static CHUNK_CENTERS_EDGE_TOP: Lazy<[types::Point; CHUNK_SIZE - 1]> = Lazy::new(|| {
-    std::array::from_fn(|id| {
-        return types::Point::new(
-            -1.5 * INV_SQRT_3 * ((id + 1) as f64),
-            (CHUNK_SIZE as f64) - 0.5 * ((id + 1) as f64),
-        );
-    })
-});
-static CHUNK_CENTERS_EDGE_MIDDLE: Lazy<[types::Point; CHUNK_SIZE - 1]> = Lazy::new(|| {
-    std::array::from_fn(|id| {
-        return types::Point::new(
-            -1.5 * (CHUNK_SIZE as f64) * INV_SQRT_3,
-            0.5 * (CHUNK_SIZE as f64) - ((id + 1) as f64),
-        );
-    })
-});
-static CHUNK_CENTERS_EDGE_BOTTOM: Lazy<[types::Point; CHUNK_SIZE - 1]> = Lazy::new(|| {
-    std::array::from_fn(|id| {
-        return types::Point::new(
-            -1.5 * ((CHUNK_SIZE - (id + 1)) as f64) * INV_SQRT_3,
-            -0.5 * ((CHUNK_SIZE + (id + 1)) as f64),
-        );
-    })
-});
-static CHUNK_CENTERS_VERTEX_TOP: Lazy<[types::Point; 1]> = Lazy::new(|| {
-    [types::Point {
-        x: -1.5 * (CHUNK_SIZE as f64) * INV_SQRT_3,
-        y: 0.5 * (CHUNK_SIZE as f64),
-    }]
-});
-static CHUNK_CENTERS_VERTEX_BOTTOM: Lazy<[types::Point; 1]> = Lazy::new(|| {
-    [types::Point {
-        x: -1.5 * (CHUNK_SIZE as f64) * INV_SQRT_3,
-        y: -0.5 * (CHUNK_SIZE as f64),
-    }]
-});
+    /// Retrieves the number of tiles from the center of a chunk to its edge
+    pub fn get_chunk_size(&self) -> usize {
+        return self.geometry.get_chunk_size();
+    }
 
-/// All data for a single tile including the base resources and current resources
-#[derive(Clone, Copy, Debug)]
-pub struct Tile {
-    /// The base values for the resources which the actual values will attempt to gravitate towards
-    pub base_resources: Resources,
-}
+    /// Retrieves the geometry of the chunks of this map
+    pub fn get_geometry(&self) -> &ChunkGeometry {
+        return &self.geometry;
+    }
 
-impl Tile {
-    /// Constructs a new til with the given base resources
+    /// Calculates what chunk the given cartesian coordinate is within and returns its chunk index
     ///
     /// # Parameters
     ///
-    /// base_resources: The base resources of this tile
-    pub fn new(base_resources: Resources) -> Self {
-        return Self { base_resources };
+    /// point: The cartesian coordinates to use
+    pub fn coordinate_to_chunk(&self, point: &types::Point) -> types::Index {
+        return self.geometry.coordinate_to_chunk(point);
     }
-}
 
-/// All the main resource types
-#[derive(Clone, Copy, Debug)]
-pub struct Resources {
-    /// Nutrients used to grow
-    pub nutrients: f64,
-    /// Energy used to survive
-    pub energy: f64,
-    /// Water used to allow processes to work
-    pub water: f64,
-}
+    /// Calculates the center cartesian coordinate of the given chunk
+    ///
+    /// # Parameters
+    ///
+    /// index: The index of the chunk to use
+    pub fn chunk_to_coordinate(&self, index: &types::Index) -> types::Point {
+        return self.geometry.chunk_to_coordinate(index);
+    }
 
-/// A source of some resource
-#[derive(Clone, Debug)]
+    /// Calculates the axis aligned bounding box of all stored chunks in world
+    /// coordinates, returns None if no chunks are stored
+    pub fn get_bounding_box(&self) -> Option<types::View> {
+        let corners = self
+            .data
+            .all_chunk_types_and_indices()
+            .into_iter()
+            .flat_map(|(chunk_type, chunk_id)| {
+                let center =
+                    self.chunk_to_coordinate(&self.data.get_coordinates(&chunk_type, chunk_id));
+                let bounding_box = self
+                    .data
+                    .get_chunk(&chunk_type, chunk_id)
+                    .bounding_box_world(&self.geometry, &center);
+                let half_size = types::Point::new(
+                    bounding_box.get_size().w * 0.5,
+                    bounding_box.get_size().h * 0.5,
+                );
+
+                return [
+                    *bounding_box.get_center() - half_size,
+                    *bounding_box.get_center() + half_size,
+                ];
+            })
+            .collect::<Vec<types::Point>>();
+
+        return types::View::from_points(&corners);
+    }
+
+    /// Retrieves centers relative to the center of the chunk for all tiles in
+    /// the given chunk type
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of chunk to get the tile centers for
+    pub fn get_tile_centers(&self, chunk_type: &ChunkType) -> &[types::Point] {
+        return self.geometry.get_tile_centers(chunk_type);
+    }
+
+    /// Retrieves a reference to the chunk data
+    pub fn get_data(&self) -> &dyn MapData {
+        return self.data.as_ref();
+    }
+
+    /// Retrieves a mutable reference to the chunk data, this invalidates the
+    /// cached resource gradient
+    pub fn get_data_mut(&mut self) -> &mut dyn MapData {
+        self.gradient = None;
+        return self.data.as_mut();
+    }
+
+    /// Retrieves a reference to the source map
+    pub fn get_sources(&self) -> &SourceMap {
+        return &self.sources;
+    }
+
+    /// Retrieves the sources for a single resource type
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to get the sources for
+    pub fn get_resource_sources(&self, resource_type: ResourceType) -> &[Source] {
+        return self.sources.get(resource_type);
+    }
+
+    /// Retrieves a mutable reference to the source map, once the mutator is
+    /// destroyed it will reload the base resources of the map
+    pub fn get_sources_mut(&mut self) -> SourceMapMut {
+        return SourceMapMut { map: self };
+    }
+
+    /// Replaces all sources for a single resource type and reloads the base
+    /// resources of the map
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to set the sources for
+    ///
+    /// sources: The new sources
+    pub fn set_sources(&mut self, resource_type: ResourceType, sources: Vec<Source>) {
+        *self.get_sources_mut().get_mut().get_mut(resource_type) = sources;
+    }
+
+    /// Removes all sources for a single resource type and reloads the base
+    /// resources of the map
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to remove the sources for
+    pub fn clear_sources(&mut self, resource_type: ResourceType) {
+        self.set_sources(resource_type, Vec::new());
+    }
+
+    /// Rescales all sources such that the largest unclamped base resource of
+    /// each resource type is 1 and reloads the base resources of the map, the
+    /// maximum includes the terrain, the noise layers and all periodic images
+    /// of the sources, resource types without any positive values are left
+    /// unchanged
+    pub fn normalize_sources(&mut self) {
+        let factors = (0..self.iter_tiles().count())
+            .map(|tile| return self.source_factor(tile))
+            .collect::<Vec<Resources>>();
+        self.sources
+            .normalize_with_factors(self.data.as_ref(), Some(&factors));
+        self.populate_resources();
+    }
+
+    /// Finds the chunk holding the tile with the given tile index, returns the
+    /// chunk type, the index of the chunk and the index of the tile within the
+    /// chunk or None if the tile is not part of the map
+    ///
+    /// # Parameters
+    ///
+    /// index: The tile index of the tile to find
+    pub fn get_tile_location(&self, index: &types::Index) -> Option<(ChunkType, usize, usize)> {
+        return self.data.get_tile_location(&self.geometry, index);
+    }
+
+    /// Retrieves the tile with the given tile index or None if it is not part of the map
+    ///
+    /// # Parameters
+    ///
+    /// index: The tile index of the tile to retrieve
+    pub fn get_tile(&self, index: &types::Index) -> Option<&Tile> {
+        return self.data.get_tile_at_index(&self.geometry, index);
+    }
+
+    /// Retrieves the mutable tile with the given tile index or None if it is
+    /// not part of the map, this invalidates the cached resource gradient
+    ///
+    /// # Parameters
+    ///
+    /// index: The tile index of the tile to retrieve
+    pub fn get_tile_mut(&mut self, index: &types::Index) -> Option<&mut Tile> {
+        let tile = self.data.get_tile_at_index_mut(&self.geometry, index)?;
+        self.gradient = None;
+
+        return Some(tile);
+    }
+
+    /// Finds the position of the tile with the given tile index when all
+    /// tiles are listed in the order of the chunks given by the map data, this
+    /// is the position used for the resource gradient, returns None if the
+    /// tile is not part of the map
+    ///
+    /// # Parameters
+    ///
+    /// index: The tile index of the tile to find
+    pub fn get_tile_id(&self, index: &types::Index) -> Option<usize> {
+        let (chunk_type, chunk_id, tile_id) = self.get_tile_location(index)?;
+
+        return Some(self.offsets[&(chunk_type, chunk_id)] + tile_id);
+    }
+
+    /// Adds a noise layer multiplying the contribution of the sources of a
+    /// resource to every tile by a noise value evaluated at the center of the
+    /// tile and repopulates the base resources, the noise is clamped to
+    /// [0, 2] and the base resources are clamped to [0, 1] afterwards, the
+    /// layer is kept when the sources or the terrain change and several layers
+    /// are multiplied together
+    ///
+    /// # Parameters
+    ///
+    /// resource: The resource type to apply the noise to
+    ///
+    /// noise_fn: The noise value at a point in world coordinates
+    pub fn apply_noise_layer(
+        &mut self,
+        resource: ResourceType,
+        noise_fn: impl Fn(&types::Point) -> f64,
+    ) {
+        let values = self
+            .iter_tiles()
+            .map(|(_, center)| return noise_fn(&center).clamp(0.0, 2.0))
+            .collect::<Vec<f64>>();
+
+        self.noise
+            .get_or_insert_with(|| return vec![Resources::new(1.0, 1.0, 1.0); values.len()])
+            .iter_mut()
+            .zip(values)
+            .for_each(|(factors, value)| *factors.get_mut(resource) *= value);
+
+        self.populate_resources();
+    }
+
+    /// Removes all noise layers and repopulates the base resources
+    pub fn clear_noise_layers(&mut self) {
+        self.noise = None;
+        self.populate_resources();
+    }
+
+    /// Calculates the tile index of the tile at the given location
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of the chunk holding the tile
+    ///
+    /// chunk_id: The storage index of the chunk holding the tile
+    ///
+    /// tile_id: The index of the tile within the chunk
+    pub fn get_tile_index(
+        &self,
+        chunk_type: &ChunkType,
+        chunk_id: usize,
+        tile_id: usize,
+    ) -> types::Index {
+        let chunk_center =
+            self.chunk_to_coordinate(&self.data.get_coordinates(chunk_type, chunk_id));
+
+        return coordinate_to_tile(&(chunk_center + self.get_tile_centers(chunk_type)[tile_id]));
+    }
+
+    /// Finds the location of all tiles for which the predicate is true,
+    /// returns the chunk type, the storage index of the chunk and the index
+    /// of the tile within the chunk for each of them
+    ///
+    /// # Parameters
+    ///
+    /// predicate: The function deciding whether a tile should be included
+    pub fn find_tiles_where(
+        &self,
+        predicate: impl Fn(&Tile) -> bool,
+    ) -> Vec<(ChunkType, usize, usize)> {
+        return self
+            .data
+            .get_chunks()
+            .flat_map(|chunk| {
+                let chunk_type = *chunk.get_chunk_type();
+                let chunk_id = chunk.get_index();
+
+                return chunk
+                    .get_tiles()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, tile)| return predicate(tile))
+                    .map(move |(tile_id, _)| return (chunk_type, chunk_id, tile_id));
+            })
+            .collect();
+    }
+
+    /// Finds the tile indices of all tiles where the current amount of the
+    /// given resource is above the threshold
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to check
+    ///
+    /// threshold: The amount the resource must exceed
+    pub fn find_tiles_above_threshold(
+        &self,
+        resource_type: ResourceType,
+        threshold: f64,
+    ) -> Vec<types::Index> {
+        return self
+            .find_tiles_where(|tile| return tile.current_resources.get(resource_type) > threshold)
+            .into_iter()
+            .map(|(chunk_type, chunk_id, tile_id)| {
+                return self.get_tile_index(&chunk_type, chunk_id, tile_id);
+            })
+            .collect();
+    }
+
+    /// Finds the tile indices of all tiles connected to the start tile
+    /// through neighbouring tiles for which the predicate is true, the start
+    /// tile is included if the predicate is true for it, on cyclic maps each
+    /// tile is only included once
+    ///
+    /// # Parameters
+    ///
+    /// start: The tile index of the tile to start from
+    ///
+    /// predicate: The function deciding whether a tile is part of the region
+    pub fn flood_fill_tiles(
+        &self,
+        start: types::Index,
+        predicate: impl Fn(&Tile) -> bool,
+    ) -> Vec<types::Index> {
+        let mut region = Vec::new();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(index) = queue.pop_front() {
+            // Tiles outside of the map or visited under another index are skipped
+            let location = match self.get_tile_location(&index) {
+                Some(value) => value,
+                None => continue,
+            };
+            if !visited.insert(location) {
+                continue;
+            }
+
+            let (chunk_type, chunk_id, tile_id) = location;
+            if !predicate(&self.data.get_chunk(&chunk_type, chunk_id).get_tiles()[tile_id]) {
+                continue;
+            }
+
+            region.push(index);
+            queue.extend(index.hex_neighbors());
+        }
+
+        return region;
+    }
+
+    /// Finds all connected clusters of tiles where the current amount of the
+    /// given resource is at least the threshold, the clusters are sorted by
+    /// size with the largest first
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to check
+    ///
+    /// threshold: The smallest amount the resource may have
+    pub fn compute_resource_clusters(
+        &self,
+        resource_type: ResourceType,
+        threshold: f64,
+    ) -> Vec<Vec<types::Index>> {
+        let predicate = |tile: &Tile| return tile.current_resources.get(resource_type) >= threshold;
+        let mut visited = HashSet::new();
+
+        let mut clusters =
+            self.find_tiles_where(predicate)
+                .into_iter()
+                .filter_map(|location| {
+                    if visited.contains(&location) {
+                        return None;
+                    }
+
+                    let (chunk_type, chunk_id, tile_id) = location;
+                    let cluster = self.flood_fill_tiles(
+                        self.get_tile_index(&chunk_type, chunk_id, tile_id),
+                        predicate,
+                    );
+                    visited.extend(cluster.iter().map(|index| {
+                        return self.get_tile_location(index).expect("Should not happen");
+                    }));
+
+                    return Some(cluster);
+                })
+                .collect::<Vec<Vec<types::Index>>>();
+
+        clusters.sort_by(|a, b| return b.len().cmp(&a.len()));
+
+        return clusters;
+    }
+
+    /// Finds the centroid of the tile centers of the largest cluster of tiles
+    /// where the current amount of the given resource is at least the
+    /// threshold, returns None if no tile reaches the threshold, on toroidal
+    /// maps a cluster crossing the edge is averaged as it was found
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to check
+    ///
+    /// threshold: The smallest amount the resource may have
+    pub fn largest_cluster_center(
+        &self,
+        resource_type: ResourceType,
+        threshold: f64,
+    ) -> Option<types::Point> {
+        let clusters = self.compute_resource_clusters(resource_type, threshold);
+        let cluster = clusters.first()?;
+
+        let sum = cluster
+            .iter()
+            .fold(types::Point::new(0.0, 0.0), |sum, index| {
+                return sum + tile_to_coordinate(index);
+            });
+
+        return Some(sum / cluster.len() as f64);
+    }
+
+    /// Retrieves the tile containing the given cartesian coordinate or None if
+    /// it is not part of the map
+    ///
+    /// # Parameters
+    ///
+    /// point: The cartesian coordinates to use
+    pub fn get_tile_at_world(&self, point: &types::Point) -> Option<&Tile> {
+        return self.get_tile(&coordinate_to_tile(point));
+    }
+
+    /// Samples the current resources at the given cartesian coordinate by
+    /// interpolating between the centers of the three closest tiles, tiles
+    /// which are not part of the map are ignored and if the point is outside
+    /// of the map the resources are all 0
+    ///
+    /// # Parameters
+    ///
+    /// point: The cartesian coordinates to sample at
+    pub fn sample_resources_at(&self, point: &types::Point) -> Resources {
+        // Find the tile containing the point
+        let index = coordinate_to_tile(point);
+        let tile = match self.get_tile(&index) {
+            Some(value) => value,
+            None => return Resources::new(0.0, 0.0, 0.0),
+        };
+        let center = tile_to_coordinate(&index);
+        let offset = *point - center;
+
+        // Find the triangle between the tile and two neighbours containing the point
+        let neighbours = index.hex_neighbors();
+        let (weight_first, weight_second, first, second) = (0..6)
+            .map(|id| {
+                let first = neighbours[id];
+                let second = neighbours[(id + 1) % 6];
+                let dir_first = tile_to_coordinate(&first) - center;
+                let dir_second = tile_to_coordinate(&second) - center;
+
+                // Calculate the barycentric coordinates
+                let det = dir_first.x * dir_second.y - dir_first.y * dir_second.x;
+                let weight_first = (offset.x * dir_second.y - offset.y * dir_second.x) / det;
+                let weight_second = (dir_first.x * offset.y - dir_first.y * offset.x) / det;
+
+                return (weight_first, weight_second, first, second);
+            })
+            .max_by(|(a_first, a_second, _, _), (b_first, b_second, _, _)| {
+                return a_first.min(*a_second).total_cmp(&b_first.min(*b_second));
+            })
+            .expect("Should not happen");
+
+        // Blend the resources of the tiles which exist
+        let weight_center = 1.0 - weight_first - weight_second;
+        let (total, weight) = [
+            (Some(tile), weight_center),
+            (self.get_tile(&first), weight_first),
+            (self.get_tile(&second), weight_second),
+        ]
+        .into_iter()
+        .filter_map(|(tile, weight)| return tile.map(|tile| (tile.current_resources, weight)))
+        .fold(
+            (Resources::new(0.0, 0.0, 0.0), 0.0),
+            |(prev_total, prev_weight), (resources, weight)| {
+                return (prev_total + resources * weight, prev_weight + weight);
+            },
+        );
+
+        return total * (1.0 / weight);
+    }
+
+    /// Calculates the sum of the base resources of all tiles
+    pub fn total_resources(&self) -> Resources {
+        return self
+            .data
+            .get_chunks()
+            .flat_map(|chunk| return chunk.get_tiles().iter())
+            .fold(Resources::new(0.0, 0.0, 0.0), |prev, tile| {
+                return prev + tile.base_resources;
+            });
+    }
+
+    /// Calculates the sum of the current resources of all tiles
+    pub fn total_current_resources(&self) -> Resources {
+        return self
+            .data
+            .get_chunks()
+            .flat_map(|chunk| return chunk.get_tiles().iter())
+            .fold(Resources::new(0.0, 0.0, 0.0), |prev, tile| {
+                return prev + tile.current_resources;
+            });
+    }
+
+    /// Retrieves the center in world coordinates and the current resources of
+    /// every tile in the order of the chunks given by the map data
+    pub fn heat_map_snapshot(&self) -> Vec<(types::Point, Resources)> {
+        return self
+            .iter_tiles()
+            .map(|(tile, center)| return (center, tile.current_resources))
+            .collect();
+    }
+
+    /// Assigns every tile to the source closest to its center, returns the
+    /// index into sources of the closest source for every tile in the same
+    /// order as iter_tiles
+    ///
+    /// # Parameters
+    ///
+    /// sources: The positions of the sources in world coordinates
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if there are no sources
+    pub fn compute_voronoi_regions(&self, sources: &[types::Point]) -> Vec<usize> {
+        if cfg!(debug_assertions) && sources.is_empty() {
+            panic!("At least one source is required for the voronoi regions");
+        }
+
+        return self
+            .iter_tiles()
+            .map(|(_, center)| {
+                return sources
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| {
+                        return (**a - center)
+                            .norm_squared()
+                            .total_cmp(&(**b - center).norm_squared());
+                    })
+                    .map(|(id, _)| return id)
+                    .unwrap_or(0);
+            })
+            .collect();
+    }
+
+    /// Writes the heat map snapshot to a csv file with a header line and one
+    /// line of x,y,nutrients,energy,water for each tile
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the file to write
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be written
+    pub fn export_csv(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "x,y,nutrients,energy,water")?;
+        for (center, resources) in self.heat_map_snapshot() {
+            writeln!(
+                writer,
+                "{},{},{},{},{}",
+                center.x, center.y, resources.nutrients, resources.energy, resources.water,
+            )?;
+        }
+        writer.flush()?;
+
+        return Ok(());
+    }
+
+    /// Retrieves the gradient of the current resources for all tiles, it is
+    /// only recalculated if the resources have changed since the last call
+    pub fn compute_gradient(&mut self) -> &ResourceGradient {
+        if self.gradient.is_none() {
+            self.gradient = Some(self.calculate_gradient());
+        }
+
+        return self.gradient.as_ref().expect("Should not happen");
+    }
+
+    /// Calculates the gradient of the current resources for all tiles by
+    /// fitting a plane through the differences to all neighbours which exist
+    fn calculate_gradient(&self) -> ResourceGradient {
+        // Get the current state
+        let current = self
+            .data
+            .get_chunks()
+            .flat_map(|chunk| return chunk.get_tiles().iter())
+            .map(|tile| return tile.current_resources)
+            .collect::<Vec<Resources>>();
+
+        // Get the direction to each neighbour
+        let directions = types::Index::new(0, 0)
+            .hex_neighbors()
+            .map(|neighbour| return neighbour.to_tile_center());
+
+        // Calculate the gradient for each tile
+        let mut gradient = ResourceGradient {
+            nutrients: Vec::with_capacity(current.len()),
+            energy: Vec::with_capacity(current.len()),
+            water: Vec::with_capacity(current.len()),
+        };
+        current
+            .iter()
+            .zip(self.neighbours.iter())
+            .for_each(|(resources, neighbours)| {
+                // Sum up the outer products of the directions and the weighted differences
+                let (outer, nutrients, energy, water) = neighbours
+                    .iter()
+                    .zip(directions.iter())
+                    .filter_map(|(neighbour, dir)| {
+                        return neighbour.map(|id| return (current[id] - *resources, dir));
+                    })
+                    .fold(
+                        (
+                            types::Matrix::zero(),
+                            types::Point::new(0.0, 0.0),
+                            types::Point::new(0.0, 0.0),
+                            types::Point::new(0.0, 0.0),
+                        ),
+                        |(outer, nutrients, energy, water), (diff, dir)| {
+                            return (
+                                outer + types::Matrix::outer_product(dir, dir),
+                                nutrients + *dir * diff.nutrients,
+                                energy + *dir * diff.energy,
+                                water + *dir * diff.water,
+                            );
+                        },
+                    );
+
+                // Solve the least squares problem, without enough neighbours the gradient is 0
+                if outer.det().abs() < 1e-10 {
+                    gradient.nutrients.push(types::Point::new(0.0, 0.0));
+                    gradient.energy.push(types::Point::new(0.0, 0.0));
+                    gradient.water.push(types::Point::new(0.0, 0.0));
+                    return;
+                }
+                let inverse = outer.inv();
+                gradient.nutrients.push(inverse * nutrients);
+                gradient.energy.push(inverse * energy);
+                gradient.water.push(inverse * water);
+            });
+
+        return gradient;
+    }
+
+    /// Lets the current resources diffuse to the neighbouring tiles while
+    /// gravitating towards the base resources
+    ///
+    /// # Parameters
+    ///
+    /// dt: The time step to advance
+    ///
+    /// rate: The diffusion rate between neighbouring tiles
+    pub fn step_diffusion(&mut self, dt: f64, rate: f64) {
+        // The gradient is no longer valid
+        self.gradient = None;
+
+        // Get the current state
+        let current = self
+            .data
+            .get_chunks()
+            .flat_map(|chunk| return chunk.get_tiles().iter())
+            .map(|tile| return tile.current_resources)
+            .collect::<Vec<Resources>>();
+
+        // Calculate the change of each tile
+        let change = current
+            .iter()
+            .zip(self.neighbours.iter())
+            .map(|(resources, neighbours)| {
+                return neighbours
+                    .iter()
+                    .filter_map(|neighbour| return neighbour.map(|id| return current[id]))
+                    .fold(Resources::new(0.0, 0.0, 0.0), |prev, next| {
+                        return prev + (next - *resources);
+                    })
+                    * rate;
+            })
+            .collect::<Vec<Resources>>();
+
+        // Apply the change
+        self.data
+            .get_chunks_mut()
+            .for_each(|chunk| chunk.modified = true);
+        self.data
+            .get_chunks_mut()
+            .flat_map(|chunk| return chunk.tiles.iter_mut())
+            .zip(change.iter())
+            .for_each(|(tile, change)| {
+                let relaxation =
+                    (tile.base_resources - tile.current_resources) * RESOURCE_RELAXATION_RATE;
+                tile.current_resources = tile.current_resources + (*change + relaxation) * dt;
+            });
+    }
+
+    /// Applies the event to the map and stores it such that it can be undone,
+    /// only the latest events are stored
+    ///
+    /// # Parameters
+    ///
+    /// event: The event to apply
+    pub fn apply_event(&mut self, event: MapEvent) -> Result<(), MapEventError> {
+        // Events changing the sources reload all base resources
+        let base_resources = match &event {
+            MapEvent::SetBaseResource(_, _) => None,
+            MapEvent::AddSource(_, _)
+            | MapEvent::InsertSource(_, _, _)
+            | MapEvent::RemoveSource(_, _) => Some(self.get_base_resources()),
+        };
+
+        let inverse = self.process_event(event)?;
+        self.push_history(MapHistoryEntry {
+            inverse,
+            base_resources,
+        });
+
+        return Ok(());
+    }
+
+    /// Adds a source to the end of the sources for the given resource type
+    /// like applying MapEvent::AddSource but only the contribution of the
+    /// new source is evaluated, returns the index of the new source, it can
+    /// be undone like any other event
+    ///
+    /// The result is identical to reloading all sources as long as no source
+    /// gives a negative contribution
+    ///
+    /// # Parameters
+    ///
+    /// source: The source to add
+    ///
+    /// resource: The resource type to add the source to
+    pub fn add_source_interactive(&mut self, source: Source, resource: ResourceType) -> usize {
+        // Undoing reloads all base resources so they must be restored afterwards
+        let base_resources = self.get_base_resources();
+
+        // Add the contribution of the new source and clamp
+        let offsets = self.chunk_offsets();
+        self.add_source_contribution(&source, &offsets, &|resources: &mut Resources| {
+            return resources.get_mut(resource);
+        });
+        self.data.get_chunks_mut().for_each(|chunk| {
+            chunk.tiles.iter_mut().for_each(|tile| {
+                let value = tile.base_resources.get_mut(resource);
+                *value = value.clamp(0.0, 1.0);
+            });
+        });
+        self.gradient = None;
+
+        // Store the source and remember how to undo it
+        let sources = self.sources.get_mut(resource);
+        sources.push(source);
+        let index = sources.len() - 1;
+        self.push_history(MapHistoryEntry {
+            inverse: MapEvent::RemoveSource(resource, index),
+            base_resources: Some(base_resources),
+        });
+
+        return index;
+    }
+
+    /// Adds a nutrient line source along every edge of the road graph and
+    /// returns a terrain where the tiles on the roads are raised, the terrain
+    /// is not applied to the map
+    ///
+    /// # Parameters
+    ///
+    /// graph: The roads to add
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if an edge refers to a node which does not exist
+    pub fn generate_roads(&mut self, graph: &RoadGraph) -> TerrainMap {
+        let segments = graph
+            .edges
+            .iter()
+            .filter_map(|(start, end)| {
+                return match (graph.nodes.get(*start), graph.nodes.get(*end)) {
+                    (Some(start), Some(end)) => Some((*start, *end)),
+                    _ => {
+                        if cfg!(debug_assertions) {
+                            panic!(
+                                "The road ({:?}, {:?}) refers to a node outside of the {:?} nodes",
+                                start,
+                                end,
+                                graph.nodes.len()
+                            );
+                        }
+                        None
+                    }
+                };
+            })
+            .collect::<Vec<(types::Point, types::Point)>>();
+
+        // Add the roads as sources
+        self.get_sources_mut()
+            .get_mut()
+            .nutrients
+            .extend(segments.iter().map(|(start, end)| {
+                return Source::Line {
+                    start: *start,
+                    end: *end,
+                    width: RoadGraph::ROAD_WIDTH,
+                    value: RoadGraph::ROAD_VALUE,
+                };
+            }));
+
+        // Raise all tiles within the width of a road
+        let heights = self
+            .iter_tiles()
+            .map(|(_, center)| {
+                let on_road = segments.iter().any(|(start, end)| {
+                    return center.distance_to_segment(start, end) <= RoadGraph::ROAD_WIDTH;
+                });
+
+                return if on_road { RoadGraph::ROAD_HEIGHT } else { 0.0 };
+            })
+            .collect();
+
+        return TerrainMap { heights };
+    }
+
+    /// Stores an entry which undoes the latest change, only the latest
+    /// entries are stored
+    ///
+    /// # Parameters
+    ///
+    /// entry: The entry which undoes the change
+    fn push_history(&mut self, entry: MapHistoryEntry) {
+        if self.history.len() >= MAP_HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back(entry);
+    }
+
+    /// Reverses the latest applied event, returns false if there are no
+    /// events to undo
+    pub fn undo(&mut self) -> bool {
+        let entry = match self.history.pop_back() {
+            Some(value) => value,
+            None => return false,
+        };
+
+        self.process_event(entry.inverse)
+            .expect("The inverse of an applied event should always be valid");
+
+        // Restore the base resources overwritten when reloading the sources
+        if let Some(base_resources) = entry.base_resources {
+            self.set_base_resources(&base_resources);
+        }
+
+        return true;
+    }
+
+    /// Retrieves the base resources of all tiles in the order of the chunks
+    /// given by the map data
+    fn get_base_resources(&self) -> Vec<Resources> {
+        return self
+            .data
+            .get_chunks()
+            .flat_map(|chunk| return chunk.get_tiles().iter())
+            .map(|tile| return tile.base_resources)
+            .collect();
+    }
+
+    /// Overwrites the base resources of all tiles in the order of the chunks
+    /// given by the map data
+    ///
+    /// # Parameters
+    ///
+    /// base_resources: The new base resources for all tiles
+    fn set_base_resources(&mut self, base_resources: &[Resources]) {
+        self.gradient = None;
+        self.data.get_chunks_mut().for_each(|chunk| {
+            chunk.modified = true;
+        });
+        self.data
+            .get_chunks_mut()
+            .flat_map(|chunk| return chunk.tiles.iter_mut())
+            .zip(base_resources.iter())
+            .for_each(|(tile, resources)| tile.base_resources = *resources);
+    }
+
+    /// Retrieves the number of events which can be undone
+    pub fn get_history_len(&self) -> usize {
+        return self.history.len();
+    }
+
+    /// Saves the layout, sources, terrain and all tiles of the map to a file
+    /// in the given format, any missing directories are created, the history
+    /// is not saved
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the file to save to
+    ///
+    /// format: The format to save the file in
+    ///
+    /// # Errors
+    ///
+    /// See MapFileError for a description of the different errors which may occur
+    pub fn save(&self, path: &Path, format: MapFormat) -> Result<(), MapFileError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let state = MapState {
+            layout: self
+                .data
+                .get_layout()
+                .ok_or(MapFileError::UnsupportedLayout)?,
+            chunk_size: self.get_chunk_size(),
+            sources: self.sources.clone(),
+            seed: self.seed,
+            tiles: self
+                .data
+                .get_chunks()
+                .flat_map(|chunk| return chunk.get_tiles().iter().copied())
+                .collect(),
+            terrain: self.terrain.clone(),
+            noise: self.noise.clone(),
+        };
+
+        let writer = BufWriter::new(File::create(path)?);
+        match format {
+            MapFormat::Json => serde_json::to_writer(writer, &state)?,
+            MapFormat::Binary => {
+                let mut encoder = GzEncoder::new(writer, Compression::default());
+                bincode::serialize_into(&mut encoder, &state)?;
+                encoder.finish()?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Saves the map to a file in the compressed binary format
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the file to save to
+    ///
+    /// # Errors
+    ///
+    /// See MapFileError for a description of the different errors which may occur
+    pub fn save_binary(&self, path: &Path) -> Result<(), MapFileError> {
+        return self.save(path, MapFormat::Binary);
+    }
+
+    /// Loads a map from a file written by save, the format is detected from
+    /// the file extension and otherwise from the content of the file, the
+    /// detected format is returned together with the map
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the file to load
+    ///
+    /// # Errors
+    ///
+    /// See MapFileError for a description of the different errors which may occur
+    pub fn load(path: &Path) -> Result<(Self, MapFormat), MapFileError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let format = match MapFormat::from_path(path) {
+            Some(value) => value,
+            None => MapFormat::from_data(reader.fill_buf()?),
+        };
+
+        return Ok((Self::load_reader(reader, format)?, format));
+    }
+
+    /// Loads a map from a file in the compressed binary format
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the file to load
+    ///
+    /// # Errors
+    ///
+    /// See MapFileError for a description of the different errors which may occur
+    pub fn load_binary(path: &Path) -> Result<Self, MapFileError> {
+        return Self::load_format(path, MapFormat::Binary);
+    }
+
+    /// Loads a map from a file in the given format
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the file to load
+    ///
+    /// format: The format of the file
+    fn load_format(path: &Path, format: MapFormat) -> Result<Self, MapFileError> {
+        return Self::load_reader(BufReader::new(File::open(path)?), format);
+    }
+
+    /// Loads a map from a reader in the given format, the chunk size and the
+    /// layout are validated before any chunks are created
+    ///
+    /// # Parameters
+    ///
+    /// reader: The reader to load the map from
+    ///
+    /// format: The format of the content
+    fn load_reader<R: BufRead>(reader: R, format: MapFormat) -> Result<Self, MapFileError> {
+        let state: MapState = match format {
+            MapFormat::Json => serde_json::from_reader(reader)?,
+            MapFormat::Binary => bincode::deserialize_from(GzDecoder::new(reader))?,
+        };
+
+        // Make sure the layout matches the tiles before creating the chunks
+        if state.chunk_size == 0 {
+            return Err(MapFileError::InvalidChunkSize);
+        }
+        let tile_count = state
+            .layout
+            .tile_count(state.chunk_size)
+            .ok_or(MapFileError::InvalidLayout(state.layout))?;
+        if tile_count != state.tiles.len() {
+            return Err(MapFileError::TileCount(state.tiles.len(), tile_count));
+        }
+        if let Some(terrain) = &state.terrain {
+            if terrain.heights.len() != tile_count {
+                return Err(MapFileError::TerrainSize(terrain.heights.len(), tile_count));
+            }
+        }
+        if let Some(noise) = &state.noise {
+            if noise.len() != tile_count {
+                return Err(MapFileError::NoiseSize(noise.len(), tile_count));
+            }
+        }
+
+        // Create the map and overwrite the tiles, the saved base resources
+        // already include the terrain and the noise
+        let mut map = MapBuilder::new(state.sources)
+            .with_chunk_size(state.chunk_size)
+            .with_layout(state.layout)
+            .with_seed(state.seed)
+            .build();
+        map.terrain = state.terrain;
+        map.noise = state.noise;
+        map.data
+            .get_chunks_mut()
+            .flat_map(|chunk| return chunk.tiles.iter_mut())
+            .zip(state.tiles)
+            .for_each(|(tile, state_tile)| *tile = state_tile);
+
+        return Ok(map);
+    }
+
+    /// Applies the event to the map without storing it and returns the event
+    /// which reverses it
+    ///
+    /// # Parameters
+    ///
+    /// event: The event to apply
+    fn process_event(&mut self, event: MapEvent) -> Result<MapEvent, MapEventError> {
+        let inverse = match event {
+            MapEvent::AddSource(resource_type, source) => {
+                let sources = self.sources.get_mut(resource_type);
+                sources.push(source);
+
+                MapEvent::RemoveSource(resource_type, sources.len() - 1)
+            }
+            MapEvent::InsertSource(resource_type, index, source) => {
+                let sources = self.sources.get_mut(resource_type);
+                if index > sources.len() {
+                    return Err(MapEventError::InvalidSource(index, sources.len()));
+                }
+                sources.insert(index, source);
+
+                MapEvent::RemoveSource(resource_type, index)
+            }
+            MapEvent::RemoveSource(resource_type, index) => {
+                let sources = self.sources.get_mut(resource_type);
+                if index >= sources.len() {
+                    return Err(MapEventError::InvalidSource(index, sources.len()));
+                }
+                let source = sources.remove(index);
+
+                MapEvent::InsertSource(resource_type, index, source)
+            }
+            MapEvent::SetBaseResource(index, resources) => {
+                let (chunk_type, chunk_id, tile_id) = self
+                    .get_tile_location(&index)
+                    .ok_or(MapEventError::InvalidTile(index))?;
+                let chunk = self.data.get_chunk_mut(&chunk_type, chunk_id);
+                chunk.modified = true;
+                let previous = std::mem::replace(
+                    &mut chunk.get_tiles_mut()[tile_id].base_resources,
+                    resources,
+                );
+
+                return Ok(MapEvent::SetBaseResource(index, previous));
+            }
+        };
+
+        // The sources have changed so the base resources must be reloaded
+        self.populate_resources();
+
+        return Ok(inverse);
+    }
+
+    /// Finds the position of the first tile of each chunk when all tiles are
+    /// listed in the order of the chunks given by the map data
+    fn chunk_offsets(&self) -> HashMap<(ChunkType, usize), usize> {
+        return chunk_offsets(self.data.as_ref());
+    }
+
+    /// Finds the neighbours of all tiles in the order of the chunks given by
+    /// the map data
+    fn find_neighbours(&self) -> Vec<[Option<usize>; 6]> {
+        // Find where each chunk starts
+        let offsets = self.chunk_offsets();
+
+        // Find the neighbours of each tile
+        return self
+            .iter_tiles()
+            .map(|(_, center)| {
+                let index = coordinate_to_tile(&center);
+
+                return index.hex_neighbors().map(|neighbour| {
+                    let (chunk_type, chunk_id, tile_id) = self.get_tile_location(&neighbour)?;
+
+                    return Some(offsets[&(chunk_type, chunk_id)] + tile_id);
+                });
+            })
+            .collect();
+    }
+
+    /// Retrieves an iterator over all tiles together with the center of each
+    /// tile in world coordinates
+    pub fn iter_tiles(&self) -> impl Iterator<Item = (&Tile, types::Point)> {
+        return self.data.get_chunks().flat_map(move |chunk| {
+            // Get the center of the chunk
+            let chunk_center = self.chunk_to_coordinate(
+                &self
+                    .data
+                    .get_coordinates(chunk.get_chunk_type(), chunk.get_index()),
+            );
+
+            return chunk
+                .iter_tiles_with_center(&self.geometry)
+                .map(move |(tile, center)| return (tile, chunk_center + center));
+        });
+    }
+
+    /// Retrieves an iterator over all mutable tiles together with the center
+    /// of each tile in world coordinates, this invalidates the cached
+    /// resource gradient
+    pub fn iter_tiles_mut(&mut self) -> impl Iterator<Item = (&mut Tile, types::Point)> {
+        self.gradient = None;
+
+        // Get the centers of all chunks before borrowing the chunks mutably
+        let chunk_centers = self
+            .data
+            .get_chunks()
+            .map(|chunk| {
+                return self.geometry.chunk_to_coordinate(
+                    &self
+                        .data
+                        .get_coordinates(chunk.get_chunk_type(), chunk.get_index()),
+                );
+            })
+            .collect::<Vec<types::Point>>();
+
+        let geometry = &self.geometry;
+        return self.data.get_chunks_mut().zip(chunk_centers).flat_map(
+            move |(chunk, chunk_center)| {
+                return chunk
+                    .iter_tiles_with_center_mut(geometry)
+                    .map(move |(tile, center)| return (tile, chunk_center + center));
+            },
+        );
+    }
+
+    /// Populates all tiles with the correct base resources as given by the
+    /// sources, the random number generator is seeded from the map seed such
+    /// that the result is reproducible
+    fn populate_resources(&mut self) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        self.populate_resource(
+            &mut rng,
+            |source_map| return &source_map.nutrients,
+            |resources| return &mut resources.nutrients,
+        );
+        self.populate_resource(
+            &mut rng,
+            |source_map| return &source_map.energy,
+            |resources| return &mut resources.energy,
+        );
+        self.populate_resource(
+            &mut rng,
+            |source_map| return &source_map.water,
+            |resources| return &mut resources.water,
+        );
+    }
+
+    /// Populates all tiles with the correct base nutrients as given by the sources
+    ///
+    /// # Parameters
+    ///
+    /// _rng: The random number generator for stochastic sources, all current
+    /// sources are deterministic
+    ///
+    /// sources_access: Retrieves the sources of the resource
+    ///
+    /// resources_access: Retrieves the resource from the resources of a tile
+    fn populate_resource<SourcesAccess, ResourcesAccess>(
+        &mut self,
+        _rng: &mut impl Rng,
+        sources_access: SourcesAccess,
+        resources_access: ResourcesAccess,
+    ) where
+        SourcesAccess: Fn(&SourceMap) -> &[Source],
+        ResourcesAccess: Fn(&mut Resources) -> &mut f64,
+    {
+        self.sum_sources(sources_access, &resources_access);
+
+        // Clamp all values
+        self.data.get_chunks_mut().into_iter().for_each(|chunk| {
+            chunk.tiles.iter_mut().for_each(|tile| {
+                *resources_access(&mut tile.base_resources) =
+                    resources_access(&mut tile.base_resources).clamp(0.0, 1.0);
+            });
+        });
+    }
+
+    /// Sets the base value of a resource of all tiles to the sum of the
+    /// contributions of its sources including all periodic images, the values
+    /// are not clamped
+    ///
+    /// # Parameters
+    ///
+    /// sources_access: Retrieves the sources of the resource
+    ///
+    /// resources_access: Retrieves the resource from the resources of a tile
+    fn sum_sources<SourcesAccess, ResourcesAccess>(
+        &mut self,
+        sources_access: SourcesAccess,
+        resources_access: ResourcesAccess,
+    ) where
+        SourcesAccess: Fn(&SourceMap) -> &[Source],
+        ResourcesAccess: Fn(&mut Resources) -> &mut f64,
+    {
+        // Reset the nutrients and set modified to true
+        self.data.get_chunks_mut().into_iter().for_each(|chunk| {
+            chunk.modified = true;
+            chunk.tiles.iter_mut().for_each(|tile| {
+                *resources_access(&mut tile.base_resources) = 0.0;
+            });
+        });
+
+        // Populate each source
+        let offsets = self.chunk_offsets();
+        sources_access(&self.sources)
+            .to_vec()
+            .iter()
+            .for_each(|source| {
+                self.add_source_contribution(source, &offsets, &resources_access);
+            });
+    }
+
+    /// Adds the contribution of a single source to the base resources of all
+    /// tiles within its range and marks their chunks as modified, the values
+    /// are not clamped
+    ///
+    /// # Parameters
+    ///
+    /// source: The source to add
+    ///
+    /// offsets: The position of the first tile of each chunk as given by
+    /// chunk_offsets
+    ///
+    /// resources_access: Retrieves the resource from the resources of a tile
+    fn add_source_contribution<ResourcesAccess>(
+        &mut self,
+        source: &Source,
+        offsets: &HashMap<(ChunkType, usize), usize>,
+        resources_access: &ResourcesAccess,
+    ) where
+        ResourcesAccess: Fn(&mut Resources) -> &mut f64,
+    {
+        evaluate_source_on_chunks(self.data.as_ref(), &self.geometry, source)
+            .into_iter()
+            .for_each(|(chunk_type, chunk_id, pop)| {
+                // Find the terrain and noise factors before borrowing the chunk
+                let offset = offsets[&(chunk_type, chunk_id)];
+                let factors = (0..pop.len())
+                    .map(|tile_id| {
+                        let mut factor = self.source_factor(offset + tile_id);
+                        return *resources_access(&mut factor);
+                    })
+                    .collect::<Vec<f64>>();
+
+                // Add the population reduced by the height of the terrain and
+                // scaled by the noise layers
+                let chunk = self.data.get_chunk_mut(&chunk_type, chunk_id);
+                chunk.modified = true;
+                pop.iter()
+                    .zip(factors.iter())
+                    .zip(chunk.get_tiles_mut().iter_mut())
+                    .for_each(|((value, factor), tile)| {
+                        *resources_access(&mut tile.base_resources) += value * factor;
+                    });
+            });
+    }
+
+    /// Calculates the factor the contribution of the sources is scaled by at
+    /// a tile for each resource type, this is the reduction from the height
+    /// of the terrain times the factor of the noise layers
+    ///
+    /// # Parameters
+    ///
+    /// tile: The position of the tile in the order of the chunks given by
+    /// the map data
+    fn source_factor(&self, tile: usize) -> Resources {
+        let terrain = match &self.terrain {
+            Some(terrain) => (1.0 - terrain.get_height_at(tile) * TERRAIN_SLOPE_FACTOR).max(0.0),
+            None => 1.0,
+        };
+        let noise = match &self.noise {
+            Some(noise) => noise[tile],
+            None => Resources::new(1.0, 1.0, 1.0),
+        };
+
+        return noise * terrain;
+    }
+}
+
+/// A network of straight roads between points in world coordinates
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoadGraph {
+    /// The end points of the roads
+    pub nodes: Vec<types::Point>,
+    /// The roads given as the indices of the two nodes they connect
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl RoadGraph {
+    /// The standard deviation of the nutrient falloff away from a road, it is
+    /// also the distance from a road at which tiles are raised
+    pub const ROAD_WIDTH: f64 = 0.5;
+    /// The amount of nutrients on a road
+    pub const ROAD_VALUE: f64 = 1.0;
+    /// The height of the tiles on a road
+    pub const ROAD_HEIGHT: f64 = 1.0;
+}
+
+/// The static height of every tile of a map
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TerrainMap {
+    /// The height of each tile in the order of the chunks given by the map
+    /// data, heights are typically between 0 and 1
+    pub heights: Vec<f64>,
+}
+
+impl TerrainMap {
+    /// Loads a greyscale image and stretches it over the tile centers of the
+    /// map, the height of each tile is the bilinearly sampled brightness
+    /// between 0 and 1
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the image to load
+    ///
+    /// map: The map to create the terrain for
+    ///
+    /// # Errors
+    ///
+    /// See TerrainError for a description of the different errors which may occur
+    pub fn from_heightmap_image(path: &Path, map: &Map) -> Result<Self, TerrainError> {
+        let image = image::open(path)?.into_luma8();
+        if image.width() == 0 || image.height() == 0 {
+            return Err(TerrainError::EmptyImage);
+        }
+
+        // Find the extent of the tile centers
+        let centers = map
+            .iter_tiles()
+            .map(|(_, center)| return center)
+            .collect::<Vec<_>>();
+        let (min, max) = centers.iter().fold(
+            (
+                types::Point::new(f64::INFINITY, f64::INFINITY),
+                types::Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |(min, max), center| {
+                return (
+                    types::Point::new(min.x.min(center.x), min.y.min(center.y)),
+                    types::Point::new(max.x.max(center.x), max.y.max(center.y)),
+                );
+            },
+        );
+
+        // Sample the image with the top of the image at the largest y
+        let pixel = |x: u32, y: u32| {
+            return image
+                .get_pixel(x.min(image.width() - 1), y.min(image.height() - 1))
+                .0[0] as f64
+                / 255.0;
+        };
+        let heights = centers
+            .iter()
+            .map(|center| {
+                let u = if max.x > min.x {
+                    (center.x - min.x) / (max.x - min.x) * (image.width() - 1) as f64
+                } else {
+                    0.0
+                };
+                let v = if max.y > min.y {
+                    (max.y - center.y) / (max.y - min.y) * (image.height() - 1) as f64
+                } else {
+                    0.0
+                };
+                let (x, y) = (u.floor() as u32, v.floor() as u32);
+                let (tx, ty) = (u - u.floor(), v - v.floor());
+                let top = pixel(x, y) * (1.0 - tx) + pixel(x + 1, y) * tx;
+                let bottom = pixel(x, y + 1) * (1.0 - tx) + pixel(x + 1, y + 1) * tx;
+
+                return top * (1.0 - ty) + bottom * ty;
+            })
+            .collect();
+
+        return Ok(Self { heights });
+    }
+
+    /// Retrieves the height of a tile, tiles outside the terrain have a
+    /// height of 0
+    ///
+    /// # Parameters
+    ///
+    /// tile_index: The position of the tile in the order of the chunks given
+    /// by the map data
+    pub fn get_height_at(&self, tile_index: usize) -> f64 {
+        return self.heights.get(tile_index).copied().unwrap_or(0.0);
+    }
+}
+
+/// A mutator for the source map
+#[derive(Debug)]
+pub struct SourceMapMut<'map> {
+    map: &'map mut Map,
+}
+
+impl<'map> SourceMapMut<'map> {
+    /// Retrieves a reference to the sources
+    pub fn get(&self) -> &SourceMap {
+        return &self.map.sources;
+    }
+
+    /// Retrieves a mutable reference to the sources
+    pub fn get_mut(&mut self) -> &mut SourceMap {
+        return &mut self.map.sources;
+    }
+}
+
+impl<'map> Drop for SourceMapMut<'map> {
+    fn drop(&mut self) {
+        // Force update the base resources for the map
+        self.map.populate_resources();
+    }
+}
+
+/// Holds all resource sources for an entire map
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SourceMap {
+    /// The sources for nutrients
+    pub nutrients: Vec<Source>,
+    /// The sources for energy
+    pub energy: Vec<Source>,
+    /// The sources for water
+    pub water: Vec<Source>,
+}
+
+impl Default for SourceMap {
+    /// Constructs a source map without any sources
+    fn default() -> Self {
+        return Self {
+            nutrients: Vec::new(),
+            energy: Vec::new(),
+            water: Vec::new(),
+        };
+    }
+}
+
+impl SourceMap {
+    /// Retrieves the sources for the given resource type
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to get the sources for
+    pub fn get(&self, resource_type: ResourceType) -> &Vec<Source> {
+        return match resource_type {
+            ResourceType::Nutrients => &self.nutrients,
+            ResourceType::Energy => &self.energy,
+            ResourceType::Water => &self.water,
+        };
+    }
+
+    /// Retrieves the mutable sources for the given resource type
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to get the sources for
+    pub fn get_mut(&mut self, resource_type: ResourceType) -> &mut Vec<Source> {
+        return match resource_type {
+            ResourceType::Nutrients => &mut self.nutrients,
+            ResourceType::Energy => &mut self.energy,
+            ResourceType::Water => &mut self.water,
+        };
+    }
+
+    /// Rescales the sources of each resource type such that the largest
+    /// combined value over all tiles of the map is 1, this keeps the relative
+    /// strengths of the sources, the combined value includes all periodic
+    /// images of the sources, resource types without any positive values are
+    /// left unchanged
+    ///
+    /// # Parameters
+    ///
+    /// data: The chunk data of the map to normalize for
+    pub fn normalize(&mut self, data: &dyn MapData) {
+        self.normalize_with_factors(data, None);
+    }
+
+    /// Rescales the sources of each resource type such that the largest
+    /// combined value over all tiles of the map is 1 when the contribution at
+    /// every tile is scaled by the given factors
+    ///
+    /// # Parameters
+    ///
+    /// data: The chunk data of the map to normalize for
+    ///
+    /// factors: The factor for each resource type of every tile in the order
+    /// of the chunks given by the map data, None if the contributions are not
+    /// scaled
+    fn normalize_with_factors(&mut self, data: &dyn MapData, factors: Option<&[Resources]>) {
+        let geometry = ChunkGeometry::new(data.get_chunk_size());
+        let offsets = chunk_offsets(data);
+        let tile_count = data
+            .get_chunks()
+            .map(|chunk| return chunk.get_tiles().len())
+            .sum::<usize>();
+
+        [
+            ResourceType::Nutrients,
+            ResourceType::Energy,
+            ResourceType::Water,
+        ]
+        .into_iter()
+        .for_each(|resource_type| {
+            // Find the combined value of all sources at every tile
+            let mut totals = vec![0.0; tile_count];
+            self.get(resource_type).iter().for_each(|source| {
+                evaluate_source_on_chunks(data, &geometry, source)
+                    .into_iter()
+                    .for_each(|(chunk_type, chunk_id, pop)| {
+                        let offset = offsets[&(chunk_type, chunk_id)];
+                        pop.iter().enumerate().for_each(|(tile_id, value)| {
+                            let factor = match factors {
+                                Some(factors) => factors[offset + tile_id].get(resource_type),
+                                None => 1.0,
+                            };
+                            totals[offset + tile_id] += value * factor;
+                        });
+                    });
+            });
+
+            let max = totals.into_iter().fold(0.0, f64::max);
+            if max <= 0.0 {
+                return;
+            }
+
+            self.get_mut(resource_type)
+                .iter_mut()
+                .for_each(|source| source.scale(1.0 / max));
+        });
+    }
+}
+
+/// The spatial gradient of each resource at every tile, the tiles are in the
+/// order of the chunks given by the map data
+#[derive(Clone, Debug)]
+pub struct ResourceGradient {
+    /// The direction of steepest ascent of the nutrients
+    pub nutrients: Vec<types::Point>,
+    /// The direction of steepest ascent of the energy
+    pub energy: Vec<types::Point>,
+    /// The direction of steepest ascent of the water
+    pub water: Vec<types::Point>,
+}
+
+impl ResourceGradient {
+    /// Retrieves the gradient of a single resource type for all tiles
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to get the gradient for
+    pub fn get(&self, resource_type: ResourceType) -> &[types::Point] {
+        return match resource_type {
+            ResourceType::Nutrients => &self.nutrients,
+            ResourceType::Energy => &self.energy,
+            ResourceType::Water => &self.water,
+        };
+    }
+}
+
+/// A single modification of a map which can be undone
+#[derive(Clone, Debug)]
+pub enum MapEvent {
+    /// Adds a source to the end of the sources for the given resource type
+    AddSource(ResourceType, Source),
+    /// Inserts a source at the given index of the sources for the given resource type
+    InsertSource(ResourceType, usize, Source),
+    /// Removes the source at the given index of the sources for the given resource type
+    RemoveSource(ResourceType, usize),
+    /// Sets the base resources of the tile with the given tile index
+    SetBaseResource(types::Index, Resources),
+}
+
+/// An entry of the undo history of a map
+#[derive(Clone, Debug)]
+struct MapHistoryEntry {
+    /// The event which undoes the change
+    inverse: MapEvent,
+    /// The base resources of all tiles before the change, they are restored
+    /// after the inverse has been applied since reloading the sources erases
+    /// any MapEvent::SetBaseResource, None if the change did not reload the
+    /// sources
+    base_resources: Option<Vec<Resources>>,
+}
+
+/// The error types for when applying an event to a map
+#[derive(Error, Debug, Clone)]
+pub enum MapEventError {
+    /// The source index was out of range
+    #[error("The source index {:?} is out of range for {:?} sources", .0, .1)]
+    InvalidSource(usize, usize),
+    /// The tile is not part of the map
+    #[error("The tile {:?} is not part of the map", .0)]
+    InvalidTile(types::Index),
+}
+
+/// The file formats a map can be saved in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapFormat {
+    /// Human readable json
+    Json,
+    /// Gzip compressed binary data
+    Binary,
+}
+
+impl MapFormat {
+    /// Detects the format from the file extension, None if the extension is unknown
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the file
+    pub fn from_path(path: &Path) -> Option<Self> {
+        return match path.extension()?.to_str()? {
+            "json" => Some(Self::Json),
+            "bin" | "gz" => Some(Self::Binary),
+            _ => None,
+        };
+    }
+
+    /// Detects the format from the content of a file by looking for the gzip magic bytes
+    ///
+    /// # Parameters
+    ///
+    /// data: The content of the file
+    pub fn from_data(data: &[u8]) -> Self {
+        return if data.starts_with(&GZIP_MAGIC) {
+            Self::Binary
+        } else {
+            Self::Json
+        };
+    }
+}
+
+/// The error types for when loading a terrain
+#[derive(Error, Debug)]
+pub enum TerrainError {
+    /// The image could not be loaded
+    #[error("Unable to load the heightmap: {:?}", .0)]
+    Image(#[from] image::ImageError),
+    /// The image has no pixels
+    #[error("The heightmap is empty")]
+    EmptyImage,
+}
+
+/// The error types for when saving or loading a map
+#[derive(Error, Debug)]
+pub enum MapFileError {
+    /// The file could not be read or written
+    #[error("Unable to access the map file: {:?}", .0)]
+    Io(#[from] io::Error),
+    /// The json content could not be converted
+    #[error("Unable to convert the map state to or from json: {:?}", .0)]
+    Json(#[from] serde_json::Error),
+    /// The binary content could not be converted
+    #[error("Unable to convert the map state to or from binary: {:?}", .0)]
+    Binary(#[from] bincode::Error),
+    /// The map data does not have one of the standard layouts
+    #[error("The map does not have a layout which can be saved")]
+    UnsupportedLayout,
+    /// The number of tiles in the file does not match the layout
+    #[error("The file has {:?} tiles but the layout requires {:?}", .0, .1)]
+    TileCount(usize, usize),
+    /// The chunk size in the file is 0
+    #[error("The chunk size must be at least 1")]
+    InvalidChunkSize,
+    /// The layout in the file has no chunks or too many tiles to be stored
+    #[error("The layout {:?} is not valid", .0)]
+    InvalidLayout(MapLayout),
+    /// The number of terrain heights does not match the number of tiles
+    #[error("The terrain has {:?} heights but the layout requires {:?}", .0, .1)]
+    TerrainSize(usize, usize),
+    /// The number of noise factors does not match the number of tiles
+    #[error("The noise has {:?} factors but the layout requires {:?}", .0, .1)]
+    NoiseSize(usize, usize),
+}
+
+/// Everything about a map which is saved to a file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct MapState {
+    /// The layout of the chunks
+    layout: MapLayout,
+    /// The number of tiles from the center of a chunk to its edge
+    chunk_size: usize,
+    /// All sources of resources
+    sources: SourceMap,
+    /// The seed for all random generation
+    #[serde(default)]
+    seed: u64,
+    /// All tiles in the order of the chunks given by the map data
+    tiles: Vec<Tile>,
+    /// The height of all tiles, None if the map is flat
+    #[serde(default)]
+    terrain: Option<TerrainMap>,
+    /// The factor from all applied noise layers for each resource of every
+    /// tile, None if no noise has been applied
+    #[serde(default)]
+    noise: Option<Vec<Resources>>,
+}
+
+/// The trait for any map of chunks, different layouts can be encoded in
+/// different types, all logic must go through this interface
+///
+/// # Examples
+///
+/// A map consisting of a single chunk without any neighbours
+///
+/// ```
+/// use growth_simulator::{
+///     map, types, Chunk, ChunkEdgeType, ChunkType, ChunkVertexType, MapData, MapLayout,
+/// };
+/// use std::iter;
+///
+/// #[derive(Debug)]
+/// struct MapSingleChunk {
+///     bulk: Chunk,
+///     edges: [Chunk; 3],
+///     vertices: [Chunk; 2],
+/// }
+///
+/// impl MapSingleChunk {
+///     fn new(chunk_size: usize) -> Self {
+///         return Self {
+///             bulk: Chunk::new_empty(ChunkType::Bulk, 0, chunk_size),
+///             edges: [
+///                 Chunk::new_empty(ChunkType::Edge(ChunkEdgeType::Top), 0, chunk_size),
+///                 Chunk::new_empty(ChunkType::Edge(ChunkEdgeType::Middle), 0, chunk_size),
+///                 Chunk::new_empty(ChunkType::Edge(ChunkEdgeType::Bottom), 0, chunk_size),
+///             ],
+///             vertices: [
+///                 Chunk::new_empty(ChunkType::Vertex(ChunkVertexType::Top), 0, chunk_size),
+///                 Chunk::new_empty(ChunkType::Vertex(ChunkVertexType::Bottom), 0, chunk_size),
+///             ],
+///         };
+///     }
+/// }
+///
+/// impl MapData for MapSingleChunk {
+///     fn get_chunk_size(&self) -> usize {
+///         return self.bulk.get_chunk_size();
+///     }
+///
+///     fn get_layout(&self) -> Option<MapLayout> {
+///         return None;
+///     }
+///
+///     fn get_index(&self, _chunk_type: &ChunkType, coordinates: types::Index) -> Option<usize> {
+///         return if coordinates.x == 0 && coordinates.y == 0 { Some(0) } else { None };
+///     }
+///
+///     fn get_coordinates(&self, _chunk_type: &ChunkType, _index: usize) -> types::Index {
+///         return types::Index::new(0, 0);
+///     }
+///
+///     fn get_chunk(&self, chunk_type: &ChunkType, _index: usize) -> &Chunk {
+///         return match chunk_type {
+///             ChunkType::Bulk => &self.bulk,
+///             ChunkType::Edge(edge_type) => &self.edges[edge_type.id()],
+///             ChunkType::Vertex(vertex_type) => &self.vertices[vertex_type.id()],
+///         };
+///     }
+///
+///     fn get_chunk_mut(&mut self, chunk_type: &ChunkType, _index: usize) -> &mut Chunk {
+///         return match chunk_type {
+///             ChunkType::Bulk => &mut self.bulk,
+///             ChunkType::Edge(edge_type) => &mut self.edges[edge_type.id()],
+///             ChunkType::Vertex(vertex_type) => &mut self.vertices[vertex_type.id()],
+///         };
+///     }
+///
+///     fn get_chunks(&self) -> Box<dyn Iterator<Item = &Chunk> + '_> {
+///         return Box::new(
+///             iter::once(&self.bulk)
+///                 .chain(self.edges.iter())
+///                 .chain(self.vertices.iter()),
+///         );
+///     }
+///
+///     fn get_chunks_mut(&mut self) -> Box<dyn Iterator<Item = &mut Chunk> + '_> {
+///         return Box::new(
+///             iter::once(&mut self.bulk)
+///                 .chain(self.edges.iter_mut())
+///                 .chain(self.vertices.iter_mut()),
+///         );
+///     }
+///
+///     fn chunk_count(&self) -> usize {
+///         return ChunkType::all().len();
+///     }
+///
+///     fn all_chunk_types_and_indices(&self) -> Vec<(ChunkType, usize)> {
+///         return ChunkType::all()
+///             .into_iter()
+///             .map(|chunk_type| return (chunk_type, 0))
+///             .collect();
+///     }
+/// }
+///
+/// let map = map::Map::new(Box::new(MapSingleChunk::new(4)), map::SourceMap::default(), 0);
+/// assert_eq!(map.get_data().chunk_count(), 6);
+/// assert!(map.get_tile(&types::Index::new(0, 0)).is_some());
+/// ```
+pub trait MapData: Debug {
+    /// Retrieves the number of tiles from the center of a chunk to its edge
+    fn get_chunk_size(&self) -> usize;
+
+    /// Retrieves the layout of the chunks, None if it is not one of the
+    /// standard layouts in which case the map cannot be saved
+    fn get_layout(&self) -> Option<MapLayout>;
+
+    /// Retrieves the index of the chunk at the given index coordinate, None if it is out of bounds
+    ///
+    /// # Parameters
+    ///
+    /// coordinates: The index coordinates to get the chunk for
+    fn get_index(&self, chunk_type: &ChunkType, coordinates: types::Index) -> Option<usize>;
+
+    /// Retrieves the index coordinates of the chunk stored at the given index
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of the chunk
+    ///
+    /// index: The index of the chunk in the list of chunks
+    fn get_coordinates(&self, chunk_type: &ChunkType, index: usize) -> types::Index;
+
+    /// Retrieves the chunk at the given index
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of chunk to retrieve
+    ///
+    /// index: The index of the chunk to retrieve
+    fn get_chunk(&self, chunk_type: &ChunkType, index: usize) -> &Chunk;
+
+    /// Retrieves the chunk at the given index as mutable
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of chunk to retrieve
+    ///
+    /// index: The index of the chunk to retrieve
+    fn get_chunk_mut(&mut self, chunk_type: &ChunkType, index: usize) -> &mut Chunk;
+
+    /// Retrieves an iterator over all chunks
+    fn get_chunks(&self) -> Box<dyn Iterator<Item = &Chunk> + '_>;
+
+    /// Retrieves an iterator over all mutable chunks
+    fn get_chunks_mut(&mut self) -> Box<dyn Iterator<Item = &mut Chunk> + '_>;
+
+    /// Retrieves the total number of stored chunks
+    fn chunk_count(&self) -> usize;
+
+    /// Retrieves the chunk type and storage index of all stored chunks in the
+    /// same order as the chunks given by get_chunks
+    fn all_chunk_types_and_indices(&self) -> Vec<(ChunkType, usize)>;
+
+    /// Retrieves an iterator over the chunk type and spatial index coordinates
+    /// of all stored chunks in the same order as the chunks given by get_chunks
+    fn iter_chunk_indices(&self) -> Box<dyn Iterator<Item = (ChunkType, types::Index)> + '_> {
+        return Box::new(self.all_chunk_types_and_indices().into_iter().map(
+            |(chunk_type, index)| {
+                return (chunk_type, self.get_coordinates(&chunk_type, index));
+            },
+        ));
+    }
+
+    /// Finds the chunk type, the storage index of the chunk and the index of
+    /// the tile within the chunk for the tile with the given tile index, None
+    /// if it is not part of the map
+    ///
+    /// # Parameters
+    ///
+    /// geometry: The geometry of the chunks of the map
+    ///
+    /// tile_index: The tile index of the tile to find
+    fn get_tile_location(
+        &self,
+        geometry: &ChunkGeometry,
+        tile_index: &types::Index,
+    ) -> Option<(ChunkType, usize, usize)> {
+        let point = tile_to_coordinate(tile_index);
+        let center_chunk = geometry.coordinate_to_chunk(&point);
+
+        // Tiles on the border of a chunk may belong to a neighbouring chunk
+        return (-1..=1)
+            .flat_map(|y| return (-1..=1).map(move |x| return types::Index::new(x, y)))
+            .find_map(|offset| {
+                let chunk_index = center_chunk + offset;
+                let chunk_center = geometry.chunk_to_coordinate(&chunk_index);
+
+                return ChunkType::all().into_iter().find_map(|chunk_type| {
+                    let tile_id =
+                        geometry
+                            .get_tile_centers(&chunk_type)
+                            .iter()
+                            .position(|center| {
+                                return (chunk_center + center - point).norm_squared() < 0.01;
+                            })?;
+                    let chunk_id = self.get_index(&chunk_type, chunk_index)?;
+
+                    return Some((chunk_type, chunk_id, tile_id));
+                });
+            });
+    }
+
+    /// Retrieves the tile with the given tile index or None if it is not part
+    /// of the map
+    ///
+    /// # Parameters
+    ///
+    /// geometry: The geometry of the chunks of the map
+    ///
+    /// tile_index: The tile index of the tile to retrieve
+    fn get_tile_at_index(
+        &self,
+        geometry: &ChunkGeometry,
+        tile_index: &types::Index,
+    ) -> Option<&Tile> {
+        let (chunk_type, chunk_id, tile_id) = self.get_tile_location(geometry, tile_index)?;
+
+        return Some(&self.get_chunk(&chunk_type, chunk_id).get_tiles()[tile_id]);
+    }
+
+    /// Retrieves the mutable tile with the given tile index or None if it is
+    /// not part of the map, the chunk holding it is marked as modified
+    ///
+    /// # Parameters
+    ///
+    /// geometry: The geometry of the chunks of the map
+    ///
+    /// tile_index: The tile index of the tile to retrieve
+    fn get_tile_at_index_mut(
+        &mut self,
+        geometry: &ChunkGeometry,
+        tile_index: &types::Index,
+    ) -> Option<&mut Tile> {
+        let (chunk_type, chunk_id, tile_id) = self.get_tile_location(geometry, tile_index)?;
+        let chunk = self.get_chunk_mut(&chunk_type, chunk_id);
+        chunk.modified = true;
+
+        return Some(&mut chunk.get_tiles_mut()[tile_id]);
+    }
+}
+
+/// A chunk of tiles clustered together can be used as the bulk, an edge or a vertex
+#[derive(Clone, Debug)]
+pub struct Chunk {
+    /// All tiles for this chunk
+    tiles: Vec<Tile>,
+    /// The type of chunk stored
+    chunk_type: ChunkType,
+    /// The index of this chunk in the list of chunks
+    index: usize,
+    /// True if one of the tiles has been modified, false once the graphics has updated
+    modified: bool,
+    /// The number of tiles from the center of the chunk to its edge
+    chunk_size: usize,
+    /// The bounding box of all tiles relative to the center of the chunk,
+    /// it is calculated the first time it is needed
+    bounding_box: OnceCell<types::View>,
+}
+
+impl Chunk {
+    /// Creates a new chunk from the given tiles
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of chunk which is being constructed
+    ///
+    /// index: The index of this chunk in the list of chunks
+    ///
+    /// chunk_size: The number of tiles from the center of the chunk to its edge
+    ///
+    /// tiles: The tiles to set for the new chunk
+    ///
+    /// # Errors
+    ///
+    /// See NewChunkError for a description of the different errors which may occur
+    pub fn new(
+        chunk_type: ChunkType,
+        index: usize,
+        chunk_size: usize,
+        tiles: Vec<Tile>,
+    ) -> Result<Self, NewChunkError> {
+        if chunk_size == 0 {
+            return Err(NewChunkError::InvalidChunkSize);
+        }
+
+        let tile_count = ChunkGeometry::tile_count(&chunk_type, chunk_size);
+        if tiles.len() != tile_count {
+            return Err(NewChunkError::InvalidSize(tiles.len(), tile_count));
+        }
+
+        return Ok(Self {
+            tiles,
+            chunk_type,
+            index,
+            modified: true,
+            chunk_size,
+            bounding_box: OnceCell::new(),
+        });
+    }
+
+    /// Constructs a new chunk with all base resources set to 0
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of chunk which is being constructed
+    ///
+    /// index: The index of this chunk in the list of chunks
+    ///
+    /// chunk_size: The number of tiles from the center of the chunk to its edge
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chunk size is 0
+    pub fn new_empty(chunk_type: ChunkType, index: usize, chunk_size: usize) -> Self {
+        if chunk_size == 0 {
+            panic!("The chunk size must be at least 1");
+        }
+
+        // Get the number of tiles
+        let tile_count = ChunkGeometry::tile_count(&chunk_type, chunk_size);
+
+        // Create the tiles
+        let tiles = (0..tile_count)
+            .map(|_| {
+                return Tile::new(Resources {
+                    nutrients: 0.0,
+                    energy: 0.0,
+                    water: 0.0,
+                });
+            })
+            .collect::<Vec<Tile>>();
+
+        return Self::new(chunk_type, index, chunk_size, tiles).expect("Should not happen");
+    }
+
+    /// Checks if the chunk has been modified
+    pub fn is_modified(&self) -> bool {
+        return self.modified;
+    }
+
+    /// Sets the modified tag to false
+    pub fn resolved(&mut self) {
+        self.modified = false;
+    }
+
+    /// Retrieves the index of this chunk in the list of chunks
+    pub fn get_index(&self) -> usize {
+        return self.index;
+    }
+
+    /// Retrieves the type of this chunk
+    pub fn get_chunk_type(&self) -> &ChunkType {
+        return &self.chunk_type;
+    }
+
+    /// Retrieves the number of tiles from the center of the chunk to its edge
+    pub fn get_chunk_size(&self) -> usize {
+        return self.chunk_size;
+    }
+
+    /// Retrieves centers relative to the center of the chunk for all tiles in
+    /// this chunk, they are sorted in the same way as the tiles
+    ///
+    /// # Parameters
+    ///
+    /// geometry: The geometry of the map holding the chunk
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if the geometry is for a different chunk size
+    pub fn get_tile_centers<'a>(&self, geometry: &'a ChunkGeometry) -> &'a [types::Point] {
+        if cfg!(debug_assertions) && geometry.get_chunk_size() != self.chunk_size {
+            panic!(
+                "The geometry has chunk size {:?} but the chunk has chunk size {:?}",
+                geometry.get_chunk_size(),
+                self.chunk_size
+            );
+        }
+
+        return geometry.get_tile_centers(&self.chunk_type);
+    }
+
+    /// Calculates the axis aligned bounding box of all tiles of the chunk in
+    /// world coordinates, it covers the full hexagon of every tile
+    ///
+    /// # Parameters
+    ///
+    /// geometry: The geometry of the map holding the chunk
+    ///
+    /// chunk_world_center: The center of the chunk in world coordinates
+    pub fn bounding_box_world(
+        &self,
+        geometry: &ChunkGeometry,
+        chunk_world_center: &types::Point,
+    ) -> types::View {
+        let bounding_box = self.bounding_box.get_or_init(|| {
+            let centers = self.get_tile_centers(geometry);
+            let min = centers.iter().fold(
+                types::Point::new(f64::INFINITY, f64::INFINITY),
+                |prev, next| return types::Point::new(prev.x.min(next.x), prev.y.min(next.y)),
+            );
+            let max = centers.iter().fold(
+                types::Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+                |prev, next| return types::Point::new(prev.x.max(next.x), prev.y.max(next.y)),
+            );
+
+            // Add half a tile to include the corners of the outer tiles
+            return types::View::new(
+                &((min + max) * 0.5),
+                &types::Size::new(max.x - min.x + 2.0 * INV_SQRT_3, max.y - min.y + 1.0),
+            );
+        });
+
+        return types::View::new(
+            &(*bounding_box.get_center() + *chunk_world_center),
+            bounding_box.get_size(),
+        );
+    }
+
+    /// Retrieves an iterator over all tiles together with the center of each
+    /// tile relative to the center of the chunk
+    ///
+    /// # Parameters
+    ///
+    /// geometry: The geometry of the map holding the chunk
+    pub fn iter_tiles_with_center<'a>(
+        &'a self,
+        geometry: &'a ChunkGeometry,
+    ) -> impl Iterator<Item = (&'a Tile, types::Point)> {
+        return self
+            .tiles
+            .iter()
+            .zip(self.get_tile_centers(geometry).iter().copied());
+    }
+
+    /// Retrieves an iterator over all mutable tiles together with the center
+    /// of each tile relative to the center of the chunk
+    ///
+    /// # Parameters
+    ///
+    /// geometry: The geometry of the map holding the chunk
+    pub fn iter_tiles_with_center_mut<'a>(
+        &'a mut self,
+        geometry: &'a ChunkGeometry,
+    ) -> impl Iterator<Item = (&'a mut Tile, types::Point)> {
+        let tile_centers = self.get_tile_centers(geometry);
+
+        return self.tiles.iter_mut().zip(tile_centers.iter().copied());
+    }
+
+    /// Retrieves a reference to the tiles
+    pub fn get_tiles(&self) -> &[Tile] {
+        return &self.tiles;
+    }
+
+    /// Retrieves a mutable reference to the tiles
+    pub fn get_tiles_mut(&mut self) -> &mut Vec<Tile> {
+        return &mut self.tiles;
+    }
+}
+
+/// The type of chunk
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChunkType {
+    /// The largest type of chunk holding the bulk of the tiles
+    Bulk,
+    /// The edge chunk which surrounds the bulk
+    Edge(ChunkEdgeType),
+    /// The vertex chunk consisting only of a single tile
+    Vertex(ChunkVertexType),
+}
+
+impl ChunkType {
+    /// Retrieves all the different chunk types
+    pub const fn all() -> [ChunkType; 6] {
+        return [
+            ChunkType::Bulk,
+            ChunkType::Edge(ChunkEdgeType::Top),
+            ChunkType::Edge(ChunkEdgeType::Middle),
+            ChunkType::Edge(ChunkEdgeType::Bottom),
+            ChunkType::Vertex(ChunkVertexType::Top),
+            ChunkType::Vertex(ChunkVertexType::Bottom),
+        ];
+    }
+
+    /// Retrieves the number of tiles in the chunk for the default chunk size
+    pub fn get_tile_count(&self) -> usize {
+        return DEFAULT_CHUNK_GEOMETRY.get_tile_count(self);
+    }
+
+    /// Retrieves centers relative to the center of the chunk for all tiles in
+    /// this chunk type for the default chunk size, they are sorted in the
+    /// same way that they are stored in the chunk
+    pub fn get_tile_centers(&self) -> &'static [types::Point] {
+        return DEFAULT_CHUNK_GEOMETRY.get_tile_centers(self);
+    }
+
+    /// Retrieves the circle in world coordinates containing all tiles of this
+    /// chunk type for the default chunk size, it is centered at the center of
+    /// the chunk, returns (center, radius)
+    ///
+    /// # Parameters
+    ///
+    /// chunk_world_center: The center of the chunk in world coordinates
+    pub fn world_bounding_circle(&self, chunk_world_center: &types::Point) -> (types::Point, f64) {
+        return DEFAULT_CHUNK_GEOMETRY.world_bounding_circle(self, chunk_world_center);
+    }
+}
+
+/// The location of the edge chunk compared to the bulk
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChunkEdgeType {
+    /// This is the top left edge
+    Top,
+    /// This is the left edge
+    Middle,
+    /// This is the bottom left edge
+    Bottom,
+}
+
+impl ChunkEdgeType {
+    /// Retrieves the id of this type of edge piece
+    pub fn id(&self) -> usize {
+        return match self {
+            ChunkEdgeType::Top => 0,
+            ChunkEdgeType::Middle => 1,
+            ChunkEdgeType::Bottom => 2,
+        };
+    }
+
+    /// Retrieves centers relative to the center of the chunk for all tiles in
+    /// this chunk type for the default chunk size, they are sorted in the
+    /// same way that they are stored in the chunk
+    pub fn get_tile_centers(&self) -> &'static [types::Point] {
+        return DEFAULT_CHUNK_GEOMETRY.get_tile_centers(&ChunkType::Edge(*self));
+    }
+}
+
+/// The location of the vertec chunk compared to the bulk
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChunkVertexType {
+    /// The top left vertex
+    Top,
+    /// The bottom left vertex
+    Bottom,
+}
+
+impl ChunkVertexType {
+    /// Retrieves the id of this type of vertex
+    pub fn id(&self) -> usize {
+        return match self {
+            ChunkVertexType::Top => 0,
+            ChunkVertexType::Bottom => 1,
+        };
+    }
+
+    /// Retrieves centers relative to the center of the chunk for all tiles in
+    /// this chunk type for the default chunk size, they are sorted in the
+    /// same way that they are stored in the chunk
+    pub fn get_tile_centers(&self) -> &'static [types::Point] {
+        return DEFAULT_CHUNK_GEOMETRY.get_tile_centers(&ChunkType::Vertex(*self));
+    }
+}
+
+/// The positions of all tiles within each type of chunk for a single chunk size
+#[derive(Clone, Debug)]
+pub struct ChunkGeometry {
+    /// The number of tiles from the center of a chunk to its edge
+    chunk_size: usize,
+    /// The tile centers of the bulk
+    centers_bulk: Vec<types::Point>,
+    /// The tile centers of all the edges
+    centers_edge: [Vec<types::Point>; 3],
+    /// The tile centers of all the vertices
+    centers_vertex: [Vec<types::Point>; 2],
+    /// The radius of the bounding circle of the bulk
+    radius_bulk: f64,
+    /// The radius of the bounding circle of all the edges
+    radius_edge: [f64; 3],
+    /// The radius of the bounding circle of all the vertices
+    radius_vertex: [f64; 2],
+}
+
+impl ChunkGeometry {
+    /// Calculates the geometry for the given chunk size
+    ///
+    /// # Parameters
+    ///
+    /// chunk_size: The number of tiles from the center of a chunk to its edge
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chunk size is 0
+    pub fn new(chunk_size: usize) -> Self {
+        if chunk_size == 0 {
+            panic!("The chunk size must be at least 1");
+        }
+
+        let centers_bulk = (0..3 * chunk_size * (chunk_size - 1) + 1)
+            .map(|id| {
+                // Do id == 0 as a special case
+                if id == 0 {
+                    return types::Point::new(0.0, 0.0);
+                }
+
+                // Get the layer and location
+                let layer =
+                    (0.5 * (1.0 + (1.0 + 4.0 / 3.0 * ((id as f64) - 1.0)).sqrt())).floor() as usize;
+                let rel_id = id - (3 * layer * (layer - 1) + 1);
+                let slice_id = rel_id / layer;
+                let location_id = rel_id - slice_id * layer;
+
+                // Get the coordinates
+                let (start, dir) = match slice_id {
+                    0 => (
+                        types::Point::new(0.0, 1.0),
+                        types::Point::new(-1.5 * INV_SQRT_3, -0.5),
+                    ),
+                    1 => (
+                        types::Point::new(-1.5 * INV_SQRT_3, 0.5),
+                        types::Point::new(0.0, -1.0),
+                    ),
+                    2 => (
+                        types::Point::new(-1.5 * INV_SQRT_3, -0.5),
+                        types::Point::new(1.5 * INV_SQRT_3, -0.5),
+                    ),
+                    3 => (
+                        types::Point::new(0.0, -1.0),
+                        types::Point::new(1.5 * INV_SQRT_3, 0.5),
+                    ),
+                    4 => (
+                        types::Point::new(1.5 * INV_SQRT_3, -0.5),
+                        types::Point::new(0.0, 1.0),
+                    ),
+                    _ => (
+                        types::Point::new(1.5 * INV_SQRT_3, 0.5),
+                        types::Point::new(-1.5 * INV_SQRT_3, 0.5),
+                    ),
+                };
+
+                return start * (layer as f64) + dir * (location_id as f64);
+            })
+            .collect();
+        let centers_edge_top = (0..chunk_size - 1)
+            .map(|id| {
+                return types::Point::new(
+                    -1.5 * INV_SQRT_3 * ((id + 1) as f64),
+                    (chunk_size as f64) - 0.5 * ((id + 1) as f64),
+                );
+            })
+            .collect();
+        let centers_edge_middle = (0..chunk_size - 1)
+            .map(|id| {
+                return types::Point::new(
+                    -1.5 * (chunk_size as f64) * INV_SQRT_3,
+                    0.5 * (chunk_size as f64) - ((id + 1) as f64),
+                );
+            })
+            .collect();
+        let centers_edge_bottom = (0..chunk_size - 1)
+            .map(|id| {
+                return types::Point::new(
+                    -1.5 * ((chunk_size - (id + 1)) as f64) * INV_SQRT_3,
+                    -0.5 * ((chunk_size + (id + 1)) as f64),
+                );
+            })
+            .collect();
+        let centers_vertex_top = vec![types::Point::new(
+            -1.5 * (chunk_size as f64) * INV_SQRT_3,
+            0.5 * (chunk_size as f64),
+        )];
+        let centers_vertex_bottom = vec![types::Point::new(
+            -1.5 * (chunk_size as f64) * INV_SQRT_3,
+            -0.5 * (chunk_size as f64),
+        )];
+
+        // The bounding circles are centered at the chunk center and include
+        // the corners of the outermost tiles
+        let radius = |centers: &Vec<types::Point>| {
+            return centers
+                .iter()
+                .fold(0.0_f64, |prev, next| return prev.max(next.norm()))
+                + INV_SQRT_3;
+        };
+        let radius_bulk = radius(&centers_bulk);
+        let radius_edge = [
+            radius(&centers_edge_top),
+            radius(&centers_edge_middle),
+            radius(&centers_edge_bottom),
+        ];
+        let radius_vertex = [radius(&centers_vertex_top), radius(&centers_vertex_bottom)];
+
+        return Self {
+            chunk_size,
+            centers_bulk,
+            centers_edge: [centers_edge_top, centers_edge_middle, centers_edge_bottom],
+            centers_vertex: [centers_vertex_top, centers_vertex_bottom],
+            radius_bulk,
+            radius_edge,
+            radius_vertex,
+        };
+    }
+
+    /// Retrieves the number of tiles from the center of a chunk to its edge
+    pub fn get_chunk_size(&self) -> usize {
+        return self.chunk_size;
+    }
+
+    /// Retrieves the number of tiles in the given chunk type
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of chunk
+    pub fn get_tile_count(&self, chunk_type: &ChunkType) -> usize {
+        return self.get_tile_centers(chunk_type).len();
+    }
+
+    /// Calculates the number of tiles in the given chunk type for any chunk
+    /// size without calculating the geometry
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of chunk
+    ///
+    /// chunk_size: The number of tiles from the center of a chunk to its edge
+    pub fn tile_count(chunk_type: &ChunkType, chunk_size: usize) -> usize {
+        return match chunk_type {
+            ChunkType::Bulk => 3 * chunk_size * chunk_size.saturating_sub(1) + 1,
+            ChunkType::Edge(_) => chunk_size.saturating_sub(1),
+            ChunkType::Vertex(_) => 1,
+        };
+    }
+
+    /// Retrieves centers relative to the center of the chunk for all tiles in
+    /// the given chunk type, they are sorted in the same way that they are
+    /// stored in the chunk
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of chunk
+    pub fn get_tile_centers(&self, chunk_type: &ChunkType) -> &[types::Point] {
+        return match chunk_type {
+            ChunkType::Bulk => &self.centers_bulk,
+            ChunkType::Edge(edge_type) => &self.centers_edge[edge_type.id()],
+            ChunkType::Vertex(vertex_type) => &self.centers_vertex[vertex_type.id()],
+        };
+    }
+
+    /// Retrieves the circle in world coordinates containing all tiles of the
+    /// given chunk type, it is centered at the center of the chunk, returns
+    /// (center, radius)
+    ///
+    /// # Parameters
+    ///
+    /// chunk_type: The type of chunk
+    ///
+    /// chunk_world_center: The center of the chunk in world coordinates
+    pub fn world_bounding_circle(
+        &self,
+        chunk_type: &ChunkType,
+        chunk_world_center: &types::Point,
+    ) -> (types::Point, f64) {
+        let radius = match chunk_type {
+            ChunkType::Bulk => self.radius_bulk,
+            ChunkType::Edge(edge_type) => self.radius_edge[edge_type.id()],
+            ChunkType::Vertex(vertex_type) => self.radius_vertex[vertex_type.id()],
+        };
+
+        return (*chunk_world_center, radius);
+    }
+
+    /// Calculates what chunk the given cartesian coordinate is within and returns its chunk index
+    ///
+    /// # Parameters
+    ///
+    /// point: The cartesian coordinates to use
+    pub fn coordinate_to_chunk(&self, point: &types::Point) -> types::Index {
+        return coordinate_to_chunk_with_size(point, self.chunk_size);
+    }
+
+    /// Calculates the center cartesian coordinate of the given chunk
+    ///
+    /// # Parameters
+    ///
+    /// index: The index of the chunk to use
+    pub fn chunk_to_coordinate(&self, index: &types::Index) -> types::Point {
+        return chunk_to_coordinate_with_size(index, self.chunk_size);
+    }
+}
+
+/// The first bytes of any gzip file
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// The chunk geometry for the default chunk size
+static DEFAULT_CHUNK_GEOMETRY: Lazy<ChunkGeometry> = Lazy::new(|| ChunkGeometry::new(CHUNK_SIZE));
+
+/// All data for a single tile including the base resources and current resources
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Tile {
+    /// The base values for the resources which the actual values will attempt to gravitate towards
+    pub base_resources: Resources,
+    /// The actual values of the resources
+    pub current_resources: Resources,
+}
+
+impl Tile {
+    /// Constructs a new til with the given base resources, the current
+    /// resources start at the base resources
+    ///
+    /// # Parameters
+    ///
+    /// base_resources: The base resources of this tile
+    pub fn new(base_resources: Resources) -> Self {
+        return Self {
+            base_resources,
+            current_resources: base_resources,
+        };
+    }
+}
+
+/// Identifies one of the main resource types
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResourceType {
+    /// Nutrients used to grow
+    Nutrients,
+    /// Energy used to survive
+    Energy,
+    /// Water used to allow processes to work
+    Water,
+}
+
+/// All the main resource types
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Resources {
+    /// Nutrients used to grow
+    pub nutrients: f64,
+    /// Energy used to survive
+    pub energy: f64,
+    /// Water used to allow processes to work
+    pub water: f64,
+}
+
+impl Resources {
+    /// Constructs a new set of resources
+    ///
+    /// # Parameters
+    ///
+    /// nutrients: The amount of nutrients
+    ///
+    /// energy: The amount of energy
+    ///
+    /// water: The amount of water
+    pub fn new(nutrients: f64, energy: f64, water: f64) -> Self {
+        return Self {
+            nutrients,
+            energy,
+            water,
+        };
+    }
+
+    /// Retrieves the amount of a single resource type
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to retrieve
+    pub fn get(&self, resource_type: ResourceType) -> f64 {
+        return match resource_type {
+            ResourceType::Nutrients => self.nutrients,
+            ResourceType::Energy => self.energy,
+            ResourceType::Water => self.water,
+        };
+    }
+
+    /// Retrieves the amount of a single resource type as mutable
+    ///
+    /// # Parameters
+    ///
+    /// resource_type: The resource type to retrieve
+    pub fn get_mut(&mut self, resource_type: ResourceType) -> &mut f64 {
+        return match resource_type {
+            ResourceType::Nutrients => &mut self.nutrients,
+            ResourceType::Energy => &mut self.energy,
+            ResourceType::Water => &mut self.water,
+        };
+    }
+}
+
+impl Add<Resources> for Resources {
+    type Output = Resources;
+
+    fn add(self, rhs: Resources) -> Self::Output {
+        return Self::new(
+            self.nutrients + rhs.nutrients,
+            self.energy + rhs.energy,
+            self.water + rhs.water,
+        );
+    }
+}
+
+impl Sub<Resources> for Resources {
+    type Output = Resources;
+
+    fn sub(self, rhs: Resources) -> Self::Output {
+        return Self::new(
+            self.nutrients - rhs.nutrients,
+            self.energy - rhs.energy,
+            self.water - rhs.water,
+        );
+    }
+}
+
+impl Mul<f64> for Resources {
+    type Output = Resources;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        return Self::new(self.nutrients * rhs, self.energy * rhs, self.water * rhs);
+    }
+}
+
+/// A source of some resource
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Source {
     /// A source with a Gaussian distribution
     Gaussian(types::Gaussian),
+    /// A source shaped like a ring which is constant between the inner and
+    /// outer radius and falls off like a Gaussian outside of it
+    Radial {
+        /// The center of the ring
+        center: types::Point,
+        /// The inner radius of the ring
+        inner_radius: f64,
+        /// The outer radius of the ring
+        outer_radius: f64,
+        /// The value within the ring
+        value: f64,
+        /// The standard deviation of the falloff outside of the ring
+        falloff: f64,
+    },
+    /// A source along a line segment which falls off like a Gaussian with
+    /// the distance to the segment
+    Line {
+        /// The start point of the segment
+        start: types::Point,
+        /// The end point of the segment
+        end: types::Point,
+        /// The standard deviation of the falloff away from the segment
+        width: f64,
+        /// The value on the segment
+        value: f64,
+    },
+    /// A source which is constant within each cell of a Voronoi diagram and
+    /// blends smoothly between neighbouring cells
+    Voronoi {
+        /// The seed point of each cell
+        seeds: Vec<types::Point>,
+        /// The value within each cell, it must have the same length as seeds
+        values: Vec<f64>,
+        /// The distance over which the values blend at the cell boundaries
+        falloff: f64,
+    },
 }
 
-impl Source {
-    /// Calculates the range for this source to be relevant
-    pub fn range(&self) -> f64 {
+impl Source {
+    /// Calculates the range for this source to be relevant
+    pub fn range(&self) -> f64 {
+        return match self {
+            Source::Gaussian(gaussian) => {
+                // Get the longest variance
+                let variances = gaussian.get_covariance().eigenvalues();
+
+                // Make sure it is not invalid
+                if cfg!(debug_assertions) && variances[1] < 0.0 {
+                    panic!("The variance is negative: {:?}", gaussian.get_covariance());
+                }
+
+                // Calculate the range at which value of the Gaussian is 1/256
+                (variances[0]
+                    * (gaussian.norm * gaussian.norm * 256.0 * 256.0
+                        / (4.0 * PI * PI * variances[0] * variances[1])))
+                    .sqrt()
+            }
+            Source::Radial {
+                outer_radius,
+                falloff,
+                ..
+            } => outer_radius + falloff * 3.0,
+            Source::Line {
+                start, end, width, ..
+            } => 0.5 * start.distance_to(end) + width * 3.0,
+            Source::Voronoi {
+                seeds,
+                values,
+                falloff,
+            } => {
+                // Make sure every cell has a value
+                if cfg!(debug_assertions) && (seeds.is_empty() || seeds.len() != values.len()) {
+                    panic!(
+                        "There are {:?} seeds but {:?} values",
+                        seeds.len(),
+                        values.len()
+                    );
+                }
+
+                // Find the seed furthest from the center
+                let center = self.center();
+                seeds
+                    .iter()
+                    .map(|seed| return seed.distance_to(&center))
+                    .fold(0.0, f64::max)
+                    + falloff * 3.0
+            }
+        };
+    }
+
+    /// Calculates the center of the source
+    pub fn center(&self) -> types::Point {
+        return match self {
+            Source::Gaussian(gaussian) => gaussian.mean,
+            Source::Radial { center, .. } => *center,
+            Source::Line { start, end, .. } => start.lerp(end, 0.5),
+            Source::Voronoi { seeds, .. } => {
+                seeds
+                    .iter()
+                    .fold(types::Point::new(0.0, 0.0), |sum, seed| return sum + seed)
+                    / seeds.len() as f64
+            }
+        };
+    }
+
+    /// Evaluates the contribution from this source on the resources at the given positions
+    ///
+    /// # Parameters
+    ///
+    /// offset: The offset to add to all points
+    ///
+    /// points: The positions to evaluate the source at
+    pub fn evaluate(&self, offset: &types::Point, points: &[types::Point]) -> Vec<f64> {
+        return match self {
+            Source::Gaussian(gaussian) => gaussian.evaluate(offset, points),
+            Source::Radial { .. } | Source::Line { .. } | Source::Voronoi { .. } => points
+                .iter()
+                .map(|point| return self.evaluate_single(&(point + offset)))
+                .collect(),
+        };
+    }
+
+    /// Evaluates the contribution from this source on the resources at the
+    /// given positions and writes them into a pre-allocated slice
+    ///
+    /// # Parameters
+    ///
+    /// offset: The offset to add to all points
+    ///
+    /// points: The positions to evaluate the source at
+    ///
+    /// out: The slice to write the values to, it must have the same length as points
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if out does not have the same length as points
+    pub fn evaluate_into(&self, offset: &types::Point, points: &[types::Point], out: &mut [f64]) {
+        if cfg!(debug_assertions) && points.len() != out.len() {
+            panic!(
+                "The output length {:?} does not match the number of points {:?}",
+                out.len(),
+                points.len()
+            );
+        }
+
+        points
+            .iter()
+            .zip(out.iter_mut())
+            .for_each(|(point, value)| {
+                *value = self.evaluate_single(&(point + offset));
+            });
+    }
+
+    /// Multiplies the strength of the source by the given factor
+    ///
+    /// # Parameters
+    ///
+    /// factor: The factor to multiply the strength by
+    pub fn scale(&mut self, factor: f64) {
+        match self {
+            Source::Gaussian(gaussian) => gaussian.norm *= factor,
+            Source::Radial { value, .. } | Source::Line { value, .. } => *value *= factor,
+            Source::Voronoi { values, .. } => values.iter_mut().for_each(|value| *value *= factor),
+        };
+    }
+
+    /// Evaluates the contribution from this source on the resources at a single position
+    ///
+    /// # Parameters
+    ///
+    /// point: The position to evaluate the source at
+    #[inline]
+    pub fn evaluate_single(&self, point: &types::Point) -> f64 {
         return match self {
             Source::Gaussian(gaussian) => {
-                // Get the longest variance
-                let variances = gaussian.get_covariance().eigenvalues();
+                gaussian.evaluate_single(&types::Point::new(0.0, 0.0), point)
+            }
+            Source::Radial {
+                center,
+                inner_radius,
+                outer_radius,
+                value,
+                falloff,
+            } => {
+                // Get the distance to the ring
+                let radius = point.distance_to(center);
+                let distance = if radius < *inner_radius {
+                    inner_radius - radius
+                } else if radius > *outer_radius {
+                    radius - outer_radius
+                } else {
+                    return *value;
+                };
 
-                // Make sure it is not invalid
-                if cfg!(debug_assertions) && variances[1] < 0.0 {
-                    panic!("The variance is negative: {:?}", gaussian.get_covariance());
+                // Without falloff the ring has sharp edges
+                if *falloff <= 0.0 {
+                    return 0.0;
                 }
 
-                // Calculate the range at which value of the Gaussian is 1/256
-                (variances[0]
-                    * (gaussian.norm * gaussian.norm * 256.0 * 256.0
-                        / (4.0 * PI * PI * variances[0] * variances[1])))
-                    .sqrt()
+                value * (-0.5 * distance * distance / (falloff * falloff)).exp()
+            }
+            Source::Line {
+                start,
+                end,
+                width,
+                value,
+            } => {
+                let distance = point.distance_to_segment(start, end);
+
+                // Without a width the line has no extent
+                if *width <= 0.0 {
+                    return 0.0;
+                }
+
+                value * (-0.5 * distance * distance / (width * width)).exp()
+            }
+            Source::Voronoi {
+                seeds,
+                values,
+                falloff,
+            } => {
+                // Find the distance to the nearest seed and the value of its cell
+                let (nearest, nearest_value) = seeds.iter().zip(values.iter()).fold(
+                    (f64::INFINITY, 0.0),
+                    |(nearest, nearest_value), (seed, value)| {
+                        let distance = point.distance_to(seed);
+                        if distance < nearest {
+                            return (distance, *value);
+                        }
+                        return (nearest, nearest_value);
+                    },
+                );
+
+                // Without falloff the value of the nearest cell is used
+                if *falloff <= 0.0 {
+                    return nearest_value;
+                }
+
+                // Weigh each cell by how much further away its seed is than the nearest seed
+                let (total, weight) = seeds.iter().zip(values.iter()).fold(
+                    (0.0, 0.0),
+                    |(total, weight), (seed, value)| {
+                        let cell_weight = (-(point.distance_to(seed) - nearest) / falloff).exp();
+                        return (total + cell_weight * value, weight + cell_weight);
+                    },
+                );
+
+                if weight <= 0.0 {
+                    return 0.0;
+                }
+
+                total / weight
             }
         };
     }
+}
 
-    /// Calculates the center of the source
-    pub fn center(&self) -> types::Point {
-        return match self {
-            Source::Gaussian(gaussian) => gaussian.mean,
+impl FromStr for Source {
+    type Err = SourceParseError;
+
+    /// Parses a source from the type followed by space separated fields
+    /// written as name=value, numbers are written as is, points as [x,y] and
+    /// matrices as [[a,b],[c,d]], e.g.
+    /// "gaussian norm=6.28 mean=[3,0] cov=[[1,0],[0,2]]",
+    /// "radial center=[0,0] inner_radius=1 outer_radius=2 value=0.5 falloff=1" or
+    /// "line start=[0,0] end=[4,0] width=0.5 value=1" or
+    /// "voronoi seeds=[[0,0],[4,0],[0,4]] values=[1,0.5,0] falloff=0.5"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = split_top_level(s, char::is_whitespace);
+        let (source_type, tokens) = match tokens.split_first() {
+            Some(value) => value,
+            None => return Err(SourceParseError::UnknownType(String::new())),
+        };
+
+        // Find the field names required by the source type
+        let required: &[&str] = match *source_type {
+            "gaussian" => &["norm", "mean", "cov"],
+            "radial" => &["center", "inner_radius", "outer_radius", "value", "falloff"],
+            "line" => &["start", "end", "width", "value"],
+            "voronoi" => &["seeds", "values", "falloff"],
+            _ => return Err(SourceParseError::UnknownType(source_type.to_string())),
         };
+
+        // Split all fields into names and values
+        let fields = tokens
+            .iter()
+            .map(|token| {
+                return match token.split_once('=') {
+                    Some((name, value)) if required.contains(&name) => Ok((name, value)),
+                    Some((name, _)) => Err(SourceParseError::UnknownField(name.to_string())),
+                    None => Err(SourceParseError::UnknownField(token.to_string())),
+                };
+            })
+            .collect::<Result<HashMap<&str, &str>, SourceParseError>>()?;
+        let field = |name: &str| {
+            return fields
+                .get(name)
+                .copied()
+                .ok_or_else(|| return SourceParseError::MissingField(name.to_string()));
+        };
+        let number = |name: &str| return parse_number(name, field(name)?);
+        let point = |name: &str| return parse_point(name, field(name)?);
+
+        return Ok(match *source_type {
+            "gaussian" => {
+                // The covariance must be symmetric and positive definite
+                let cov = parse_matrix("cov", field("cov")?)?;
+                let [[a, b], [c, d]] = cov.values;
+                if b != c || a <= 0.0 || d <= 0.0 || cov.det() <= 0.0 {
+                    return Err(SourceParseError::InvalidValue(
+                        "cov".to_string(),
+                        field("cov")?.to_string(),
+                    ));
+                }
+
+                Source::Gaussian(types::Gaussian::new(number("norm")?, point("mean")?, cov))
+            }
+            "radial" => Source::Radial {
+                center: point("center")?,
+                inner_radius: number("inner_radius")?,
+                outer_radius: number("outer_radius")?,
+                value: number("value")?,
+                falloff: number("falloff")?,
+            },
+            "line" => Source::Line {
+                start: point("start")?,
+                end: point("end")?,
+                width: number("width")?,
+                value: number("value")?,
+            },
+            _ => {
+                let seeds = parse_items("seeds", field("seeds")?)?
+                    .into_iter()
+                    .map(|seed| return parse_point("seeds", seed))
+                    .collect::<Result<Vec<types::Point>, SourceParseError>>()?;
+                let values = parse_items("values", field("values")?)?
+                    .into_iter()
+                    .map(|value| return parse_number("values", value))
+                    .collect::<Result<Vec<f64>, SourceParseError>>()?;
+
+                // Every cell must have a value
+                if seeds.is_empty() || seeds.len() != values.len() {
+                    return Err(SourceParseError::InvalidValue(
+                        "values".to_string(),
+                        field("values")?.to_string(),
+                    ));
+                }
+
+                Source::Voronoi {
+                    seeds,
+                    values,
+                    falloff: number("falloff")?,
+                }
+            }
+        });
     }
+}
 
-    /// Evaluates the contribution from this source on the resources at the given positions
-    ///
-    /// # Parameters
-    ///
-    /// points: The positions to evaluate the source at
-    pub fn evaluate(&self, offset: &types::Point, points: &[types::Point]) -> Vec<f64> {
+impl fmt::Display for Source {
+    /// Writes the source in the format read by from_str
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         return match self {
-            Source::Gaussian(gaussian) => gaussian.evaluate(offset, points),
+            Source::Gaussian(gaussian) => {
+                let cov = gaussian.get_covariance().values;
+                write!(
+                    f,
+                    "gaussian norm={} mean=[{},{}] cov=[[{},{}],[{},{}]]",
+                    gaussian.norm,
+                    gaussian.mean.x,
+                    gaussian.mean.y,
+                    cov[0][0],
+                    cov[0][1],
+                    cov[1][0],
+                    cov[1][1],
+                )
+            }
+            Source::Radial {
+                center,
+                inner_radius,
+                outer_radius,
+                value,
+                falloff,
+            } => write!(
+                f,
+                "radial center=[{},{}] inner_radius={} outer_radius={} value={} falloff={}",
+                center.x, center.y, inner_radius, outer_radius, value, falloff,
+            ),
+            Source::Line {
+                start,
+                end,
+                width,
+                value,
+            } => write!(
+                f,
+                "line start=[{},{}] end=[{},{}] width={} value={}",
+                start.x, start.y, end.x, end.y, width, value,
+            ),
+            Source::Voronoi {
+                seeds,
+                values,
+                falloff,
+            } => write!(
+                f,
+                "voronoi seeds=[{}] values=[{}] falloff={}",
+                seeds
+                    .iter()
+                    .map(|seed| return format!("[{},{}]", seed.x, seed.y))
+                    .collect::<Vec<String>>()
+                    .join(","),
+                values
+                    .iter()
+                    .map(|value| return value.to_string())
+                    .collect::<Vec<String>>()
+                    .join(","),
+                falloff,
+            ),
+        };
+    }
+}
+
+/// Splits the text at all characters for which is_separator is true which
+/// are not inside square brackets, empty parts are removed
+///
+/// # Parameters
+///
+/// text: The text to split
+///
+/// is_separator: Returns true for the characters to split at
+fn split_top_level(text: &str, is_separator: impl Fn(char) -> bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    text.char_indices().for_each(|(id, character)| {
+        match character {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ if depth == 0 && is_separator(character) => {
+                parts.push(&text[start..id]);
+                start = id + character.len_utf8();
+            }
+            _ => (),
         };
+    });
+    parts.push(&text[start..]);
+
+    return parts
+        .into_iter()
+        .map(|part| return part.trim())
+        .filter(|part| return !part.is_empty())
+        .collect();
+}
+
+/// Parses the items of a list written as [a,b], returns the items which may
+/// themselves be lists
+///
+/// # Parameters
+///
+/// name: The name of the field for error messages
+///
+/// value: The text to parse
+fn parse_list<'a>(name: &str, value: &'a str) -> Result<Vec<&'a str>, SourceParseError> {
+    let items = parse_items(name, value)?;
+    if items.len() != 2 {
+        return Err(SourceParseError::InvalidValue(
+            name.to_string(),
+            value.to_string(),
+        ));
     }
+
+    return Ok(items);
+}
+
+/// Parses the items of a list of any length written as [a,b,...], returns
+/// the items which may themselves be lists
+///
+/// # Parameters
+///
+/// name: The name of the field for error messages
+///
+/// value: The text to parse
+fn parse_items<'a>(name: &str, value: &'a str) -> Result<Vec<&'a str>, SourceParseError> {
+    let inner = value
+        .trim()
+        .strip_prefix('[')
+        .and_then(|value| return value.strip_suffix(']'))
+        .ok_or_else(|| {
+            return SourceParseError::InvalidValue(name.to_string(), value.to_string());
+        })?;
+
+    return Ok(split_top_level(inner, |character| return character == ','));
+}
+
+/// Parses a finite number for a source field
+///
+/// # Parameters
+///
+/// name: The name of the field for error messages
+///
+/// value: The text to parse
+fn parse_number(name: &str, value: &str) -> Result<f64, SourceParseError> {
+    return match value.trim().parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(number),
+        _ => Err(SourceParseError::InvalidValue(
+            name.to_string(),
+            value.to_string(),
+        )),
+    };
+}
+
+/// Parses a point written as [x,y] for a source field
+///
+/// # Parameters
+///
+/// name: The name of the field for error messages
+///
+/// value: The text to parse
+fn parse_point(name: &str, value: &str) -> Result<types::Point, SourceParseError> {
+    let items = parse_list(name, value)?;
+
+    return Ok(types::Point::new(
+        parse_number(name, items[0])?,
+        parse_number(name, items[1])?,
+    ));
+}
+
+/// Parses a matrix written as [[a,b],[c,d]] for a source field
+///
+/// # Parameters
+///
+/// name: The name of the field for error messages
+///
+/// value: The text to parse
+fn parse_matrix(name: &str, value: &str) -> Result<types::Matrix, SourceParseError> {
+    let rows = parse_list(name, value)?;
+    let row0 = parse_point(name, rows[0])?;
+    let row1 = parse_point(name, rows[1])?;
+
+    return Ok(types::Matrix::from_rows([row0.x, row0.y], [row1.x, row1.y]));
+}
+
+/// The error types for when parsing a source from a string
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum SourceParseError {
+    /// The type of source is not known
+    #[error("Unknown source type {:?}", .0)]
+    UnknownType(String),
+    /// A field required by the source type is missing
+    #[error("The field {:?} is missing", .0)]
+    MissingField(String),
+    /// A field is not used by the source type
+    #[error("The field {:?} is not used by the source type", .0)]
+    UnknownField(String),
+    /// The value of a field could not be parsed
+    #[error("Invalid value {:?} for the field {:?}", .1, .0)]
+    InvalidValue(String, String),
 }
 
 /// The error types for when creating a new chunk
@@ -756,4 +4052,599 @@ pub enum NewChunkError {
     /// The number of tiles was incorrect
     #[error("The number of tiles was incorrect, received {:?} but expected {:?}", .0, .1)]
     InvalidSize(usize, usize),
+    /// The chunk size was 0
+    #[error("The chunk size must be at least 1")]
+    InvalidChunkSize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::f64::consts::PI;
+
+    /// The tolerance used when comparing floating point results
+    const EPS: f64 = 1e-9;
+
+    /// Creates sources giving every resource type a non-uniform distribution
+    fn test_sources() -> SourceMap {
+        return SourceMap {
+            nutrients: vec![Source::Gaussian(types::Gaussian::new(
+                2.0 * PI,
+                types::Point::new(0.0, 0.0),
+                types::Matrix::identity(),
+            ))],
+            energy: vec![Source::Gaussian(types::Gaussian::new(
+                2.0 * PI * 2.0,
+                types::Point::new(3.0, 0.0),
+                types::Matrix::new(&[[1.0, 0.0], [0.0, 2.0]]),
+            ))],
+            water: vec![Source::Gaussian(types::Gaussian::new(
+                2.0 * PI * 0.5,
+                types::Point::new(0.0, 4.0),
+                types::Matrix::new(&[[1.0, 0.5], [0.5, 1.0]]),
+            ))],
+        };
+    }
+
+    /// Creates a small map of every layout with the test sources
+    fn test_maps() -> Vec<Map> {
+        return [
+            MapLayout::Cyclic,
+            MapLayout::Finite(3, 2),
+            MapLayout::Toroidal(3, 2),
+        ]
+        .into_iter()
+        .map(|layout| {
+            return MapBuilder::new(test_sources())
+                .with_chunk_size(4)
+                .with_layout(layout)
+                .build();
+        })
+        .collect();
+    }
+
+    /// Checks that all resource types are equal within the tolerance
+    ///
+    /// # Parameters
+    ///
+    /// a: The first resources
+    ///
+    /// b: The second resources
+    fn resources_approx_eq(a: &Resources, b: &Resources) -> bool {
+        return (a.nutrients - b.nutrients).abs() < EPS
+            && (a.energy - b.energy).abs() < EPS
+            && (a.water - b.water).abs() < EPS;
+    }
+
+    #[test]
+    fn sampling_at_tile_centers_gives_the_tile_resources() {
+        test_maps().into_iter().for_each(|map| {
+            map.iter_tiles().for_each(|(tile, center)| {
+                assert!(resources_approx_eq(
+                    &map.sample_resources_at(&center),
+                    &tile.current_resources
+                ));
+            });
+
+            // A point inside the triangle between three centers blends them
+            let index = types::Index::new(0, 0);
+            let neighbours = index.hex_neighbors();
+            let corners = [index, neighbours[0], neighbours[1]];
+            let weights = [0.6, 0.25, 0.15];
+            let (point, expected) = corners.iter().zip(weights.iter()).fold(
+                (types::Point::new(0.0, 0.0), Resources::new(0.0, 0.0, 0.0)),
+                |(prev_point, prev_resources), (corner, weight)| {
+                    let resources = map
+                        .get_tile(corner)
+                        .expect("Should not happen")
+                        .current_resources;
+                    return (
+                        prev_point + tile_to_coordinate(corner) * *weight,
+                        prev_resources + resources * *weight,
+                    );
+                },
+            );
+            assert!(resources_approx_eq(
+                &map.sample_resources_at(&point),
+                &expected
+            ));
+        });
+    }
+
+    #[test]
+    fn normalized_source_map_gives_the_expected_mean() {
+        let mut map = MapBuilder::new(test_sources())
+            .with_chunk_size(4)
+            .with_layout(MapLayout::Finite(3, 2))
+            .build();
+        let mut sources = map.get_sources().clone();
+        sources
+            .get_mut(ResourceType::Nutrients)
+            .iter_mut()
+            .for_each(|source| source.scale(10.0));
+
+        // Without periodic images the combined value is the sum of the sources
+        // evaluated at the tile centers, the mean scales with the maximum
+        let values = map
+            .iter_tiles()
+            .map(|(_, center)| {
+                return sources
+                    .get(ResourceType::Nutrients)
+                    .iter()
+                    .map(|source| return source.evaluate_single(&center))
+                    .sum::<f64>();
+            })
+            .collect::<Vec<f64>>();
+        let max = values.iter().copied().fold(0.0, f64::max);
+        assert!(max > 1.0);
+        let desired_mean = values.iter().sum::<f64>() / (values.len() as f64 * max);
+
+        sources.normalize(map.get_data());
+        *map.get_sources_mut().get_mut() = sources;
+        let tile_count = map.iter_tiles().count() as f64;
+        let mean = map.total_resources().nutrients / tile_count;
+        assert!((mean - desired_mean).abs() < 1e-6 * desired_mean);
+    }
+
+    #[test]
+    fn normalized_sources_peak_at_one_without_clamping() {
+        let max_base = |map: &Map, resource_type: ResourceType| {
+            return map
+                .get_data()
+                .get_chunks()
+                .flat_map(|chunk| return chunk.get_tiles().iter())
+                .map(|tile| return tile.base_resources.get(resource_type))
+                .fold(0.0, f64::max);
+        };
+
+        test_maps().into_iter().for_each(|mut map| {
+            let layout = map.get_data().get_layout().expect("Should not happen");
+
+            // A wide source centered far outside the stored tiles only reaches
+            // the map through its periodic images
+            if !matches!(layout, MapLayout::Finite(_, _)) {
+                map.set_sources(
+                    ResourceType::Nutrients,
+                    vec![Source::Gaussian(types::Gaussian::new(
+                        100.0,
+                        types::Point::new(157.0, -83.0),
+                        types::Matrix::new(&[[9.0, 0.0], [0.0, 9.0]]),
+                    ))],
+                );
+            }
+
+            map.normalize_sources();
+            let sources = map.get_sources().clone();
+            [
+                ResourceType::Nutrients,
+                ResourceType::Energy,
+                ResourceType::Water,
+            ]
+            .into_iter()
+            .for_each(|resource_type| {
+                assert!(
+                    (max_base(&map, resource_type) - 1.0).abs() < EPS,
+                    "{:?} {:?}",
+                    layout,
+                    resource_type
+                );
+
+                // Halving the sources halves the peak so nothing was clamped
+                let mut halved = sources.get(resource_type).clone();
+                halved.iter_mut().for_each(|source| source.scale(0.5));
+                map.set_sources(resource_type, halved);
+                assert!(
+                    (max_base(&map, resource_type) - 0.5).abs() < EPS,
+                    "{:?} {:?}",
+                    layout,
+                    resource_type
+                );
+            });
+        });
+    }
+
+    #[test]
+    fn flood_fill_stays_within_high_nutrient_disc() {
+        // The nutrients peak at 1 in the origin and exceed one half within
+        // a radius of sqrt(2 ln 2) from it
+        let radius = (2.0 * 2.0f64.ln()).sqrt();
+        test_maps().into_iter().for_each(|map| {
+            let region = map.flood_fill_tiles(types::Index::new(0, 0), |tile| {
+                return tile.current_resources.nutrients > 0.5;
+            });
+
+            assert!(!region.is_empty());
+            region.iter().for_each(|index| {
+                assert!(tile_to_coordinate(index).norm() < radius);
+            });
+
+            let mut region = region;
+            let mut expected = map.find_tiles_above_threshold(ResourceType::Nutrients, 0.5);
+            region.sort();
+            expected.sort();
+            assert_eq!(region, expected);
+
+            // Nothing is found when the start tile is outside of the region
+            assert!(map
+                .flood_fill_tiles(types::Index::new(2, 0), |tile| {
+                    return tile.current_resources.nutrients > 0.5;
+                })
+                .is_empty());
+        });
+    }
+
+    #[test]
+    fn voronoi_regions_assign_the_closest_source() {
+        let sources = [
+            types::Point::new(0.0, 0.0),
+            types::Point::new(6.0, 1.0),
+            types::Point::new(-3.0, 5.0),
+            types::Point::new(2.0, -7.0),
+        ];
+        test_maps().into_iter().for_each(|map| {
+            let regions = map.compute_voronoi_regions(&sources);
+            assert_eq!(regions.len(), map.iter_tiles().count());
+
+            map.iter_tiles()
+                .zip(regions.iter())
+                .for_each(|((_, center), region)| {
+                    let distance = (sources[*region] - center).norm();
+                    sources.iter().for_each(|source| {
+                        assert!(distance <= (*source - center).norm() + EPS);
+                    });
+                });
+        });
+    }
+
+    #[test]
+    fn noise_layer_persists_through_repopulation() {
+        test_maps().into_iter().for_each(|mut map| {
+            let base_of = |map: &Map| {
+                return map
+                    .iter_tiles()
+                    .map(|(tile, _)| return tile.base_resources)
+                    .collect::<Vec<Resources>>();
+            };
+            let base = base_of(&map);
+
+            // A constant noise of 1 changes nothing
+            map.apply_noise_layer(ResourceType::Nutrients, |_| return 1.0);
+            base_of(&map).iter().zip(base.iter()).for_each(|(a, b)| {
+                assert!(resources_approx_eq(a, b));
+            });
+
+            // The nutrients peak at 1 so halving them is not affected by clamping
+            map.apply_noise_layer(ResourceType::Nutrients, |_| return 0.5);
+            let halved = base
+                .iter()
+                .map(|resources| {
+                    return Resources::new(
+                        resources.nutrients * 0.5,
+                        resources.energy,
+                        resources.water,
+                    );
+                })
+                .collect::<Vec<Resources>>();
+            base_of(&map).iter().zip(halved.iter()).for_each(|(a, b)| {
+                assert!(resources_approx_eq(a, b));
+            });
+
+            // Repopulating from the sources keeps the layer
+            let sources = map.get_sources().get(ResourceType::Nutrients).clone();
+            map.set_sources(ResourceType::Nutrients, sources);
+            base_of(&map).iter().zip(halved.iter()).for_each(|(a, b)| {
+                assert!(resources_approx_eq(a, b));
+            });
+
+            map.clear_noise_layers();
+            base_of(&map).iter().zip(base.iter()).for_each(|(a, b)| {
+                assert!(resources_approx_eq(a, b));
+            });
+        });
+    }
+
+    #[test]
+    fn source_parsing_rejects_invalid_covariances_and_numbers() {
+        assert!("gaussian norm=6.28 mean=[3,0] cov=[[1,0.5],[0.5,2]]"
+            .parse::<Source>()
+            .is_ok());
+
+        [
+            "gaussian norm=6.28 mean=[3,0] cov=[[1,2],[2,4]]",
+            "gaussian norm=6.28 mean=[3,0] cov=[[1,2],[2,1]]",
+            "gaussian norm=6.28 mean=[3,0] cov=[[-1,0],[0,-2]]",
+            "gaussian norm=6.28 mean=[3,0] cov=[[1,0.5],[0,2]]",
+        ]
+        .into_iter()
+        .for_each(|text| {
+            assert!(
+                matches!(
+                    text.parse::<Source>(),
+                    Err(SourceParseError::InvalidValue(name, _)) if name == "cov"
+                ),
+                "{:?}",
+                text
+            );
+        });
+
+        [
+            ("gaussian norm=NaN mean=[3,0] cov=[[1,0],[0,1]]", "norm"),
+            ("gaussian norm=1 mean=[inf,0] cov=[[1,0],[0,1]]", "mean"),
+            ("line start=[0,0] end=[4,0] width=-inf value=1", "width"),
+        ]
+        .into_iter()
+        .for_each(|(text, field)| {
+            assert!(
+                matches!(
+                    text.parse::<Source>(),
+                    Err(SourceParseError::InvalidValue(name, _)) if name == field
+                ),
+                "{:?}",
+                text
+            );
+        });
+    }
+
+    #[test]
+    fn saved_maps_keep_their_terrain() {
+        let directory = std::env::temp_dir().join("growth_simulator_terrain_test");
+        test_maps().into_iter().for_each(|mut map| {
+            let layout = map.get_data().get_layout().expect("Should not happen");
+            let count = map.iter_tiles().count();
+            map.set_terrain(TerrainMap {
+                heights: (0..count)
+                    .map(|id| return id as f64 / count as f64)
+                    .collect(),
+            });
+
+            [MapFormat::Json, MapFormat::Binary]
+                .into_iter()
+                .for_each(|format| {
+                    let path = directory.join(format!("{:?}_{:?}", layout, format));
+                    map.save(&path, format).expect("Should not happen");
+                    let (mut loaded, _) = Map::load(&path).expect("Should not happen");
+
+                    let heights = &loaded.get_terrain().expect("Should not happen").heights;
+                    let expected = &map.get_terrain().expect("Should not happen").heights;
+                    assert_eq!(heights.len(), expected.len());
+                    heights.iter().zip(expected.iter()).for_each(|(a, b)| {
+                        assert!((a - b).abs() < EPS);
+                    });
+
+                    // Repopulating the loaded map gives the same base resources
+                    loaded.populate_resources();
+                    loaded
+                        .iter_tiles()
+                        .zip(map.iter_tiles())
+                        .for_each(|(a, b)| {
+                            assert!(resources_approx_eq(
+                                &a.0.base_resources,
+                                &b.0.base_resources
+                            ));
+                        });
+                });
+        });
+    }
+
+    #[test]
+    fn saved_maps_keep_their_noise() {
+        let directory = std::env::temp_dir().join("growth_simulator_noise_test");
+        test_maps().into_iter().for_each(|mut map| {
+            let layout = map.get_data().get_layout().expect("Should not happen");
+
+            // The nutrients peak at 1 so halving them is not affected by clamping
+            let halved = map
+                .iter_tiles()
+                .map(|(tile, _)| {
+                    let resources = tile.base_resources;
+                    return Resources::new(
+                        resources.nutrients * 0.5,
+                        resources.energy,
+                        resources.water,
+                    );
+                })
+                .collect::<Vec<Resources>>();
+            map.apply_noise_layer(ResourceType::Nutrients, |_| return 0.5);
+
+            [MapFormat::Json, MapFormat::Binary]
+                .into_iter()
+                .for_each(|format| {
+                    let path = directory.join(format!("{:?}_{:?}", layout, format));
+                    map.save(&path, format).expect("Should not happen");
+                    let (mut loaded, _) = Map::load(&path).expect("Should not happen");
+
+                    // Repopulating the loaded map keeps the noise layer
+                    loaded.populate_resources();
+                    loaded
+                        .iter_tiles()
+                        .zip(halved.iter())
+                        .for_each(|((tile, _), expected)| {
+                            assert!(resources_approx_eq(&tile.base_resources, expected));
+                        });
+                });
+        });
+    }
+
+    #[test]
+    fn voronoi_source_blends_the_nearest_cells() {
+        let source = |falloff: f64| {
+            return Source::Voronoi {
+                seeds: vec![types::Point::new(0.0, 0.0), types::Point::new(4.0, 0.0)],
+                values: vec![1.0, 0.5],
+                falloff,
+            };
+        };
+
+        // Without falloff the value of the nearest cell is used
+        assert_eq!(
+            source(0.0).evaluate_single(&types::Point::new(1.0, 1.0)),
+            1.0
+        );
+        assert_eq!(
+            source(0.0).evaluate_single(&types::Point::new(3.0, -1.0)),
+            0.5
+        );
+
+        // Halfway between the seeds both cells are weighted equally
+        let value = source(0.5).evaluate_single(&types::Point::new(2.0, 3.0));
+        assert!((value - 0.75).abs() < EPS);
+
+        // Next to a seed the other cell only contributes a little
+        let value = source(0.5).evaluate_single(&types::Point::new(0.0, 0.0));
+        let weight = (-4.0f64 / 0.5).exp();
+        assert!((value - (1.0 + 0.5 * weight) / (1.0 + weight)).abs() < EPS);
+    }
+
+    #[test]
+    fn layout_tile_count_matches_built_map() {
+        test_maps().into_iter().for_each(|map| {
+            let layout = map.get_data().get_layout().expect("Should not happen");
+            let tile_count = map
+                .get_data()
+                .get_chunks()
+                .map(|chunk| return chunk.get_tiles().len())
+                .sum::<usize>();
+            assert_eq!(layout.tile_count(4), Some(tile_count), "{:?}", layout);
+        });
+
+        assert_eq!(MapLayout::Finite(0, 3).tile_count(4), None);
+        assert_eq!(MapLayout::Toroidal(usize::MAX, 2).tile_count(4), None);
+    }
+
+    #[test]
+    fn maps_with_same_seed_are_identical() {
+        let build = |seed: u64| {
+            return MapBuilder::new(test_sources())
+                .with_chunk_size(4)
+                .with_layout(MapLayout::Finite(3, 2))
+                .with_seed(seed)
+                .build();
+        };
+        let map_a = build(7);
+        let map_b = build(7);
+        assert_eq!(map_a.get_seed(), 7);
+
+        // Compare the bits such that even the rounding must be the same
+        let bits = |map: &Map| {
+            return map
+                .get_base_resources()
+                .iter()
+                .flat_map(|resources| {
+                    return [resources.nutrients, resources.energy, resources.water];
+                })
+                .map(|value| return value.to_bits())
+                .collect::<Vec<u64>>();
+        };
+        assert_eq!(bits(&map_a), bits(&map_b));
+    }
+
+    #[test]
+    fn diffusion_conserves_total_resources() {
+        test_maps().into_iter().for_each(|mut map| {
+            // Move the current resources away from the base resources such that the relaxation contributes
+            let index = types::Index::new(0, 0);
+            map.get_tile_mut(&index)
+                .expect("Should not happen")
+                .current_resources = Resources::new(1.0, 1.0, 1.0);
+
+            let dt = 0.1;
+            (0..10).for_each(|_| {
+                let total_base = map.total_resources();
+                let total_current = map.total_current_resources();
+                map.step_diffusion(dt, 0.2);
+
+                // The diffusion itself moves resources between tiles, only the relaxation changes the total
+                let expected =
+                    total_current + (total_base - total_current) * (RESOURCE_RELAXATION_RATE * dt);
+                assert!(
+                    resources_approx_eq(&map.total_current_resources(), &expected),
+                    "{:?} != {:?} for {:?}",
+                    map.total_current_resources(),
+                    expected,
+                    map.get_data().get_layout()
+                );
+            });
+        });
+    }
+
+    #[test]
+    fn interactive_source_matches_full_population() {
+        let source = Source::Gaussian(types::Gaussian::new(
+            PI,
+            types::Point::new(1.5, -1.0),
+            types::Matrix::new(&[[0.5, 0.1], [0.1, 0.8]]),
+        ));
+
+        test_maps()
+            .into_iter()
+            .zip(test_maps())
+            .for_each(|(mut map, mut expected)| {
+                let index = map.add_source_interactive(source.clone(), ResourceType::Nutrients);
+                assert_eq!(index, 1);
+
+                let mut sources = expected.get_sources().get(ResourceType::Nutrients).clone();
+                sources.push(source.clone());
+                expected.set_sources(ResourceType::Nutrients, sources);
+
+                map.iter_tiles()
+                    .zip(expected.iter_tiles())
+                    .for_each(|((a, _), (b, _))| {
+                        assert!(resources_approx_eq(&a.base_resources, &b.base_resources));
+                    });
+            });
+    }
+
+    #[test]
+    fn reset_to_base_restarts_the_diffusion_trajectory() {
+        let current = |map: &Map| {
+            return map
+                .iter_tiles()
+                .map(|(tile, _)| return tile.current_resources)
+                .collect::<Vec<Resources>>();
+        };
+
+        test_maps()
+            .into_iter()
+            .zip(test_maps())
+            .for_each(|(mut map, mut fresh)| {
+                // Run the simulation for a while and disturb it before resetting
+                (0..10).for_each(|_| map.step_diffusion(0.1, 0.2));
+                map.get_tile_mut(&types::Index::new(0, 0))
+                    .expect("Should not happen")
+                    .current_resources = Resources::new(1.0, 1.0, 1.0);
+                map.reset_to_base();
+                map.iter_tiles().for_each(|(tile, _)| {
+                    assert!(resources_approx_eq(
+                        &tile.current_resources,
+                        &tile.base_resources
+                    ));
+                });
+
+                (0..10).for_each(|_| {
+                    map.step_diffusion(0.1, 0.2);
+                    fresh.step_diffusion(0.1, 0.2);
+                    current(&map)
+                        .iter()
+                        .zip(current(&fresh).iter())
+                        .for_each(|(a, b)| assert!(resources_approx_eq(a, b)));
+                });
+            });
+    }
+
+    proptest! {
+        #[test]
+        fn tile_to_coordinate_round_trip(x in -1000i64..=1000, y in -1000i64..=1000) {
+            let index = types::Index::new(x, y);
+            prop_assert_eq!(coordinate_to_tile(&tile_to_coordinate(&index)), index);
+        }
+
+        #[test]
+        fn coordinate_to_tile_is_within_tile(x in -1000.0f64..1000.0, y in -1000.0f64..1000.0) {
+            // The corners of a tile are INV_SQRT_3 from its center
+            let point = types::Point::new(x, y);
+            let center = tile_to_coordinate(&coordinate_to_tile(&point));
+            prop_assert!((center - point).norm() <= INV_SQRT_3 + EPS);
+        }
+    }
 }
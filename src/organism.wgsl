@@ -0,0 +1,42 @@
+// Uniforms
+// The transform to apply to each vertex
+@group(0) @binding(0)
+var<uniform> transform: mat2x2<f32>;
+
+// The center of the coordinate system which is subtracted before transforming
+@group(0) @binding(3)
+var<uniform> offset: vec2<f32>;
+
+// Type definitions
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+// Vertex shader, the corner is on an octagon around the unit circle
+@vertex
+fn vs_main(
+    @location(0) corner: vec2<f32>,
+    @location(1) center: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) radius: f32,
+) -> VertexOutput {
+    var out: VertexOutput;
+    var pos = transform * (center + corner * radius - offset);
+    out.clip_position = vec4<f32>(pos, 0.0, 1.0);
+    out.local = corner;
+    out.color = color;
+    return out;
+}
+
+// Fragment shader, everything outside the unit circle is removed
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    if length(in.local) > 1.0 {
+        discard;
+    }
+    return in.color;
+}
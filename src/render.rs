@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
 use thiserror::Error;
 use winit::{dpi::PhysicalSize, window::Window};
 
@@ -8,9 +8,11 @@ pub struct RenderState {
     device: wgpu::Device,
     /// The command queue for sending info to the gpu
     queue: wgpu::Queue,
-    /// The surface to draw on
-    surface: wgpu::Surface<'static>,
-    /// The configurations of the surface
+    /// The surface to draw on, None if rendering offscreen
+    surface: Option<wgpu::Surface<'static>>,
+    /// The texture to draw on when rendering offscreen, None if rendering to a surface
+    offscreen: Option<wgpu::Texture>,
+    /// The configurations of the surface, for offscreen rendering it describes the texture
     config: wgpu::SurfaceConfiguration,
 }
 
@@ -53,17 +55,7 @@ impl RenderState {
             .ok_or(NewRenderStateError::GetAdapter)?;
 
         // Create a logical device and a command queue
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: Some("Request Device"),
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                    memory_hints: wgpu::MemoryHints::Performance,
-                },
-                None,
-            )
-            .await?;
+        let (device, queue) = Self::request_device(&adapter).await?;
 
         // Get the capabilities of the surface
         let surface_caps = surface.get_capabilities(&adapter);
@@ -92,20 +84,159 @@ impl RenderState {
         Ok(Self {
             device,
             queue,
-            surface,
+            surface: Some(surface),
+            offscreen: None,
             config,
         })
     }
 
-    /// Called when the window has been resized
+    /// Creates a new render state which renders to a texture instead of a
+    /// window, the software fallback adapter is preferred if it exists
+    ///
+    /// # Parameters
+    ///
+    /// width: The width of the texture to render to
+    ///
+    /// height: The height of the texture to render to
+    ///
+    /// # Errors
+    ///
+    /// See NewRenderStateError for a description of the different errors which may occur
+    pub async fn new_offscreen(width: u32, height: u32) -> Result<Self, NewRenderStateError> {
+        if width == 0 || height == 0 {
+            return Err(NewRenderStateError::InvalidSize(PhysicalSize::new(
+                width, height,
+            )));
+        }
+
+        // Get a handle to the API
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            flags: wgpu::InstanceFlags::VALIDATION,
+            dx12_shader_compiler: Default::default(),
+            gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
+        });
+
+        // Get an adapter to the GPU, prefer the software fallback
+        let mut adapter = None;
+        for force_fallback_adapter in [true, false] {
+            adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::default(),
+                    compatible_surface: None,
+                    force_fallback_adapter,
+                })
+                .await;
+            if adapter.is_some() {
+                break;
+            }
+        }
+        let adapter = adapter.ok_or(NewRenderStateError::GetAdapter)?;
+
+        // Create a logical device and a command queue
+        let (device, queue) = Self::request_device(&adapter).await?;
+
+        // Setup the configurations as if it was a surface and create the texture
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+        };
+        let offscreen = Self::create_offscreen_texture(&device, &config);
+
+        Ok(Self {
+            device,
+            queue,
+            surface: None,
+            offscreen: Some(offscreen),
+            config,
+        })
+    }
+
+    /// Creates the logical device and command queue for an adapter, timestamp
+    /// queries are enabled if the adapter supports them
+    ///
+    /// # Parameters
+    ///
+    /// adapter: The adapter to create the device for
+    async fn request_device(
+        adapter: &wgpu::Adapter,
+    ) -> Result<(wgpu::Device, wgpu::Queue), NewRenderStateError> {
+        let result = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("Request Device"),
+                    required_features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
+                    required_limits: wgpu::Limits::default(),
+                    memory_hints: wgpu::MemoryHints::Performance,
+                },
+                None,
+            )
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Creates a texture to render to when rendering offscreen
+    ///
+    /// # Parameters
+    ///
+    /// device: The device to create the texture on
+    ///
+    /// config: The configurations describing the size, format and usage of the texture
+    fn create_offscreen_texture(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            view_formats: &[],
+        })
+    }
+
+    /// Called when the window has been resized, it reconfigures the surface,
+    /// any size dependent resources of a graphics state must be updated afterwards
     ///
     /// # Parameters
     ///
     /// new_size: The new size of the window
-    pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
+    pub fn on_resize(&mut self, new_size: PhysicalSize<u32>) {
+        // A surface cannot have a size of 0 which happens when minimizing
+        if new_size.width == 0 || new_size.height == 0 {
+            return;
+        }
+
         self.config.width = new_size.width;
         self.config.height = new_size.height;
-        self.surface.configure(&self.device, &self.config);
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
+        }
+        if self.offscreen.is_some() {
+            self.offscreen = Some(Self::create_offscreen_texture(&self.device, &self.config));
+        }
+    }
+
+    /// Reconfigures the surface with the current configuration, this is used
+    /// to recover after the surface has been lost or become outdated
+    pub fn reconfigure(&self) {
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
+        }
     }
 
     /// Get a reference to the device
@@ -128,14 +259,19 @@ impl RenderState {
         &mut self.queue
     }
 
-    /// Get a reference to the surface
-    pub fn get_surface(&self) -> &wgpu::Surface {
-        &self.surface
+    /// Get a reference to the surface, None if rendering offscreen
+    pub fn get_surface(&self) -> Option<&wgpu::Surface<'static>> {
+        self.surface.as_ref()
+    }
+
+    /// Get a mutable reference to the surface, None if rendering offscreen
+    pub fn get_surface_mut(&mut self) -> Option<&mut wgpu::Surface<'static>> {
+        self.surface.as_mut()
     }
 
-    /// Get a mutable reference to the surface
-    pub fn get_surface_mut(&mut self) -> &'static mut wgpu::Surface {
-        &mut self.surface
+    /// Get a reference to the texture used for offscreen rendering, None if rendering to a surface
+    pub fn get_offscreen_texture(&self) -> Option<&wgpu::Texture> {
+        self.offscreen.as_ref()
     }
 
     /// Get a reference to the configs
@@ -147,6 +283,166 @@ impl RenderState {
     pub fn get_config_mut(&mut self) -> &mut wgpu::SurfaceConfiguration {
         &mut self.config
     }
+
+    /// Get the size of the surface or offscreen texture in pixels
+    pub fn get_surface_size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.config.width, self.config.height)
+    }
+
+    /// Get the ratio of the width to the height of the surface
+    pub fn get_aspect_ratio(&self) -> f64 {
+        self.config.width as f64 / self.config.height as f64
+    }
+
+    /// Creates a set of timestamp queries for measuring the time the gpu
+    /// spends on render passes, returns None if the device does not support
+    /// timestamp queries
+    ///
+    /// # Parameters
+    ///
+    /// capacity: The number of timestamps in the set
+    pub fn create_timestamp_query_set(&self, capacity: u32) -> Option<TimestampQuerySet> {
+        if !self
+            .device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+        {
+            return None;
+        }
+
+        let query_set = self.device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Timestamp Query Set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: capacity,
+        });
+        let size = capacity as u64 * wgpu::QUERY_SIZE as u64;
+        let resolve_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Resolve Buffer"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let read_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Read Buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Some(TimestampQuerySet {
+            query_set,
+            resolve_buffer,
+            read_buffer,
+            capacity,
+        })
+    }
+}
+
+/// A set of gpu timestamps with the buffers needed to read them back
+pub struct TimestampQuerySet {
+    /// The queries the timestamps are written to
+    query_set: wgpu::QuerySet,
+    /// The buffer the queries are resolved into
+    resolve_buffer: wgpu::Buffer,
+    /// The buffer the resolved timestamps are copied to for reading on the cpu
+    read_buffer: wgpu::Buffer,
+    /// The number of timestamps in the set
+    capacity: u32,
+}
+
+impl TimestampQuerySet {
+    /// Retrieves the timestamp writes for a render pass such that a
+    /// timestamp is written when the pass begins and when it ends
+    ///
+    /// # Parameters
+    ///
+    /// begin_idx: The index of the timestamp written at the start of the pass
+    ///
+    /// end_idx: The index of the timestamp written at the end of the pass
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if either index is outside the set
+    pub fn wrap_pass(&self, begin_idx: u32, end_idx: u32) -> wgpu::RenderPassTimestampWrites<'_> {
+        if cfg!(debug_assertions) && (begin_idx >= self.capacity || end_idx >= self.capacity) {
+            panic!(
+                "The timestamp indices {:?} and {:?} must be below {:?}",
+                begin_idx, end_idx, self.capacity
+            );
+        }
+
+        wgpu::RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(begin_idx),
+            end_of_pass_write_index: Some(end_idx),
+        }
+    }
+
+    /// Resolves all timestamps such that they can be read, this must be run
+    /// after all passes have been submitted and before reading
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state the passes were submitted to
+    pub fn resolve(&self, render_state: &RenderState) {
+        let mut encoder =
+            render_state
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Command Encoder Timestamps"),
+                });
+        encoder.resolve_query_set(&self.query_set, 0..self.capacity, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.read_buffer,
+            0,
+            self.read_buffer.size(),
+        );
+        render_state.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Reads back the time between two timestamps in microseconds, it blocks
+    /// until the gpu has finished, returns None if the timestamps could not
+    /// be read or the end is before the beginning
+    ///
+    /// # Parameters
+    ///
+    /// render_state: The render state the passes were submitted to
+    ///
+    /// begin_idx: The index of the first timestamp
+    ///
+    /// end_idx: The index of the last timestamp
+    pub fn read_elapsed_us(
+        &self,
+        render_state: &RenderState,
+        begin_idx: u32,
+        end_idx: u32,
+    ) -> Option<u64> {
+        if begin_idx >= self.capacity || end_idx >= self.capacity {
+            return None;
+        }
+
+        // Wait for the buffer to be readable
+        let slice = self.read_buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        render_state.device.poll(wgpu::Maintain::Wait);
+        receiver.recv().ok()?.ok()?;
+
+        // Get the ticks and convert them to microseconds
+        let ticks = {
+            let data = slice.get_mapped_range();
+            let timestamps: &[u64] = bytemuck::cast_slice(&data);
+            timestamps[end_idx as usize].checked_sub(timestamps[begin_idx as usize])
+        };
+        self.read_buffer.unmap();
+        let period = render_state.queue.get_timestamp_period() as f64;
+
+        ticks.map(|ticks| (ticks as f64 * period / 1000.0) as u64)
+    }
 }
 
 /// The error types for when creating a new RenderState
@@ -0,0 +1,25 @@
+// Type definitions
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) value: f32,
+};
+
+// Vertex shader, each vertex is a single pixel already in screen space
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) value: f32,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.value = value;
+    return out;
+}
+
+// Fragment shader
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.value, 0.0, 0.0, 1.0);
+}
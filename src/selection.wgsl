@@ -0,0 +1,27 @@
+// Uniforms
+// The color to draw with
+@group(0) @binding(0)
+var<uniform> color: vec4<f32>;
+
+// Type definitions
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+// Vertex shader, the positions are already in screen space
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    return out;
+}
+
+// Fragment shader
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return color;
+}
@@ -3,27 +3,44 @@
 @group(0) @binding(0)
 var<uniform> transform: mat2x2<f32>;
 
-// The draw mode, 0: fill mode, 1: edge mode
+// The width of the border as a fraction of the distance from the center to the edge
 @group(0) @binding(1)
-var<uniform> draw_mode: u32;
+var<uniform> border_width: f32;
 
 // The color to use for drawing edges
 @group(0) @binding(2)
 var<uniform> edge_color: vec4<f32>;
 
+// The center of the coordinate system which is subtracted before transforming
+@group(0) @binding(3)
+var<uniform> offset: vec2<f32>;
+
+// The factor to scale each hexagon by around its center at a zoom of 1, the gap it leaves is kept constant on the screen
+@group(0) @binding(4)
+var<uniform> tile_scale: f32;
+
 // Type definitions
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) hex_offset: vec2<f32>,
 };
 
 // Vertex shader
 @vertex
 fn vs_main(
     @location(0) hex_offset: vec2<f32>,
+    @location(1) center: vec2<f32>,
+    @location(2) color: vec4<f32>,
 ) -> VertexOutput {
     var out: VertexOutput;
-    var pos = transform * hex_offset;
+    // The zoom is the length of a world unit along the y-axis of the screen which is not affected by the aspect ratio
+    var zoom = length(vec2<f32>(transform[0].y, transform[1].y));
+    var scale = max(1.0 - (1.0 - tile_scale) / zoom, 0.0);
+    var pos = transform * (hex_offset * scale + center - offset);
     out.clip_position = vec4<f32>(pos, 0.0, 1.0);
+    out.color = color;
+    out.hex_offset = hex_offset;
     return out;
 }
 
@@ -32,9 +49,11 @@ fn vs_main(
 fn fs_main(
     in: VertexOutput
 ) -> @location(0) vec4<f32> {
-    if draw_mode == 0u {
-        return vec4<f32>(1.0, 0.0, 0.0, 1.0);
-    } else {
+    // The distance from the center to the edge is 0.5, find the relative distance to the closest edge
+    var local = abs(in.hex_offset);
+    var distance = max(local.y, 0.5 * sqrt(3.0) * local.x + 0.5 * local.y);
+    if 1.0 - 2.0 * distance < border_width {
         return edge_color;
     }
-}
\ No newline at end of file
+    return in.color;
+}
@@ -0,0 +1,725 @@
+use crate::{
+    constants::{FRAMERATE, INV_SQRT_3, ORGANISM_MOVEMENT_COST, ORGANISM_UPKEEP, SQRT_3},
+    map::{Map, ResourceGradient, Resources},
+    types,
+};
+use rand::Rng;
+use std::{
+    cell::OnceCell,
+    collections::{HashMap, VecDeque},
+    f64::consts::PI,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// All parameters controlling the diffusion of resources between tiles
+#[derive(Clone, Copy, Debug)]
+pub struct DiffusionParams {
+    /// The diffusion rate between neighbouring tiles
+    pub rate: f64,
+    /// The time step to advance each tick
+    pub dt: f64,
+    /// True if the diffusion should run
+    pub enabled: bool,
+}
+
+impl Default for DiffusionParams {
+    /// Creates diffusion parameters with default values
+    fn default() -> Self {
+        return Self {
+            rate: 0.1,
+            dt: 1.0 / FRAMERATE,
+            enabled: true,
+        };
+    }
+}
+
+impl DiffusionParams {
+    /// Changes the diffusion rate and returns the updated object
+    ///
+    /// # Parameters
+    ///
+    /// rate: The new diffusion rate
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        return self;
+    }
+
+    /// Changes the time step and returns the updated object
+    ///
+    /// # Parameters
+    ///
+    /// dt: The new time step
+    pub fn with_dt(mut self, dt: f64) -> Self {
+        self.dt = dt;
+        return self;
+    }
+
+    /// Changes whether the diffusion is enabled and returns the updated object
+    ///
+    /// # Parameters
+    ///
+    /// enabled: True if the diffusion should run
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        return self;
+    }
+}
+
+/// The heritable genes of an organism, the genes are unbounded and are
+/// expressed as traits through a sigmoid
+#[derive(Clone, Debug)]
+pub struct Genome {
+    /// The value of each gene, missing genes are expressed as if they were 0
+    pub genes: Vec<f64>,
+    /// The standard deviation of the noise added to each gene when mutating
+    pub mutation_rate: f64,
+}
+
+impl Genome {
+    /// The number of genes used when expressing the traits
+    pub const GENE_COUNT: usize = 5;
+
+    /// Creates a new genome
+    ///
+    /// # Parameters
+    ///
+    /// genes: The value of each gene
+    ///
+    /// mutation_rate: The standard deviation of the noise added to each gene when mutating
+    pub fn new(genes: Vec<f64>, mutation_rate: f64) -> Self {
+        return Self {
+            genes,
+            mutation_rate,
+        };
+    }
+
+    /// Adds normally distributed noise with a standard deviation of the
+    /// mutation rate to each gene
+    ///
+    /// # Parameters
+    ///
+    /// rng: The random number generator to use
+    pub fn mutate(&mut self, rng: &mut impl Rng) {
+        let mutation_rate = self.mutation_rate;
+        self.genes.iter_mut().for_each(|gene| {
+            // Draw a standard normal value using the Box-Muller transform
+            let radius = (-2.0 * (1.0 - rng.gen::<f64>()).ln()).sqrt();
+            let angle = 2.0 * PI * rng.gen::<f64>();
+            *gene += mutation_rate * radius * angle.cos();
+        });
+    }
+
+    /// Creates a child genome using single-point crossover, the genes before
+    /// a random point are taken from this genome and the rest from the other
+    /// genome, the mutation rate is taken from this genome
+    ///
+    /// # Parameters
+    ///
+    /// other: The genome to cross with
+    ///
+    /// rng: The random number generator to use
+    pub fn crossover(&self, other: &Genome, rng: &mut impl Rng) -> Genome {
+        let point = rng.gen_range(0..=self.genes.len().min(other.genes.len()));
+        let genes = self.genes[..point]
+            .iter()
+            .chain(other.genes[point..].iter())
+            .copied()
+            .collect();
+
+        return Genome::new(genes, self.mutation_rate);
+    }
+
+    /// Calculates the traits of the organism by scaling each gene with a
+    /// sigmoid into the range of its trait
+    pub fn express(&self) -> OrganismTraits {
+        let express_gene = |id: usize, min: f64, max: f64| {
+            let gene = self.genes.get(id).copied().unwrap_or(0.0);
+            return min + (max - min) / (1.0 + (-gene).exp());
+        };
+
+        return OrganismTraits {
+            speed: express_gene(0, 0.0, 2.0),
+            consumption_nutrients: express_gene(1, 0.0, 0.2),
+            consumption_energy: express_gene(2, 0.0, 0.2),
+            consumption_water: express_gene(3, 0.0, 0.2),
+            reproduction_threshold: express_gene(4, 0.5, 5.0),
+        };
+    }
+}
+
+/// The traits of an organism as expressed by its genome
+#[derive(Clone, Copy, Debug)]
+pub struct OrganismTraits {
+    /// The distance the organism moves per unit time
+    pub speed: f64,
+    /// The amount of nutrients the organism consumes from its tile per unit time
+    pub consumption_nutrients: f64,
+    /// The amount of energy the organism consumes from its tile per unit time
+    pub consumption_energy: f64,
+    /// The amount of water the organism consumes from its tile per unit time
+    pub consumption_water: f64,
+    /// The amount of each resource the organism must store before it can reproduce
+    pub reproduction_threshold: f64,
+}
+
+/// A single organism living on the map
+#[derive(Clone, Debug)]
+pub struct Organism {
+    /// The unique id of the organism
+    pub id: u64,
+    /// The position of the organism in world coordinates
+    pub position: types::Point,
+    /// The stored energy
+    pub energy: f64,
+    /// The stored nutrients
+    pub nutrients: f64,
+    /// The stored water
+    pub water: f64,
+    /// The genes of the organism
+    pub genome: Genome,
+    /// The traits expressed by the genome
+    pub traits: OrganismTraits,
+}
+
+impl Organism {
+    /// Checks if the organism is still alive, it dies when it runs out of any resource
+    pub fn is_alive(&self) -> bool {
+        return self.energy > 0.0 && self.nutrients > 0.0 && self.water > 0.0;
+    }
+
+    /// Advances the organism by consuming resources from the tile it is
+    /// standing on, paying the upkeep and moving along the resource gradient,
+    /// returns true if the organism is still alive afterwards
+    ///
+    /// # Parameters
+    ///
+    /// map: The map the organism lives on
+    ///
+    /// gradient: The gradient of the resources of the map at the start of the tick
+    ///
+    /// dt: The time step to advance
+    pub fn step(&mut self, map: &mut Map, gradient: &ResourceGradient, dt: f64) -> bool {
+        // The organism dies if it leaves the map
+        let index = self.position.to_tile_index();
+        let tile_id = match map.get_tile_id(&index) {
+            Some(value) => value,
+            None => {
+                self.energy = 0.0;
+                return false;
+            }
+        };
+
+        // Find the direction with the most resources
+        let direction =
+            gradient.nutrients[tile_id] + gradient.energy[tile_id] + gradient.water[tile_id];
+
+        // Consume resources from the tile
+        let tile = map.get_tile_mut(&index).expect("Should not happen");
+        let consumed = Resources::new(
+            (self.traits.consumption_nutrients * dt).min(tile.current_resources.nutrients.max(0.0)),
+            (self.traits.consumption_energy * dt).min(tile.current_resources.energy.max(0.0)),
+            (self.traits.consumption_water * dt).min(tile.current_resources.water.max(0.0)),
+        );
+        tile.current_resources = tile.current_resources - consumed;
+
+        // Pay the upkeep of staying alive and moving
+        let upkeep = (ORGANISM_UPKEEP + ORGANISM_MOVEMENT_COST * self.traits.speed) * dt;
+        self.nutrients += consumed.nutrients - upkeep;
+        self.energy += consumed.energy - upkeep;
+        self.water += consumed.water - upkeep;
+        if !self.is_alive() {
+            return false;
+        }
+
+        // Move along the gradient
+        let length = direction.norm();
+        if length > 0.0 {
+            self.position = self.position + direction * (self.traits.speed * dt / length);
+        }
+
+        return true;
+    }
+}
+
+/// A collection of organisms which are advanced together
+#[derive(Clone, Debug)]
+pub struct OrganismPool {
+    /// All living organisms
+    organisms: Vec<Organism>,
+    /// The id to give the next organism
+    next_id: u64,
+    /// The number of organisms spawned since the statistics were last collected
+    births: u32,
+    /// The number of organisms which died since the statistics were last collected
+    deaths: u32,
+    /// The positions of the current organisms, it is built when first needed
+    /// after the organisms have changed
+    spatial_index: OnceCell<SpatialIndex>,
+}
+
+impl Default for OrganismPool {
+    /// Creates a new empty pool
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl OrganismPool {
+    /// Creates a new empty pool
+    pub fn new() -> Self {
+        return Self {
+            organisms: Vec::new(),
+            next_id: 0,
+            births: 0,
+            deaths: 0,
+            spatial_index: OnceCell::new(),
+        };
+    }
+
+    /// Adds a newborn organism to the pool and returns its id
+    ///
+    /// # Parameters
+    ///
+    /// position: The position to place the organism at in world coordinates
+    ///
+    /// resources: The resources the organism starts with
+    ///
+    /// genome: The genes of the organism
+    pub fn spawn(&mut self, position: types::Point, resources: Resources, genome: Genome) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.births += 1;
+        self.spatial_index.take();
+        self.organisms.push(Organism {
+            id,
+            position,
+            energy: resources.energy,
+            nutrients: resources.nutrients,
+            water: resources.water,
+            traits: genome.express(),
+            genome,
+        });
+
+        return id;
+    }
+
+    /// Retrieves all living organisms
+    pub fn get_organisms(&self) -> &[Organism] {
+        return &self.organisms;
+    }
+
+    /// Retrieves the spatial index of the organisms, the indices refer to
+    /// get_organisms, it is rebuilt if the organisms have changed since it
+    /// was last built
+    pub fn spatial_index(&self) -> &SpatialIndex {
+        return self.spatial_index.get_or_init(|| {
+            let positions = self
+                .organisms
+                .iter()
+                .map(|organism| return organism.position)
+                .collect::<Vec<types::Point>>();
+            return SpatialIndex::new(&positions);
+        });
+    }
+
+    /// Advances all organisms and removes the ones which have died, the
+    /// resource gradient is only calculated once for all organisms
+    ///
+    /// # Parameters
+    ///
+    /// map: The map the organisms live on
+    ///
+    /// dt: The time step to advance
+    pub fn step_all(&mut self, map: &mut Map, dt: f64) {
+        self.spatial_index.take();
+
+        let gradient = map.compute_gradient().clone();
+        let count = self.organisms.len();
+        self.organisms
+            .retain_mut(|organism| return organism.step(map, &gradient, dt));
+        self.deaths += (count - self.organisms.len()) as u32;
+    }
+
+    /// Collects the statistics of the current state of the pool and resets
+    /// the count of births and deaths
+    ///
+    /// # Parameters
+    ///
+    /// tick: The simulation tick the statistics are collected at
+    pub fn collect_statistics(&mut self, tick: u64) -> Statistics {
+        // Find the mean stored resources
+        let total = self
+            .organisms
+            .iter()
+            .fold(Resources::new(0.0, 0.0, 0.0), |total, organism| {
+                return total + Resources::new(organism.nutrients, organism.energy, organism.water);
+            });
+        let mean_resources = if self.organisms.is_empty() {
+            total
+        } else {
+            total * (1.0 / self.organisms.len() as f64)
+        };
+
+        let statistics = Statistics {
+            tick,
+            organism_count: self.organisms.len(),
+            mean_resources,
+            births: self.births,
+            deaths: self.deaths,
+        };
+        self.births = 0;
+        self.deaths = 0;
+
+        return statistics;
+    }
+}
+
+/// A lookup of points by position where the points are grouped into coarse
+/// hexagonal cells
+#[derive(Clone, Debug)]
+pub struct SpatialIndex {
+    /// The indices of the points within each cell, the key is the tile index
+    /// of the cell on a hex grid scaled by CELL_SIZE
+    cells: HashMap<types::Index, Vec<usize>>,
+    /// The position of each point
+    positions: Vec<types::Point>,
+}
+
+impl SpatialIndex {
+    /// The distance between the centers of neighbouring cells in world units
+    pub const CELL_SIZE: f64 = 4.0;
+
+    /// Creates a new index of the given points
+    ///
+    /// # Parameters
+    ///
+    /// positions: The positions of the points, the index of a position is
+    /// returned when querying
+    pub fn new(positions: &[types::Point]) -> Self {
+        let mut cells: HashMap<types::Index, Vec<usize>> = HashMap::new();
+        positions.iter().enumerate().for_each(|(id, position)| {
+            cells.entry(Self::get_cell(position)).or_default().push(id);
+        });
+
+        return Self {
+            cells,
+            positions: positions.to_vec(),
+        };
+    }
+
+    /// Finds the indices of all points within the given distance of the
+    /// center, the indices are in no particular order
+    ///
+    /// # Parameters
+    ///
+    /// center: The center to search around
+    ///
+    /// radius: The largest distance from the center
+    pub fn query_radius(&self, center: &types::Point, radius: f64) -> Vec<usize> {
+        // Cells at a hex distance of n have centers at least n sqrt(3) / 2
+        // apart and a point is at most INV_SQRT_3 from the center of its cell
+        let range =
+            ((radius.max(0.0) / Self::CELL_SIZE + 2.0 * INV_SQRT_3) * 2.0 / SQRT_3).ceil() as i64;
+        let center_cell = Self::get_cell(center);
+
+        // The axial coordinates are at most the hex distance so search a square
+        return (-range..range + 1)
+            .flat_map(|x| {
+                return (-range..range + 1)
+                    .map(move |y| return center_cell + types::Index::new(x, y));
+            })
+            .filter_map(|cell| return self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|id| return self.positions[*id].distance_to(center) <= radius)
+            .collect();
+    }
+
+    /// Finds the cell containing the given position
+    ///
+    /// # Parameters
+    ///
+    /// position: The position to find the cell for
+    fn get_cell(position: &types::Point) -> types::Index {
+        return (*position * (1.0 / Self::CELL_SIZE)).to_tile_index();
+    }
+}
+
+/// The health of the simulation at a single tick
+#[derive(Clone, Copy, Debug)]
+pub struct Statistics {
+    /// The simulation tick the statistics were collected at
+    pub tick: u64,
+    /// The number of living organisms
+    pub organism_count: usize,
+    /// The mean resources stored by the living organisms
+    pub mean_resources: Resources,
+    /// The number of organisms spawned since the previous statistics
+    pub births: u32,
+    /// The number of organisms which died since the previous statistics
+    pub deaths: u32,
+}
+
+impl Statistics {
+    /// The header line of a csv file with statistics
+    pub const CSV_HEADER: &'static str =
+        "tick,organism_count,mean_nutrients,mean_energy,mean_water,births,deaths";
+
+    /// Writes the statistics as a single line of a csv file
+    ///
+    /// # Parameters
+    ///
+    /// writer: The writer to write the line to
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the line could not be written
+    pub fn write_csv_row(&self, writer: &mut impl Write) -> io::Result<()> {
+        return writeln!(
+            writer,
+            "{},{},{},{},{},{},{}",
+            self.tick,
+            self.organism_count,
+            self.mean_resources.nutrients,
+            self.mean_resources.energy,
+            self.mean_resources.water,
+            self.births,
+            self.deaths,
+        );
+    }
+}
+
+/// A ring buffer of the latest statistics, once it is full the oldest
+/// statistics are dropped
+#[derive(Clone, Debug)]
+pub struct StatisticsBuffer {
+    /// The statistics from oldest to newest
+    data: VecDeque<Statistics>,
+    /// The maximum number of statistics to keep
+    capacity: usize,
+}
+
+impl StatisticsBuffer {
+    /// Creates a new empty buffer
+    ///
+    /// # Parameters
+    ///
+    /// capacity: The maximum number of statistics to keep
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if the capacity is 0
+    pub fn new(capacity: usize) -> Self {
+        if cfg!(debug_assertions) && capacity == 0 {
+            panic!("The capacity must be at least 1");
+        }
+
+        return Self {
+            data: VecDeque::with_capacity(capacity),
+            capacity,
+        };
+    }
+
+    /// Retrieves all stored statistics from oldest to newest
+    pub fn get_data(&self) -> &VecDeque<Statistics> {
+        return &self.data;
+    }
+
+    /// Adds new statistics, the oldest statistics are dropped if the buffer is full
+    ///
+    /// # Parameters
+    ///
+    /// stats: The statistics to add
+    pub fn push(&mut self, stats: Statistics) {
+        if self.data.len() >= self.capacity {
+            self.data.pop_front();
+        }
+        self.data.push_back(stats);
+    }
+
+    /// Averages the last n statistics, the tick is that of the newest
+    /// statistics and the counts are rounded to the nearest integer, if the
+    /// buffer is empty all values are 0
+    ///
+    /// # Parameters
+    ///
+    /// n: The number of statistics to average over, if there are fewer then
+    /// all statistics are used
+    pub fn average_over_last_n(&self, n: usize) -> Statistics {
+        let count = n.min(self.data.len());
+        let tick = self.data.back().map_or(0, |stats| return stats.tick);
+        if count == 0 {
+            return Statistics {
+                tick,
+                organism_count: 0,
+                mean_resources: Resources::new(0.0, 0.0, 0.0),
+                births: 0,
+                deaths: 0,
+            };
+        }
+
+        // Sum up all values
+        let (organism_count, mean_resources, births, deaths) =
+            self.data.iter().skip(self.data.len() - count).fold(
+                (0.0, Resources::new(0.0, 0.0, 0.0), 0.0, 0.0),
+                |(organism_count, mean_resources, births, deaths), stats| {
+                    return (
+                        organism_count + stats.organism_count as f64,
+                        mean_resources + stats.mean_resources,
+                        births + stats.births as f64,
+                        deaths + stats.deaths as f64,
+                    );
+                },
+            );
+
+        let scale = 1.0 / count as f64;
+        return Statistics {
+            tick,
+            organism_count: (organism_count * scale).round() as usize,
+            mean_resources: mean_resources * scale,
+            births: (births * scale).round() as u32,
+            deaths: (deaths * scale).round() as u32,
+        };
+    }
+
+    /// Writes all stored statistics to a csv file with a header line
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the file to write
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be written
+    pub fn export_csv(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", Statistics::CSV_HEADER)?;
+        for stats in self.data.iter() {
+            stats.write_csv_row(&mut writer)?;
+        }
+        writer.flush()?;
+
+        return Ok(());
+    }
+}
+
+/// A csv file the statistics are appended to as they are collected, the
+/// rows are flushed to the file periodically
+#[derive(Debug)]
+pub struct StatisticsLog {
+    /// The writer for the csv file
+    writer: BufWriter<File>,
+    /// The number of rows written since the last flush
+    unflushed: u32,
+}
+
+impl StatisticsLog {
+    /// The number of rows to write before flushing them to the file
+    pub const FLUSH_INTERVAL: u32 = 60;
+
+    /// Creates a new csv file with a header line, an existing file is overwritten
+    ///
+    /// # Parameters
+    ///
+    /// path: The path of the file to write
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be created
+    pub fn create(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", Statistics::CSV_HEADER)?;
+        writer.flush()?;
+
+        return Ok(Self {
+            writer,
+            unflushed: 0,
+        });
+    }
+
+    /// Appends the statistics as a new row, the file is flushed every
+    /// FLUSH_INTERVAL rows
+    ///
+    /// # Parameters
+    ///
+    /// stats: The statistics to append
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the row could not be written
+    pub fn append(&mut self, stats: &Statistics) -> io::Result<()> {
+        stats.write_csv_row(&mut self.writer)?;
+        self.unflushed += 1;
+        if self.unflushed >= Self::FLUSH_INTERVAL {
+            self.flush()?;
+        }
+
+        return Ok(());
+    }
+
+    /// Writes all appended rows to the file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rows could not be written
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        self.unflushed = 0;
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{MapBuilder, MapLayout, SourceMap};
+
+    #[test]
+    fn spatial_index_follows_deaths_and_births() {
+        let mut map = MapBuilder::new(SourceMap::default())
+            .with_layout(MapLayout::Finite(2, 2))
+            .build();
+        let mut pool = OrganismPool::new();
+        let resources = Resources::new(1.0, 1.0, 1.0);
+        let genome = Genome::new(vec![0.0; Genome::GENE_COUNT], 0.0);
+
+        // The organism outside of the map dies in the first step
+        pool.spawn(types::Point::new(0.0, 0.0), resources, genome.clone());
+        pool.spawn(types::Point::new(1000.0, 0.0), resources, genome.clone());
+        pool.spawn(types::Point::new(1.0, 0.0), resources, genome.clone());
+        assert_eq!(
+            pool.spatial_index()
+                .query_radius(&types::Point::new(0.0, 0.0), 2.0)
+                .len(),
+            2
+        );
+
+        pool.step_all(&mut map, 0.1);
+        pool.spawn(types::Point::new(0.0, 1.0), resources, genome);
+
+        // The indices refer to the organisms after the step and the birth
+        let center = types::Point::new(0.0, 0.0);
+        let mut found = pool.spatial_index().query_radius(&center, 2.0);
+        found.sort();
+        let expected = pool
+            .get_organisms()
+            .iter()
+            .enumerate()
+            .filter(|(_, organism)| return organism.position.distance_to(&center) <= 2.0)
+            .map(|(id, _)| return id)
+            .collect::<Vec<usize>>();
+        assert_eq!(pool.get_organisms().len(), 3);
+        assert_eq!(found, expected);
+    }
+}
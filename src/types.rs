@@ -1,10 +1,16 @@
+use crate::{
+    constants::{INV_SQRT_3, SQRT_3},
+    map,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::{
-    f64::consts::PI,
+    f64::consts::{E, PI},
     ops::{Add, Div, Mul, Neg, Sub},
 };
 
 /// A 2D point
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Point {
     /// The x-coordinate
     pub x: f64,
@@ -34,6 +40,109 @@ impl Point {
         return self.norm_squared().sqrt();
     }
 
+    /// Calculates the distance to another point
+    ///
+    /// # Parameters
+    ///
+    /// other: The point to calculate the distance to
+    pub fn distance_to(&self, other: &Point) -> f64 {
+        return (*self - *other).norm();
+    }
+
+    /// Constrains the point to the rectangle spanned by min and max, the x
+    /// and y coordinates are clamped independently
+    ///
+    /// # Parameters
+    ///
+    /// min: The lower left corner of the rectangle
+    ///
+    /// max: The upper right corner of the rectangle
+    pub fn clamp(&self, min: &Point, max: &Point) -> Point {
+        return Point::new(self.x.clamp(min.x, max.x), self.y.clamp(min.y, max.y));
+    }
+
+    /// Linearly interpolates between this point and another point, t=0 gives
+    /// this point and t=1 gives the other point
+    ///
+    /// # Parameters
+    ///
+    /// other: The point to interpolate towards
+    ///
+    /// t: The interpolation parameter
+    pub fn lerp(&self, other: &Point, t: f64) -> Point {
+        return *self + (*other - *self) * t;
+    }
+
+    /// Finds the point on the segment from a to b which is closest to this
+    /// point, if a and b coincide then a is returned
+    ///
+    /// # Parameters
+    ///
+    /// a: The start of the segment
+    ///
+    /// b: The end of the segment
+    pub fn project_onto_segment(&self, a: &Point, b: &Point) -> Point {
+        let direction = *b - *a;
+        let length_squared = direction.norm_squared();
+        if length_squared <= 0.0 {
+            return *a;
+        }
+
+        let t = ((*self - *a) * direction / length_squared).clamp(0.0, 1.0);
+        return a.lerp(b, t);
+    }
+
+    /// Calculates the distance to the closest point on the segment from a to b
+    ///
+    /// # Parameters
+    ///
+    /// a: The start of the segment
+    ///
+    /// b: The end of the segment
+    pub fn distance_to_segment(&self, a: &Point, b: &Point) -> f64 {
+        return self.distance_to(&self.project_onto_segment(a, b));
+    }
+
+    /// Rotates the point 90 degrees counterclockwise around the origin
+    pub fn perpendicular(&self) -> Point {
+        return Point::new(-self.y, self.x);
+    }
+
+    /// Calculates the z-component of the cross product of the two points
+    /// extended to 3D, it is positive if other is counterclockwise from this
+    /// point
+    ///
+    /// # Parameters
+    ///
+    /// other: The point to take the cross product with
+    pub fn cross_2d(&self, other: &Point) -> f64 {
+        return self.x * other.y - self.y * other.x;
+    }
+
+    /// Checks if the point is strictly to the left of the line going from a
+    /// to b, points on the line are not to the left
+    ///
+    /// # Parameters
+    ///
+    /// a: The start of the line
+    ///
+    /// b: The end of the line
+    pub fn is_left_of_line(&self, a: &Point, b: &Point) -> bool {
+        return (*b - *a).cross_2d(&(*self - *a)) > 0.0;
+    }
+
+    /// Checks if the two points are equal within the tolerance, each
+    /// coordinate is compared separately
+    ///
+    /// # Parameters
+    ///
+    /// other: The point to compare with
+    ///
+    /// eps: The largest allowed difference of each coordinate
+    pub fn approx_eq(&self, other: &Point, eps: f64) -> bool {
+        return (self.x - other.x).abs() <= eps && (self.y - other.y).abs() <= eps;
+    }
+
     /// Retrieves the data for the gpu
     pub fn get_data(&self) -> [f32; 2] {
         return [self.x as f32, self.y as f32];
@@ -43,6 +152,105 @@ impl Point {
     pub fn to_size(&self) -> Size {
         return Size::new(self.x, self.y);
     }
+
+    /// Moves the point to the center of the tile it is within
+    pub fn snap_to_hex_grid(&self) -> Point {
+        return self.to_tile_index().to_tile_center();
+    }
+
+    /// Moves the point to the center of the chunk it is within for the
+    /// default chunk size
+    pub fn snap_to_chunk_grid(&self) -> Point {
+        return map::chunk_to_coordinate(&map::coordinate_to_chunk(self));
+    }
+
+    /// Moves the point to the nearest point where both coordinates are
+    /// multiples of the spacing
+    ///
+    /// # Parameters
+    ///
+    /// spacing: The distance between grid points
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if the spacing is not positive
+    pub fn snap_to_cartesian_grid(&self, spacing: f64) -> Point {
+        if cfg!(debug_assertions) && spacing <= 0.0 {
+            panic!("The spacing must be positive: {:?}", spacing);
+        }
+
+        return Point::new(
+            (self.x / spacing).round() * spacing,
+            (self.y / spacing).round() * spacing,
+        );
+    }
+
+    /// Calculates what tile this cartesian coordinate is within and returns its tile index,
+    /// the direction for positive x tiles is up-left and the direction for positive y tiles is up
+    pub fn to_tile_index(&self) -> Index {
+        // Calculate the skewed coordinates and index
+        let skew_point = Point::new(0.5 * (SQRT_3 * self.x - self.y), self.y);
+        let skew_index = Index::new(
+            (skew_point.x + 0.5).floor() as i64,
+            (skew_point.y + 0.5).floor() as i64,
+        );
+
+        // Figure out what strip of locations this index is part of
+        let strip = (skew_index.y - skew_index.x) % 3;
+        let strip = if strip < 0 { strip + 3 } else { strip };
+
+        // Get the relative coordinate
+        let rel_point = skew_point - Point::new(skew_index.x as f64, skew_index.y as f64);
+
+        // Find the correct tile
+        match strip {
+            0 => {
+                let main_index = Index::new(
+                    -(4 * skew_index.x + 2 * skew_index.y) / 3,
+                    (2 * skew_index.x + 4 * skew_index.y) / 3,
+                );
+
+                main_index
+                    + if rel_point.x + rel_point.y < -0.5 {
+                        Index::new(1, -1)
+                    } else if rel_point.x + rel_point.y > 0.5 {
+                        Index::new(-1, 1)
+                    } else {
+                        Index::new(0, 0)
+                    }
+            }
+            1 => {
+                let main_index = Index::new(
+                    -(4 * skew_index.x + 2 * skew_index.y + 1) / 3,
+                    (2 * skew_index.x + 4 * skew_index.y - 1) / 3,
+                );
+
+                main_index
+                    + if rel_point.x > 0.0 && rel_point.y < 0.0 {
+                        Index::new(0, 0)
+                    } else if rel_point.x + rel_point.y < 0.0 {
+                        Index::new(1, 0)
+                    } else {
+                        Index::new(0, 1)
+                    }
+            }
+            _ => {
+                let main_index = Index::new(
+                    -(4 * skew_index.x + 2 * skew_index.y - 1) / 3,
+                    (2 * skew_index.x + 4 * skew_index.y + 1) / 3,
+                );
+
+                main_index
+                    + if rel_point.x < 0.0 && rel_point.y > 0.0 {
+                        Index::new(0, 0)
+                    } else if rel_point.x + rel_point.y < 0.0 {
+                        Index::new(0, -1)
+                    } else {
+                        Index::new(-1, 0)
+                    }
+            }
+        }
+    }
 }
 
 impl Neg for Point {
@@ -270,7 +478,7 @@ impl Mul<&Point> for &Point {
 }
 
 /// A 2D size of width and height which are both non-negative
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Size {
     /// The width
     pub w: f64,
@@ -382,8 +590,8 @@ impl Add<&Size> for &Size {
     }
 }
 
-/// A 2D index
-#[derive(Clone, Copy, Debug)]
+/// A 2D index, indices are ordered lexicographically by x and then y
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Index {
     /// The x-index
     pub x: i64,
@@ -402,6 +610,48 @@ impl Index {
     pub fn new(x: i64, y: i64) -> Self {
         return Self { x, y };
     }
+
+    /// Calculates the center cartesian coordinate of the tile with this index,
+    /// the direction for positive x tiles is up-left and the direction for positive y tiles is up
+    pub fn to_tile_center(&self) -> Point {
+        return Point::new(-1.5 * INV_SQRT_3, 0.5) * (self.x as f64)
+            + Point::new(0.0, 1.0) * (self.y as f64);
+    }
+
+    /// Retrieves the tile indices of the six neighbouring hexagonal tiles,
+    /// they are sorted clockwise starting with the upper left neighbour
+    pub fn hex_neighbors(&self) -> [Index; 6] {
+        return [(1, 0), (0, 1), (-1, 1), (-1, 0), (0, -1), (1, -1)]
+            .map(|(x, y)| return Index::new(self.x + x, self.y + y));
+    }
+
+    /// Converts the index to cube coordinates (x, y, z) where x + y + z = 0,
+    /// the x and y axes are the same as for the index
+    pub fn axial_to_cube(&self) -> (i64, i64, i64) {
+        return (self.x, self.y, -self.x - self.y);
+    }
+
+    /// Converts cube coordinates (x, y, z) where x + y + z = 0 to an index,
+    /// this is the inverse of axial_to_cube
+    ///
+    /// # Parameters
+    ///
+    /// x: The x-coordinate
+    ///
+    /// y: The y-coordinate
+    ///
+    /// z: The z-coordinate
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if the coordinates do not sum to 0
+    pub fn cube_to_axial(x: i64, y: i64, z: i64) -> Self {
+        if cfg!(debug_assertions) && x + y + z != 0 {
+            panic!("Cube coordinates must sum to 0: {:?}", (x, y, z));
+        }
+
+        return Self::new(x, y);
+    }
 }
 
 impl Add<Index> for Index {
@@ -472,6 +722,47 @@ impl View {
         };
     }
 
+    /// Creates the smallest axis aligned view containing all the points,
+    /// returns None if there are no points
+    ///
+    /// # Parameters
+    ///
+    /// points: The points to contain
+    pub fn from_points(points: &[Point]) -> Option<Self> {
+        let first = points.first()?;
+        let (min, max) = points.iter().fold((*first, *first), |(min, max), point| {
+            return (
+                Point::new(min.x.min(point.x), min.y.min(point.y)),
+                Point::new(max.x.max(point.x), max.y.max(point.y)),
+            );
+        });
+
+        return Some(Self::new(
+            &((min + max) * 0.5),
+            &Size::new(max.x - min.x, max.y - min.y),
+        ));
+    }
+
+    /// Creates the smallest axis aligned view in world coordinates containing
+    /// everything visible on the screen for the given transform from world
+    /// coordinates to normalized device coordinates
+    ///
+    /// # Parameters
+    ///
+    /// transform: The transform from world coordinates to normalized device coordinates
+    pub fn from_transform(transform: &Transform2D) -> Self {
+        let inverse = transform.inv();
+        let corners = [
+            Point::new(-1.0, -1.0),
+            Point::new(1.0, -1.0),
+            Point::new(1.0, 1.0),
+            Point::new(-1.0, 1.0),
+        ]
+        .map(|corner| return inverse * corner);
+
+        return Self::from_points(&corners).expect("Should not happen");
+    }
+
     /// Retrieves the center
     pub fn get_center(&self) -> &Point {
         return &self.center;
@@ -488,10 +779,93 @@ impl View {
             && self.center.x + self.size.w * 0.5 >= other.center.x + other.size.w * 0.5
             && self.center.y + self.size.h * 0.5 >= other.center.y + other.size.h * 0.5;
     }
+
+    /// Checks if the point is inside the view, points on the border are
+    /// inside
+    ///
+    /// # Parameters
+    ///
+    /// p: The point to check
+    pub fn contains_point(&self, p: &Point) -> bool {
+        return (p.x - self.center.x).abs() <= self.size.w * 0.5
+            && (p.y - self.center.y).abs() <= self.size.h * 0.5;
+    }
+
+    /// Counts the tiles which overlap the view, a tile is counted if its
+    /// center is inside the view expanded by the tile radius on all sides
+    ///
+    /// # Parameters
+    ///
+    /// tile_centers: The centers of the tiles to count
+    ///
+    /// tile_radius: The distance from the center of a tile to its corners
+    pub fn tile_overlap_count(&self, tile_centers: &[Point], tile_radius: f64) -> usize {
+        let expanded = self.expand(tile_radius);
+
+        return tile_centers
+            .iter()
+            .filter(|center| return expanded.contains_point(center))
+            .count();
+    }
+
+    /// Checks if the two views overlap, views which only touch at the border
+    /// do not overlap
+    ///
+    /// # Parameters
+    ///
+    /// other: The view to check against
+    pub fn intersects(&self, other: &View) -> bool {
+        return (self.center.x - other.center.x).abs() < (self.size.w + other.size.w) * 0.5
+            && (self.center.y - other.center.y).abs() < (self.size.h + other.size.h) * 0.5;
+    }
+
+    /// Calculates the overlapping view of the two views, returns None if they
+    /// do not intersect
+    ///
+    /// # Parameters
+    ///
+    /// other: The view to intersect with
+    pub fn intersection(&self, other: &View) -> Option<View> {
+        if !self.intersects(other) {
+            return None;
+        }
+
+        // Find the corners of the overlap
+        let min = Point::new(
+            (self.center.x - self.size.w * 0.5).max(other.center.x - other.size.w * 0.5),
+            (self.center.y - self.size.h * 0.5).max(other.center.y - other.size.h * 0.5),
+        );
+        let max = Point::new(
+            (self.center.x + self.size.w * 0.5).min(other.center.x + other.size.w * 0.5),
+            (self.center.y + self.size.h * 0.5).min(other.center.y + other.size.h * 0.5),
+        );
+
+        return Some(View::new(
+            &((min + max) * 0.5),
+            &Size::new(max.x - min.x, max.y - min.y),
+        ));
+    }
+
+    /// Creates a new view with the same center and a border of the given size
+    /// added on all sides, a negative margin shrinks the view but never below
+    /// a size of 0
+    ///
+    /// # Parameters
+    ///
+    /// margin: The size of the border to add
+    pub fn expand(&self, margin: f64) -> View {
+        return View::new(
+            &self.center,
+            &Size::new(
+                (self.size.w + 2.0 * margin).max(0.0),
+                (self.size.h + 2.0 * margin).max(0.0),
+            ),
+        );
+    }
 }
 
 /// Defines a 2x2 matrix
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Matrix {
     /// The values of the matrix
     pub values: [[f64; 2]; 2],
@@ -507,6 +881,90 @@ impl Matrix {
         return Self { values: *values };
     }
 
+    /// Creates a matrix from its two rows
+    ///
+    /// # Parameters
+    ///
+    /// row0: The first row
+    ///
+    /// row1: The second row
+    pub fn from_rows(row0: [f64; 2], row1: [f64; 2]) -> Self {
+        return Self::new(&[row0, row1]);
+    }
+
+    /// Creates a matrix from its two columns
+    ///
+    /// # Parameters
+    ///
+    /// col0: The first column
+    ///
+    /// col1: The second column
+    pub fn from_cols(col0: [f64; 2], col1: [f64; 2]) -> Self {
+        return Self::new(&[[col0[0], col1[0]], [col0[1], col1[1]]]);
+    }
+
+    /// Creates the identity matrix
+    pub fn identity() -> Self {
+        return Self::new(&[[1.0, 0.0], [0.0, 1.0]]);
+    }
+
+    /// Creates a matrix with all values set to 0
+    pub fn zero() -> Self {
+        return Self::new(&[[0.0, 0.0], [0.0, 0.0]]);
+    }
+
+    /// Creates a matrix rotating counter clockwise by the given angle
+    ///
+    /// # Parameters
+    ///
+    /// angle: The angle to rotate by in radians
+    pub fn rotation(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        return Self::new(&[[cos, -sin], [sin, cos]]);
+    }
+
+    /// Creates a matrix scaling the x- and y-axis independently
+    ///
+    /// # Parameters
+    ///
+    /// sx: The scale of the x-axis
+    ///
+    /// sy: The scale of the y-axis
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        return Self::new(&[[sx, 0.0], [0.0, sy]]);
+    }
+
+    /// Creates the outer product a * b^T of two vectors
+    ///
+    /// # Parameters
+    ///
+    /// a: The vector giving the rows
+    ///
+    /// b: The vector giving the columns
+    pub fn outer_product(a: &Point, b: &Point) -> Self {
+        return Self::new(&[[a.x * b.x, a.x * b.y], [a.y * b.x, a.y * b.y]]);
+    }
+
+    /// Calculates the sample covariance matrix of the points around the
+    /// given mean, returns the zero matrix if there are fewer than 2 points
+    ///
+    /// # Parameters
+    ///
+    /// points: The sample points
+    ///
+    /// mean: The mean of the points
+    pub fn covariance(points: &[Point], mean: &Point) -> Self {
+        if points.len() < 2 {
+            return Self::zero();
+        }
+
+        return points.iter().fold(Self::zero(), |prev, point| {
+            let diff = point - mean;
+            return prev + Self::outer_product(&diff, &diff);
+        }) * (1.0 / (points.len() - 1) as f64);
+    }
+
     /// Transposes the matrix
     pub fn transpose(&self) -> Self {
         return Self::new(&[
@@ -536,11 +994,69 @@ impl Matrix {
         ]);
     }
 
+    /// Raises the matrix to an integer power, negative powers are powers of the inverse
+    ///
+    /// # Parameters
+    ///
+    /// n: The power to raise the matrix to
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if n is negative and the matrix is not invertible
+    pub fn pow(&self, n: i32) -> Self {
+        // Use repeated squaring
+        let mut result = Self::new(&[[1.0, 0.0], [0.0, 1.0]]);
+        let mut base = if n < 0 { self.inv() } else { *self };
+        let mut exponent = n.unsigned_abs();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            exponent >>= 1;
+        }
+
+        return result;
+    }
+
     /// Calculates the determinant
     pub fn det(&self) -> f64 {
         return self.values[0][0] * self.values[1][1] - self.values[0][1] * self.values[1][0];
     }
 
+    /// Calculates the Cholesky decomposition of the matrix, this is the lower
+    /// triangular matrix L such that L * L^T is this matrix, returns None if
+    /// the matrix is not symmetric and positive semi-definite
+    pub fn cholesky(&self) -> Option<Self> {
+        // Make sure it is symmetric
+        if (self.values[0][1] - self.values[1][0]).abs()
+            > 1e-12 * (self.values[0][1].abs() + self.values[1][0].abs())
+        {
+            return None;
+        }
+
+        // Calculate the first column
+        if self.values[0][0] < 0.0 {
+            return None;
+        }
+        let l00 = self.values[0][0].sqrt();
+        let l10 = if l00 > 0.0 {
+            self.values[1][0] / l00
+        } else if self.values[1][0] == 0.0 {
+            0.0
+        } else {
+            return None;
+        };
+
+        // Calculate the last diagonal element
+        let rest = self.values[1][1] - l10 * l10;
+        if rest < 0.0 {
+            return None;
+        }
+
+        return Some(Self::new(&[[l00, 0.0], [l10, rest.sqrt()]]));
+    }
+
     /// Calculates the two eigenvalues sorting them from largest to smallest
     pub fn eigenvalues(&self) -> [f64; 2] {
         let d = (self.values[0][0] + self.values[1][1]) * (self.values[0][0] + self.values[1][1])
@@ -559,6 +1075,66 @@ impl Matrix {
         ];
     }
 
+    /// Calculates the polar decomposition R * S of the matrix where R is
+    /// orthogonal and S is symmetric positive semi-definite, returns (R, S)
+    pub fn polar(&self) -> (Self, Self) {
+        let (u, sigma, vt) = self.svd();
+        let stretch = vt.transpose() * Matrix::scale(sigma[0], sigma[1]) * vt;
+
+        return (u * vt, stretch);
+    }
+
+    /// Calculates the singular value decomposition U * diag(sigma) * Vt of
+    /// the matrix where U and Vt are orthogonal and the singular values are
+    /// non-negative and sorted from largest to smallest, returns (U, sigma, Vt)
+    pub fn svd(&self) -> (Self, [f64; 2], Self) {
+        let [[a, b], [c, d]] = self.values;
+
+        // Split into a rotation and a reflection part
+        let e = 0.5 * (a + d);
+        let f = 0.5 * (a - d);
+        let g = 0.5 * (c + b);
+        let h = 0.5 * (c - b);
+        let q = e.hypot(h);
+        let r = f.hypot(g);
+
+        // Find the angles of the two rotations
+        let angle_reflection = g.atan2(f);
+        let angle_rotation = h.atan2(e);
+        let angle_u = 0.5 * (angle_rotation + angle_reflection);
+        let angle_v = 0.5 * (angle_rotation - angle_reflection);
+        let rotation = |angle: f64| {
+            return Self::new(&[[angle.cos(), -angle.sin()], [angle.sin(), angle.cos()]]);
+        };
+
+        // A negative second singular value is moved into U as a reflection
+        let sigma = [q + r, (q - r).abs()];
+        let u = if q < r {
+            rotation(angle_u) * Self::new(&[[1.0, 0.0], [0.0, -1.0]])
+        } else {
+            rotation(angle_u)
+        };
+
+        return (u, sigma, rotation(angle_v));
+    }
+
+    /// Checks if the two matrices are equal within the tolerance, each
+    /// element is compared separately
+    ///
+    /// # Parameters
+    ///
+    /// other: The matrix to compare with
+    ///
+    /// eps: The largest allowed difference of each element
+    pub fn approx_eq(&self, other: &Matrix, eps: f64) -> bool {
+        return self
+            .values
+            .iter()
+            .flatten()
+            .zip(other.values.iter().flatten())
+            .all(|(a, b)| return (a - b).abs() <= eps);
+    }
+
     /// Retrieves the data for the gpu
     pub fn get_data(&self) -> [f32; 4] {
         return [
@@ -654,6 +1230,32 @@ impl Mul<f64> for Matrix {
     }
 }
 
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::Matrix2<f64>> for Matrix {
+    fn from(value: nalgebra::Matrix2<f64>) -> Self {
+        return Self::new(&[
+            [value[(0, 0)], value[(0, 1)]],
+            [value[(1, 0)], value[(1, 1)]],
+        ]);
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<Matrix> for nalgebra::Matrix2<f64> {
+    fn from(value: Matrix) -> Self {
+        return Self::new(
+            value.values[0][0],
+            value.values[0][1],
+            value.values[1][0],
+            value.values[1][1],
+        );
+    }
+}
+
+/// An easing function mapping an interpolation parameter in [0, 1] to a new
+/// interpolation parameter, it should map 0 to 0 and 1 to 1
+pub type Blend = fn(f64) -> f64;
+
 /// A 2D transform which acts on Point types, including rotation, scaling and translation.
 ///
 /// The operation is y = r * (x - c) where
@@ -665,7 +1267,7 @@ impl Mul<f64> for Matrix {
 /// c: The center point
 ///
 /// r: The 2x2 center_transform matrix
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Transform2D {
     /// The transform to apply relative to the center
     pub center_transform: Matrix,
@@ -676,7 +1278,7 @@ pub struct Transform2D {
 impl Transform2D {
     /// Creates the identity operation
     pub fn identity() -> Self {
-        let center_transform = Matrix::new(&[[1.0, 0.0], [0.0, 1.0]]);
+        let center_transform = Matrix::identity();
         let center = Point::new(0.0, 0.0);
 
         return Self {
@@ -691,8 +1293,7 @@ impl Transform2D {
     ///
     /// angle: The angle to rotate
     pub fn rotation(angle: f64) -> Self {
-        let center_transform =
-            Matrix::new(&[[angle.cos(), -angle.sin()], [angle.sin(), angle.cos()]]);
+        let center_transform = Matrix::rotation(angle);
         let center = Point::new(0.0, 0.0);
 
         return Self {
@@ -709,8 +1310,7 @@ impl Transform2D {
     ///
     /// rotation_center: The center of the rotation
     pub fn rotation_at(angle: f64, rotation_center: &Point) -> Self {
-        let center_transform =
-            Matrix::new(&[[angle.cos(), -angle.sin()], [angle.sin(), angle.cos()]]);
+        let center_transform = Matrix::rotation(angle);
         let center = *rotation_center - center_transform.inv() * *rotation_center;
 
         return Self {
@@ -725,7 +1325,7 @@ impl Transform2D {
     ///
     /// scale: The ratio to scale x and y with
     pub fn scale(scale: &Point) -> Self {
-        let center_transform = Matrix::new(&[[scale.x, 0.0], [0.0, scale.y]]);
+        let center_transform = Matrix::scale(scale.x, scale.y);
         let center = Point::new(0.0, 0.0);
 
         return Self {
@@ -742,7 +1342,7 @@ impl Transform2D {
     ///
     /// center: The center of the scaling
     pub fn scale_at(scale: &Point, scale_center: &Point) -> Self {
-        let center_transform = Matrix::new(&[[scale.x, 0.0], [0.0, scale.y]]);
+        let center_transform = Matrix::scale(scale.x, scale.y);
         let center = *scale_center - center_transform.inv() * *scale_center;
 
         return Self {
@@ -757,7 +1357,7 @@ impl Transform2D {
     ///
     /// offset: The amount to translate
     pub fn translate(offset: &Point) -> Self {
-        let center_transform = Matrix::new(&[[1.0, 0.0], [0.0, 1.0]]);
+        let center_transform = Matrix::identity();
         let center = *offset;
 
         return Self {
@@ -766,6 +1366,140 @@ impl Transform2D {
         };
     }
 
+    /// Shear at origo
+    ///
+    /// # Parameters
+    ///
+    /// sx: The amount of x added per y
+    ///
+    /// sy: The amount of y added per x
+    pub fn shear(sx: f64, sy: f64) -> Self {
+        let center_transform = Matrix::new(&[[1.0, sx], [sy, 1.0]]);
+        let center = Point::new(0.0, 0.0);
+
+        return Self {
+            center_transform,
+            center,
+        };
+    }
+
+    /// Flips the x-axis at origo
+    pub fn reflect_x() -> Self {
+        let center_transform = Matrix::scale(-1.0, 1.0);
+        let center = Point::new(0.0, 0.0);
+
+        return Self {
+            center_transform,
+            center,
+        };
+    }
+
+    /// Flips the y-axis at origo
+    pub fn reflect_y() -> Self {
+        let center_transform = Matrix::scale(1.0, -1.0);
+        let center = Point::new(0.0, 0.0);
+
+        return Self {
+            center_transform,
+            center,
+        };
+    }
+
+    /// Creates a general transform from a matrix and a center
+    ///
+    /// # Parameters
+    ///
+    /// center_transform: The transform to apply relative to the center
+    ///
+    /// center: The center of the coordinate system
+    pub fn from_matrix(center_transform: Matrix, center: Point) -> Self {
+        return Self {
+            center_transform,
+            center,
+        };
+    }
+
+    /// Decomposes the transform into a scale followed by a rotation using the
+    /// QR decomposition of the center transform together with the
+    /// translation given by the center, any shear is discarded, returns
+    /// (scale_x, scale_y, rotation, translate_x, translate_y)
+    pub fn decompose(&self) -> (f64, f64, f64, f64, f64) {
+        let values = &self.center_transform.values;
+
+        // The first column is the rotated x-axis scaled by the x-scale
+        let scale_x = values[0][0].hypot(values[1][0]);
+        let rotation = values[1][0].atan2(values[0][0]);
+
+        // The determinant is the product of the scales
+        let scale_y = if scale_x > 0.0 {
+            self.center_transform.det() / scale_x
+        } else {
+            values[0][1].hypot(values[1][1])
+        };
+
+        return (scale_x, scale_y, rotation, self.center.x, self.center.y);
+    }
+
+    /// Interpolates between this transform and another transform, t=0 gives
+    /// this transform and t=1 gives the other transform. The matrices are
+    /// split into a rotation followed by a symmetric stretch using the
+    /// singular value decomposition, the rotation angle is interpolated
+    /// along the shortest way and the stretch and center are interpolated
+    /// linearly. If only one of the transforms is a reflection the matrices
+    /// are interpolated linearly instead
+    ///
+    /// # Parameters
+    ///
+    /// other: The transform to interpolate towards
+    ///
+    /// t: The interpolation parameter
+    pub fn lerp(&self, other: &Transform2D, t: f64) -> Self {
+        let center = self.center.lerp(&other.center, t);
+
+        // Split into rotation and stretch
+        let (rotation_self, stretch_self) = self.center_transform.polar();
+        let (rotation_other, stretch_other) = other.center_transform.polar();
+        let reflect = rotation_self.det() < 0.0;
+        if reflect != (rotation_other.det() < 0.0) {
+            return Self {
+                center_transform: self.center_transform * (1.0 - t) + other.center_transform * t,
+                center,
+            };
+        }
+
+        // Interpolate the rotation angle the shortest way
+        let angle_self = rotation_self.values[1][0].atan2(rotation_self.values[0][0]);
+        let angle_other = rotation_other.values[1][0].atan2(rotation_other.values[0][0]);
+        let difference = (angle_other - angle_self + PI).rem_euclid(2.0 * PI) - PI;
+        let angle = angle_self + difference * t;
+        let rotation = if reflect {
+            Matrix::rotation(angle) * Matrix::scale(1.0, -1.0)
+        } else {
+            Matrix::rotation(angle)
+        };
+
+        let stretch = stretch_self * (1.0 - t) + stretch_other * t;
+
+        return Self {
+            center_transform: rotation * stretch,
+            center,
+        };
+    }
+
+    /// Interpolates between this transform and another transform like lerp
+    /// after applying an easing function to the interpolation parameter
+    ///
+    /// # Parameters
+    ///
+    /// other: The transform to interpolate towards
+    ///
+    /// t: The interpolation parameter
+    ///
+    /// easing: The easing function to apply to t
+    pub fn lerp_eased(&self, other: &Transform2D, t: f64, easing: Blend) -> Self {
+        return self.lerp(other, easing(t));
+    }
+
     /// Retrieves the inverse transform
     pub fn inv(&self) -> Self {
         let center_transform = self.center_transform.inv();
@@ -777,6 +1511,43 @@ impl Transform2D {
         };
     }
 
+    /// Transforms all the points in place without allocating
+    ///
+    /// # Parameters
+    ///
+    /// points: The points to transform
+    pub fn apply_list_inplace(&self, points: &mut [Point]) {
+        points
+            .iter_mut()
+            .for_each(|point| *point = self.center_transform * (*point - self.center));
+    }
+
+    /// Transforms all the points and writes them to the destination without
+    /// allocating
+    ///
+    /// # Parameters
+    ///
+    /// src: The points to transform
+    ///
+    /// dst: The location to write the transformed points to
+    ///
+    /// # Panics
+    ///
+    /// In debug mode it panics if src and dst have different lengths
+    pub fn apply_list_into(&self, src: &[Point], dst: &mut [Point]) {
+        if cfg!(debug_assertions) && src.len() != dst.len() {
+            panic!(
+                "The source and destination have different lengths: {:?} and {:?}",
+                src.len(),
+                dst.len()
+            );
+        }
+
+        src.iter()
+            .zip(dst.iter_mut())
+            .for_each(|(point, out)| *out = self.center_transform * (*point - self.center));
+    }
+
     /// Retrieves the offset
     pub fn get_center(&self) -> &Point {
         return &self.center;
@@ -796,6 +1567,21 @@ impl Transform2D {
     pub fn get_data_center_transform(&self) -> [f32; 4] {
         return self.center_transform.get_data();
     }
+
+    /// Checks if the two transforms are equal within the tolerance, the
+    /// center transforms and centers are compared element-wise
+    ///
+    /// # Parameters
+    ///
+    /// other: The transform to compare with
+    ///
+    /// eps: The largest allowed difference of each element
+    pub fn approx_eq(&self, other: &Transform2D, eps: f64) -> bool {
+        return self
+            .center_transform
+            .approx_eq(&other.center_transform, eps)
+            && self.center.approx_eq(&other.center, eps);
+    }
 }
 
 impl Mul<Transform2D> for Transform2D {
@@ -890,8 +1676,39 @@ impl Mul<&Point> for &Transform2D {
     }
 }
 
+/// Converts the transform into the isometry y = r * x + t, only the rotation
+/// found by decompose is kept and any scale or shear is discarded
+#[cfg(feature = "nalgebra")]
+impl From<Transform2D> for nalgebra::Isometry2<f64> {
+    fn from(value: Transform2D) -> Self {
+        let (_, _, rotation, _, _) = value.decompose();
+        let translation = -(Matrix::rotation(rotation) * value.center);
+
+        return Self::new(
+            nalgebra::Vector2::new(translation.x, translation.y),
+            rotation,
+        );
+    }
+}
+
+/// Converts the isometry y = r * x + t into a transform with the center
+/// c = -r^T * t
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::Isometry2<f64>> for Transform2D {
+    fn from(value: nalgebra::Isometry2<f64>) -> Self {
+        let center_transform = Matrix::rotation(value.rotation.angle());
+        let translation = value.translation.vector;
+        let center = -(center_transform.transpose() * Point::new(translation.x, translation.y));
+
+        return Self {
+            center_transform,
+            center,
+        };
+    }
+}
+
 /// Describes a single 2D Gaussian
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Gaussian {
     /// The norm of the Gaussian
     pub norm: f64,
@@ -923,22 +1740,417 @@ impl Gaussian {
     ///
     /// # Parameters
     ///
+    /// offset: The offset to add to all points
+    ///
     /// points: The points to evaluate at
     pub fn evaluate(&self, offset: &Point, points: &[Point]) -> Vec<f64> {
-        let coeff = self.norm * self.matrix.det().sqrt() / PI;
+        let coeff = self.coefficient();
 
         return points
             .iter()
-            .map(|point| {
-                let rel_point = (point + offset) - self.mean;
-                let exponent = -rel_point * (self.matrix * rel_point);
-                return coeff * exponent.exp();
-            })
+            .map(|point| return self.evaluate_with_coefficient(coeff, &(point + offset)))
             .collect();
     }
 
+    /// Evaluates the Gaussian in a single location without allocating
+    ///
+    /// # Parameters
+    ///
+    /// offset: The offset to add to the point
+    ///
+    /// point: The point to evaluate at
+    #[inline(always)]
+    pub fn evaluate_single(&self, offset: &Point, point: &Point) -> f64 {
+        return self.evaluate_with_coefficient(self.coefficient(), &(point + offset));
+    }
+
+    /// Calculates the value of the Gaussian at its mean
+    fn coefficient(&self) -> f64 {
+        return self.norm * self.matrix.det().sqrt() / PI;
+    }
+
+    /// Evaluates the Gaussian in a single location given the precalculated
+    /// value at its mean
+    ///
+    /// # Parameters
+    ///
+    /// coeff: The value at the mean as given by coefficient
+    ///
+    /// point: The point to evaluate at
+    #[inline(always)]
+    fn evaluate_with_coefficient(&self, coeff: f64, point: &Point) -> f64 {
+        let rel_point = *point - self.mean;
+        let exponent = -rel_point * (self.matrix * rel_point);
+
+        return coeff * exponent.exp();
+    }
+
     /// Retrieves the covariance matrix
     pub fn get_covariance(&self) -> Matrix {
         return (self.matrix * 2.0).inv();
     }
+
+    /// Draws a random point from the normalized distribution of this Gaussian
+    ///
+    /// # Parameters
+    ///
+    /// rng: The random number generator to use
+    pub fn sample(&self, rng: &mut impl Rng) -> Point {
+        // Draw two independent standard normal values using the Box-Muller transform
+        let radius = (-2.0 * (1.0 - rng.gen::<f64>()).ln()).sqrt();
+        let angle = 2.0 * PI * rng.gen::<f64>();
+        let standard = Point::new(radius * angle.cos(), radius * angle.sin());
+
+        // Transform to the covariance of the Gaussian
+        let transform = self
+            .get_covariance()
+            .cholesky()
+            .expect("The covariance of a Gaussian must be positive semi-definite");
+
+        return self.mean + transform * standard;
+    }
+
+    /// Calculates the convolution of the two Gaussians which is a Gaussian
+    /// with the sum of the means, the sum of the covariances and the product
+    /// of the norms
+    ///
+    /// # Parameters
+    ///
+    /// other: The Gaussian to convolve with
+    pub fn convolve(&self, other: &Gaussian) -> Gaussian {
+        return Gaussian::new(
+            self.norm * other.norm,
+            self.mean + other.mean,
+            self.get_covariance() + other.get_covariance(),
+        );
+    }
+
+    /// Calculates the differential entropy of the normalized distribution of
+    /// this Gaussian, ln(2 pi e sqrt(det(cov))), the norm is ignored
+    pub fn entropy(&self) -> f64 {
+        return (2.0 * PI * E * self.get_covariance().det().sqrt()).ln();
+    }
+
+    /// Calculates the Kullback-Leibler divergence from the other Gaussian to
+    /// this Gaussian, KL(self || other), using the normalized distributions,
+    /// it is 0 if the two distributions are identical
+    ///
+    /// # Parameters
+    ///
+    /// other: The Gaussian to use as the reference distribution
+    pub fn kl_divergence(&self, other: &Gaussian) -> f64 {
+        let cov = self.get_covariance();
+        let other_inv_cov = other.matrix * 2.0;
+        let product = other_inv_cov * cov;
+        let trace = product.values[0][0] + product.values[1][1];
+        let difference = other.mean - self.mean;
+        let mahalanobis = difference * (other_inv_cov * difference);
+
+        return 0.5 * (trace + mahalanobis - 2.0 + (other.get_covariance().det() / cov.det()).ln());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The tolerance used when comparing floating point results
+    const EPS: f64 = 1e-9;
+
+    /// Asserts that two transforms are equal within the tolerance, on failure
+    /// the transforms and their element-wise difference are shown
+    ///
+    /// # Parameters
+    ///
+    /// a: The first transform
+    ///
+    /// b: The second transform
+    ///
+    /// eps: The largest allowed difference of each element
+    macro_rules! assert_transform_approx_eq {
+        ($a:expr, $b:expr, $eps:expr) => {{
+            let a: &Transform2D = &$a;
+            let b: &Transform2D = &$b;
+            let eps: f64 = $eps;
+            if !a.approx_eq(b, eps) {
+                panic!(
+                    "Transforms are not approximately equal (eps = {}):\n  left: {:?}\n right: {:?}\n  diff: center_transform = {:?}, center = {:?}",
+                    eps,
+                    a,
+                    b,
+                    a.center_transform - b.center_transform,
+                    a.center - b.center,
+                );
+            }
+        }};
+    }
+
+    /// A general invertible matrix which is neither symmetric nor orthogonal
+    fn test_matrix() -> Matrix {
+        return Matrix::new(&[[1.5, -0.3], [0.7, 2.0]]);
+    }
+
+    #[test]
+    fn index_hash_map_has_no_collisions() {
+        let indices = (-5..5)
+            .flat_map(|x| return (-5..5).map(move |y| return Index::new(x, y)))
+            .collect::<Vec<Index>>();
+        let map = indices
+            .iter()
+            .enumerate()
+            .map(|(id, index)| return (*index, id))
+            .collect::<std::collections::HashMap<Index, usize>>();
+
+        assert_eq!(map.len(), 100);
+        indices.iter().enumerate().for_each(|(id, index)| {
+            assert_eq!(map.get(index), Some(&id));
+        });
+    }
+
+    #[test]
+    fn index_ordering_is_lexicographic() {
+        assert!(Index::new(0, 5) < Index::new(1, -5));
+        assert!(Index::new(1, -5) < Index::new(1, 0));
+        assert_eq!(
+            Index::new(2, 3).cmp(&Index::new(2, 3)),
+            std::cmp::Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn project_onto_segment_before_start() {
+        let a = Point::new(1.0, 1.0);
+        let b = Point::new(4.0, 1.0);
+        let p = Point::new(-1.0, 3.0);
+        assert!(p.project_onto_segment(&a, &b).approx_eq(&a, EPS));
+        assert!((p.distance_to_segment(&a, &b) - 8.0_f64.sqrt()).abs() < EPS);
+    }
+
+    #[test]
+    fn project_onto_segment_after_end() {
+        let a = Point::new(1.0, 1.0);
+        let b = Point::new(4.0, 1.0);
+        let p = Point::new(7.0, -3.0);
+        assert!(p.project_onto_segment(&a, &b).approx_eq(&b, EPS));
+        assert!((p.distance_to_segment(&a, &b) - 5.0).abs() < EPS);
+    }
+
+    #[test]
+    fn project_onto_segment_interior() {
+        let a = Point::new(0.0, 0.0);
+        let b = Point::new(2.0, 2.0);
+        let p = Point::new(0.0, 2.0);
+        assert!(p
+            .project_onto_segment(&a, &b)
+            .approx_eq(&Point::new(1.0, 1.0), EPS));
+        assert!((p.distance_to_segment(&a, &b) - 2.0_f64.sqrt()).abs() < EPS);
+    }
+
+    #[test]
+    fn project_onto_degenerate_segment() {
+        let a = Point::new(1.0, 2.0);
+        let p = Point::new(4.0, 6.0);
+        assert!(p.project_onto_segment(&a, &a).approx_eq(&a, EPS));
+        assert!((p.distance_to_segment(&a, &a) - 5.0).abs() < EPS);
+    }
+
+    #[test]
+    fn matrix_pow_zero_is_identity() {
+        assert!(test_matrix().pow(0).approx_eq(&Matrix::identity(), EPS));
+    }
+
+    #[test]
+    fn matrix_pow_one_is_self() {
+        assert!(test_matrix().pow(1).approx_eq(&test_matrix(), EPS));
+    }
+
+    #[test]
+    fn matrix_pow_two_is_square() {
+        let m = test_matrix();
+        assert!(m.pow(2).approx_eq(&(m * m), EPS));
+    }
+
+    #[test]
+    fn matrix_pow_negative_is_inverse_power() {
+        let m = test_matrix();
+        assert!(m.pow(-3).approx_eq(&(m.inv() * m.inv() * m.inv()), EPS));
+        assert!((m.pow(5) * m.pow(-5)).approx_eq(&Matrix::identity(), EPS));
+    }
+
+    #[test]
+    fn matrix_svd_reconstructs_matrix() {
+        [
+            test_matrix(),
+            Matrix::identity(),
+            Matrix::rotation(0.4) * Matrix::scale(2.0, -0.5),
+            Matrix::new(&[[0.0, 1.0], [0.0, 0.0]]),
+            Matrix::new(&[[2.0, 2.0], [1.0, 1.0]]),
+            Matrix::new(&[[0.0, 0.0], [0.0, 0.0]]),
+        ]
+        .into_iter()
+        .for_each(|m| {
+            let (u, sigma, vt) = m.svd();
+
+            // U and Vt are orthogonal and the singular values are sorted
+            assert!((u * u.transpose()).approx_eq(&Matrix::identity(), EPS));
+            assert!((vt * vt.transpose()).approx_eq(&Matrix::identity(), EPS));
+            assert!(sigma[0] >= sigma[1] && sigma[1] >= 0.0, "{:?}", sigma);
+
+            let reconstructed = u * Matrix::scale(sigma[0], sigma[1]) * vt;
+            assert!(
+                reconstructed.approx_eq(&m, EPS),
+                "{:?} != {:?}",
+                reconstructed,
+                m
+            );
+        });
+    }
+
+    /// Two transforms with different rotations, uniform scales and centers
+    fn test_transforms() -> (Transform2D, Transform2D) {
+        return (
+            Transform2D::from_matrix(Matrix::rotation(0.2) * 2.0, Point::new(1.0, 2.0)),
+            Transform2D::from_matrix(Matrix::rotation(1.0) * 3.0, Point::new(5.0, -2.0)),
+        );
+    }
+
+    #[test]
+    fn transform_lerp_endpoints() {
+        let (a, b) = test_transforms();
+        assert_transform_approx_eq!(a.lerp(&b, 0.0), a, EPS);
+        assert_transform_approx_eq!(a.lerp(&b, 1.0), b, EPS);
+    }
+
+    #[test]
+    fn transform_lerp_midpoint() {
+        // The rotation, scale and center are each halfway
+        let (a, b) = test_transforms();
+        let expected = Transform2D::from_matrix(Matrix::rotation(0.6) * 2.5, Point::new(3.0, 0.0));
+        assert_transform_approx_eq!(a.lerp(&b, 0.5), expected, EPS);
+    }
+
+    #[test]
+    fn transform_lerp_rotates_the_shortest_way() {
+        let a = Transform2D::rotation(PI - 0.1);
+        let b = Transform2D::rotation(-PI + 0.1);
+        assert_transform_approx_eq!(a.lerp(&b, 0.5), Transform2D::rotation(PI), EPS);
+    }
+
+    #[test]
+    fn transform_lerp_eased_applies_easing() {
+        let (a, b) = test_transforms();
+        let eased = a.lerp_eased(&b, 0.5, |t| return t * t);
+        assert_transform_approx_eq!(eased, a.lerp(&b, 0.25), EPS);
+    }
+
+    #[test]
+    fn transform_decompose_round_trip() {
+        [
+            (1.0, 1.0, 0.0, 0.0, 0.0),
+            (2.0, 0.5, 0.3, 1.5, -2.0),
+            (0.7, 3.0, -2.5, -4.0, 0.25),
+            (1.2, -0.8, 1.9, 0.0, 3.0),
+        ]
+        .into_iter()
+        .for_each(|(scale_x, scale_y, rotation, translate_x, translate_y)| {
+            let transform = Transform2D::from_matrix(
+                Matrix::rotation(rotation) * Matrix::scale(scale_x, scale_y),
+                Point::new(translate_x, translate_y),
+            );
+
+            // Decompose and build the transform again from the parts
+            let (scale_x, scale_y, rotation, translate_x, translate_y) = transform.decompose();
+            let reconstructed = Transform2D::from_matrix(
+                Matrix::rotation(rotation) * Matrix::scale(scale_x, scale_y),
+                Point::new(translate_x, translate_y),
+            );
+
+            assert_transform_approx_eq!(reconstructed, transform, EPS);
+        });
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn nalgebra_matrix_round_trip() {
+        let matrix = test_matrix();
+        let converted = nalgebra::Matrix2::from(matrix);
+        assert_eq!(converted, nalgebra::Matrix2::new(1.5, -0.3, 0.7, 2.0));
+        assert!(Matrix::from(converted).approx_eq(&matrix, EPS));
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn nalgebra_isometry_round_trip() {
+        let point = Point::new(-1.5, 0.75);
+
+        // From a rigid transform through the isometry and back
+        let transform = Transform2D::from_matrix(Matrix::rotation(0.8), Point::new(2.0, -3.0));
+        let isometry = nalgebra::Isometry2::from(transform);
+        let mapped = isometry * nalgebra::Point2::new(point.x, point.y);
+        assert!(Point::new(mapped.x, mapped.y).approx_eq(&(&transform * &point), EPS));
+        assert_transform_approx_eq!(Transform2D::from(isometry), transform, EPS);
+
+        // From an isometry through the transform and back
+        let isometry = nalgebra::Isometry2::new(nalgebra::Vector2::new(-4.0, 1.5), -2.2);
+        let reconstructed = nalgebra::Isometry2::from(Transform2D::from(isometry));
+        assert!((reconstructed.translation.vector - isometry.translation.vector).norm() < EPS);
+        assert!((reconstructed.rotation.angle() - isometry.rotation.angle()).abs() < EPS);
+    }
+
+    #[test]
+    fn gaussian_entropy_grows_with_variance() {
+        let unit = Gaussian::new(1.0, Point::new(0.0, 0.0), Matrix::identity());
+        assert!((unit.entropy() - (2.0 * PI * E).ln()).abs() < EPS);
+
+        let entropies = [0.1, 0.5, 1.0, 2.0, 10.0]
+            .into_iter()
+            .map(|variance| {
+                return Gaussian::new(
+                    1.0,
+                    Point::new(0.0, 0.0),
+                    Matrix::new(&[[variance, 0.2 * variance], [0.2 * variance, variance]]),
+                )
+                .entropy();
+            })
+            .collect::<Vec<f64>>();
+        entropies.windows(2).for_each(|pair| {
+            assert!(pair[0] < pair[1], "{:?}", entropies);
+        });
+    }
+
+    #[test]
+    fn gaussian_kl_divergence() {
+        let a = Gaussian::new(1.0, Point::new(0.0, 0.0), Matrix::identity());
+        let b = Gaussian::new(3.0, Point::new(1.0, 0.0), Matrix::identity() * 2.0);
+
+        // Identical distributions have no divergence regardless of the norm
+        assert!(a.kl_divergence(&a).abs() < EPS);
+        assert!(b.kl_divergence(&Gaussian { norm: 1.0, ..b }).abs() < EPS);
+
+        // 0.5 (tr(S_b^-1 S_a) + m^T S_b^-1 m - 2 + ln(det(S_b) / det(S_a)))
+        let expected = 0.5 * (1.0 + 0.5 - 2.0 + 4.0f64.ln());
+        assert!((a.kl_divergence(&b) - expected).abs() < EPS);
+        assert!(a.kl_divergence(&b) >= 0.0 && b.kl_divergence(&a) >= 0.0);
+    }
+
+    #[test]
+    fn gaussian_convolution_of_isotropic_gaussians() {
+        let sigma2 = 1.5;
+        let a = Gaussian::new(2.0, Point::new(1.0, -2.0), Matrix::identity() * sigma2);
+        let b = Gaussian::new(3.0, Point::new(0.5, 4.0), Matrix::identity() * sigma2);
+        let c = a.convolve(&b);
+
+        // The variances add up to twice the variance
+        let cov = c.get_covariance();
+        let expected = Matrix::identity() * (2.0 * sigma2);
+        (0..2).for_each(|i| {
+            (0..2).for_each(|j| {
+                assert!((cov.values[i][j] - expected.values[i][j]).abs() < EPS);
+            });
+        });
+
+        assert!((c.mean.x - 1.5).abs() < EPS);
+        assert!((c.mean.y - 2.0).abs() < EPS);
+        assert!((c.norm - 6.0).abs() < EPS);
+    }
 }